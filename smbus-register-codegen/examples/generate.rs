@@ -0,0 +1,8 @@
+//! Regenerate a register module, e.g. the test fixture:
+//! `cargo run --example generate fixtures/thermometer.toml fixtures/thermometer.rs`
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let (spec, out) = (args.next().unwrap(), args.next().unwrap());
+    smbus_register_codegen::generate_file(spec, out).unwrap();
+}
@@ -0,0 +1,129 @@
+#[derive(Debug, Clone, ::smbus_request_parser::SmbusDevice)]
+pub struct Thermometer {
+    /// Conversion rate and alert polarity.
+    #[smbus(reg = 0x01, byte)]
+    pub config: u8,
+    /// Last conversion result in 1/256 degree Celsius.
+    #[smbus(reg = 0x02, word, read)]
+    pub temperature: u16,
+    #[smbus(reg = 0x03, word)]
+    pub alert_limit: u16,
+    #[smbus(reg = 0x04, byte, write)]
+    pub command: u8,
+    #[smbus(reg = 0x10, block, read)]
+    pub serial: ::smbus_request_parser::register_map::Block<8>,
+    #[smbus(reg = 0x11, block)]
+    pub label: ::smbus_request_parser::register_map::Block<16>,
+    /// Latched alert flags, cleared by writing ones.
+    #[smbus(reg = 0x05, byte, write_one_to_clear)]
+    pub alarms: u8,
+    #[smbus(reg = 0x06, word, clear_on_read)]
+    pub faults: u16,
+    #[smbus(reg = 0x07, word, write_once = calibration_locked)]
+    pub calibration: u16,
+    /// Set once `calibration` has been written.
+    pub calibration_locked: bool,
+}
+impl Thermometer {
+    pub const CONFIG: u8 = 0x01;
+    pub const TEMPERATURE: u8 = 0x02;
+    pub const ALERT_LIMIT: u8 = 0x03;
+    pub const COMMAND: u8 = 0x04;
+    pub const SERIAL: u8 = 0x10;
+    pub const LABEL: u8 = 0x11;
+    pub const ALARMS: u8 = 0x05;
+    pub const FAULTS: u8 = 0x06;
+    pub const CALIBRATION: u8 = 0x07;
+}
+impl Default for Thermometer {
+    fn default() -> Self {
+        Self {
+            config: 0x10,
+            temperature: 0,
+            alert_limit: 0x5000,
+            command: 0,
+            serial: ::smbus_request_parser::register_map::Block::new(
+                    &[84, 72, 45, 48, 48, 48, 49],
+                )
+                .unwrap_or_default(),
+            label: ::smbus_request_parser::register_map::Block::new(&[])
+                .unwrap_or_default(),
+            alarms: 0,
+            faults: 0,
+            calibration: 0,
+            calibration_locked: false,
+        }
+    }
+}
+#[derive(Debug)]
+pub struct ThermometerClient<M> {
+    master: M,
+}
+impl<M: ::smbus_request_parser::client::SMBusMaster> ThermometerClient<M> {
+    pub fn new(master: M) -> Self {
+        Self { master }
+    }
+    pub fn release(self) -> M {
+        self.master
+    }
+    pub fn read_config(&mut self) -> Result<u8, M::Error> {
+        self.master.read_byte_data(Thermometer::CONFIG)
+    }
+    pub fn write_config(&mut self, value: u8) -> Result<(), M::Error> {
+        self.master.write_byte_data(Thermometer::CONFIG, value)
+    }
+    pub fn read_temperature(&mut self) -> Result<u16, M::Error> {
+        self.master.read_word_data(Thermometer::TEMPERATURE)
+    }
+    pub fn read_alert_limit(&mut self) -> Result<u16, M::Error> {
+        self.master.read_word_data(Thermometer::ALERT_LIMIT)
+    }
+    pub fn write_alert_limit(&mut self, value: u16) -> Result<(), M::Error> {
+        self.master.write_word_data(Thermometer::ALERT_LIMIT, value)
+    }
+    pub fn write_command(&mut self, value: u8) -> Result<(), M::Error> {
+        self.master.write_byte_data(Thermometer::COMMAND, value)
+    }
+    pub fn read_serial(
+        &mut self,
+    ) -> Result<::smbus_request_parser::register_map::Block<8>, M::Error> {
+        let mut buffer = [0; 8];
+        let count = self.master.read_block_data(Thermometer::SERIAL, &mut buffer)?;
+        Ok(
+            ::smbus_request_parser::register_map::Block::new(
+                    &buffer[..count.min(buffer.len())],
+                )
+                .unwrap_or_default(),
+        )
+    }
+    pub fn read_label(
+        &mut self,
+    ) -> Result<::smbus_request_parser::register_map::Block<16>, M::Error> {
+        let mut buffer = [0; 16];
+        let count = self.master.read_block_data(Thermometer::LABEL, &mut buffer)?;
+        Ok(
+            ::smbus_request_parser::register_map::Block::new(
+                    &buffer[..count.min(buffer.len())],
+                )
+                .unwrap_or_default(),
+        )
+    }
+    pub fn write_label(&mut self, value: &[u8]) -> Result<(), M::Error> {
+        self.master.write_block_data(Thermometer::LABEL, value)
+    }
+    pub fn read_alarms(&mut self) -> Result<u8, M::Error> {
+        self.master.read_byte_data(Thermometer::ALARMS)
+    }
+    pub fn write_alarms(&mut self, value: u8) -> Result<(), M::Error> {
+        self.master.write_byte_data(Thermometer::ALARMS, value)
+    }
+    pub fn read_faults(&mut self) -> Result<u16, M::Error> {
+        self.master.read_word_data(Thermometer::FAULTS)
+    }
+    pub fn read_calibration(&mut self) -> Result<u16, M::Error> {
+        self.master.read_word_data(Thermometer::CALIBRATION)
+    }
+    pub fn write_calibration(&mut self, value: u16) -> Result<(), M::Error> {
+        self.master.write_word_data(Thermometer::CALIBRATION, value)
+    }
+}
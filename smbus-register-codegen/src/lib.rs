@@ -0,0 +1,406 @@
+//! Generate a register struct with a `CommandHandler` implementation and a
+//! matching host-side typed client from a TOML register description.
+//!
+//! ```toml
+//! device = "Thermometer"
+//!
+//! [[register]]
+//! name = "config"
+//! code = 0x01
+//! width = "byte"
+//! reset = 0x10
+//!
+//! [[register]]
+//! name = "temperature"
+//! code = 0x02
+//! width = "word"
+//! access = "read-only"
+//!
+//! [[register]]
+//! name = "serial"
+//! code = 0x10
+//! width = "block"
+//! capacity = 8
+//! access = "read-only"
+//! reset = "TH-0001"
+//! ```
+//!
+//! `access` is one of `read-only`, `write-only`, `read-write` (the default),
+//! `write-one-to-clear`, `clear-on-read` or `write-once`. A write-once register
+//! gets a `<name>_locked` field which the application clears to unlock it.
+//!
+//! From `build.rs`:
+//!
+//! ```no_run
+//! use std::{env, path::PathBuf};
+//!
+//! let out = PathBuf::from(env::var("OUT_DIR").unwrap()).join("registers.rs");
+//! smbus_register_codegen::generate_file("registers.toml", &out).unwrap();
+//! println!("cargo:rerun-if-changed=registers.toml");
+//! ```
+//!
+//! and `include!(concat!(env!("OUT_DIR"), "/registers.rs"));` in the firmware
+//! and the host tooling. The device side needs the `derive` feature of
+//! `smbus-request-parser`; the client is generic over
+//! `smbus_request_parser::client::SMBusMaster`.
+
+use std::{collections::BTreeSet, fmt, fs, path::Path};
+
+use proc_macro2::{Ident, Literal, Span, TokenStream};
+use quote::{format_ident, quote};
+use serde::Deserialize;
+
+#[cfg(test)]
+mod test;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeviceSpec {
+    pub device: String,
+    #[serde(default, rename = "register")]
+    pub registers: Vec<RegisterSpec>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RegisterSpec {
+    pub name: String,
+    pub code: u8,
+    pub width: Width,
+    #[serde(default)]
+    pub access: Access,
+    /// Maximum length of a block register.
+    pub capacity: Option<u8>,
+    pub reset: Option<Reset>,
+    pub doc: Option<String>,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Width {
+    Byte,
+    Word,
+    Block,
+}
+
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Access {
+    ReadOnly,
+    WriteOnly,
+    #[default]
+    ReadWrite,
+    WriteOneToClear,
+    ClearOnRead,
+    /// Generates a `<name>_locked` flag next to the register.
+    WriteOnce,
+}
+
+impl Access {
+    fn is_readable(self) -> bool {
+        self != Access::WriteOnly
+    }
+
+    fn is_writable(self) -> bool {
+        !matches!(self, Access::ReadOnly | Access::ClearOnRead)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Reset {
+    Value(u64),
+    Bytes(Vec<u8>),
+    Text(String),
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(std::io::Error),
+    Toml(toml::de::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Toml(e) => write!(f, "{}", e),
+            Error::Invalid(message) => write!(f, "invalid register description: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<toml::de::Error> for Error {
+    fn from(e: toml::de::Error) -> Self {
+        Error::Toml(e)
+    }
+}
+
+/// Read the description at `spec` and write the generated code to `out`.
+pub fn generate_file(spec: impl AsRef<Path>, out: impl AsRef<Path>) -> Result<(), Error> {
+    let spec = fs::read_to_string(spec)?;
+    fs::write(out, generate(&spec)?)?;
+    Ok(())
+}
+
+/// Generate formatted Rust source from a TOML register description.
+pub fn generate(spec: &str) -> Result<String, Error> {
+    let spec: DeviceSpec = toml::from_str(spec)?;
+    let tokens = generate_tokens(&spec)?;
+    let file = syn::parse2(tokens).map_err(|e| Error::Invalid(e.to_string()))?;
+    Ok(prettyplease::unparse(&file))
+}
+
+pub fn generate_tokens(spec: &DeviceSpec) -> Result<TokenStream, Error> {
+    validate(spec)?;
+
+    let device = ident(&spec.device)?;
+    let client = format_ident!("{}Client", device);
+
+    let mut fields = Vec::new();
+    let mut resets = Vec::new();
+    let mut codes = Vec::new();
+    let mut methods = Vec::new();
+
+    for register in &spec.registers {
+        let name = ident(&register.name)?;
+        let code = hex(register.code as u64);
+        let constant = format_ident!("{}", register.name.to_uppercase());
+        let doc = register
+            .doc
+            .as_ref()
+            .map(|doc| {
+                let doc = format!(" {}", doc);
+                quote!(#[doc = #doc])
+            })
+            .unwrap_or_default();
+        let width = match register.width {
+            Width::Byte => quote!(byte),
+            Width::Word => quote!(word),
+            Width::Block => quote!(block),
+        };
+        let locked = format_ident!("{}_locked", register.name);
+        let access = match register.access {
+            Access::ReadOnly => quote!(, read),
+            Access::WriteOnly => quote!(, write),
+            Access::ReadWrite => quote!(),
+            Access::WriteOneToClear => quote!(, write_one_to_clear),
+            Access::ClearOnRead => quote!(, clear_on_read),
+            Access::WriteOnce => quote!(, write_once = #locked),
+        };
+        let ty = field_type(register);
+        fields.push(quote! {
+            #doc
+            #[smbus(reg = #code, #width #access)]
+            pub #name: #ty,
+        });
+        let reset = reset_value(register)?;
+        resets.push(quote!(#name: #reset,));
+        if register.access == Access::WriteOnce {
+            let doc = format!(" Set once `{}` has been written.", register.name);
+            fields.push(quote! {
+                #[doc = #doc]
+                pub #locked: bool,
+            });
+            resets.push(quote!(#locked: false,));
+        }
+        codes.push(quote!(pub const #constant: u8 = #code;));
+
+        let read = format_ident!("read_{}", register.name);
+        let write = format_ident!("write_{}", register.name);
+        match register.width {
+            Width::Byte | Width::Word => {
+                let (read_fn, write_fn) = match register.width {
+                    Width::Byte => (quote!(read_byte_data), quote!(write_byte_data)),
+                    _ => (quote!(read_word_data), quote!(write_word_data)),
+                };
+                if register.access.is_readable() {
+                    methods.push(quote! {
+                        pub fn #read(&mut self) -> Result<#ty, M::Error> {
+                            self.master.#read_fn(#device::#constant)
+                        }
+                    });
+                }
+                if register.access.is_writable() {
+                    methods.push(quote! {
+                        pub fn #write(&mut self, value: #ty) -> Result<(), M::Error> {
+                            self.master.#write_fn(#device::#constant, value)
+                        }
+                    });
+                }
+            }
+            Width::Block => {
+                let capacity = Literal::u8_unsuffixed(register.capacity.unwrap_or_default());
+                if register.access.is_readable() {
+                    methods.push(quote! {
+                        pub fn #read(&mut self) -> Result<#ty, M::Error> {
+                            let mut buffer = [0; #capacity];
+                            let count = self.master.read_block_data(#device::#constant, &mut buffer)?;
+                            Ok(::smbus_request_parser::register_map::Block::new(&buffer[..count.min(buffer.len())])
+                                .unwrap_or_default())
+                        }
+                    });
+                }
+                if register.access.is_writable() {
+                    methods.push(quote! {
+                        pub fn #write(&mut self, value: &[u8]) -> Result<(), M::Error> {
+                            self.master.write_block_data(#device::#constant, value)
+                        }
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(quote! {
+        #[derive(Debug, Clone, ::smbus_request_parser::SmbusDevice)]
+        pub struct #device {
+            #(#fields)*
+        }
+
+        impl #device {
+            #(#codes)*
+        }
+
+        impl Default for #device {
+            fn default() -> Self {
+                Self {
+                    #(#resets)*
+                }
+            }
+        }
+
+        #[derive(Debug)]
+        pub struct #client<M> {
+            master: M,
+        }
+
+        impl<M: ::smbus_request_parser::client::SMBusMaster> #client<M> {
+            pub fn new(master: M) -> Self {
+                Self { master }
+            }
+
+            pub fn release(self) -> M {
+                self.master
+            }
+
+            #(#methods)*
+        }
+    })
+}
+
+fn validate(spec: &DeviceSpec) -> Result<(), Error> {
+    let mut names = BTreeSet::new();
+    let mut readable = BTreeSet::new();
+    let mut writable = BTreeSet::new();
+    for register in &spec.registers {
+        let name = &register.name;
+        if !names.insert(name.as_str()) {
+            return Err(Error::Invalid(format!(
+                "duplicate register name `{}`",
+                name
+            )));
+        }
+        if (register.access.is_readable() && !readable.insert(register.code))
+            || (register.access.is_writable() && !writable.insert(register.code))
+        {
+            return Err(Error::Invalid(format!(
+                "register `{}` reuses code {:#04x}",
+                name, register.code
+            )));
+        }
+        match (register.width, register.capacity) {
+            (Width::Block, None) => {
+                return Err(Error::Invalid(format!(
+                    "block register `{}` needs a capacity",
+                    name
+                )))
+            }
+            (Width::Byte | Width::Word, Some(_)) => {
+                return Err(Error::Invalid(format!(
+                    "only block registers have a capacity, `{}` is not a block",
+                    name
+                )))
+            }
+            _ => {}
+        }
+        if register.width == Width::Block
+            && matches!(
+                register.access,
+                Access::WriteOneToClear | Access::ClearOnRead
+            )
+        {
+            return Err(Error::Invalid(format!(
+                "only byte and word registers can be cleared, `{}` is a block",
+                name
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn ident(name: &str) -> Result<Ident, Error> {
+    syn::parse_str::<Ident>(name)
+        .map_err(|_| Error::Invalid(format!("`{}` is not a valid identifier", name)))
+}
+
+fn hex(value: u64) -> TokenStream {
+    let literal = syn::LitInt::new(&format!("{:#04x}", value), Span::call_site());
+    quote!(#literal)
+}
+
+fn field_type(register: &RegisterSpec) -> TokenStream {
+    match register.width {
+        Width::Byte => quote!(u8),
+        Width::Word => quote!(u16),
+        Width::Block => {
+            let capacity = Literal::u8_unsuffixed(register.capacity.unwrap_or_default());
+            quote!(::smbus_request_parser::register_map::Block<#capacity>)
+        }
+    }
+}
+
+fn reset_value(register: &RegisterSpec) -> Result<TokenStream, Error> {
+    let invalid = || {
+        Error::Invalid(format!(
+            "reset value of `{}` does not fit the register",
+            register.name
+        ))
+    };
+    match (register.width, &register.reset) {
+        (Width::Byte, None) | (Width::Word, None) => Ok(quote!(0)),
+        (Width::Byte, Some(Reset::Value(value))) => {
+            let value = u8::try_from(*value).map_err(|_| invalid())?;
+            Ok(hex(value as u64))
+        }
+        (Width::Word, Some(Reset::Value(value))) => {
+            let value = u16::try_from(*value).map_err(|_| invalid())?;
+            Ok(hex(value as u64))
+        }
+        (Width::Block, reset) => {
+            let bytes = match reset {
+                None => Vec::new(),
+                Some(Reset::Bytes(bytes)) => bytes.clone(),
+                Some(Reset::Text(text)) => text.as_bytes().to_vec(),
+                Some(Reset::Value(_)) => return Err(invalid()),
+            };
+            if bytes.len() > register.capacity.unwrap_or_default() as usize {
+                return Err(invalid());
+            }
+            let bytes = bytes.into_iter().map(Literal::u8_unsuffixed);
+            Ok(quote! {
+                ::smbus_request_parser::register_map::Block::new(&[#(#bytes),*]).unwrap_or_default()
+            })
+        }
+        _ => Err(invalid()),
+    }
+}
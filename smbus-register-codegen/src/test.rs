@@ -0,0 +1,128 @@
+use crate::*;
+
+mod thermometer {
+    include!("../fixtures/thermometer.rs");
+}
+
+use smbus_request_parser::{client::Loopback, SMBusProtocolError};
+use thermometer::{Thermometer, ThermometerClient};
+
+#[test]
+fn test_fixture_is_up_to_date() {
+    let generated = generate(include_str!("../fixtures/thermometer.toml")).unwrap();
+    assert_eq!(include_str!("../fixtures/thermometer.rs"), generated);
+}
+
+#[test]
+fn test_reset_values() {
+    let device = Thermometer::default();
+    assert_eq!(0x10, device.config);
+    assert_eq!(0, device.temperature);
+    assert_eq!(0x5000, device.alert_limit);
+    assert_eq!(b"TH-0001", device.serial.as_slice());
+    assert_eq!(b"", device.label.as_slice());
+}
+
+#[test]
+fn test_client_talks_to_device() {
+    let mut device = Thermometer {
+        temperature: 0x1980,
+        ..Default::default()
+    };
+    let mut client = ThermometerClient::new(Loopback::new(&mut device));
+
+    assert_eq!(Ok(0x10), client.read_config());
+    client.write_config(0x21).unwrap();
+    assert_eq!(Ok(0x21), client.read_config());
+    assert_eq!(Ok(0x1980), client.read_temperature());
+    client.write_alert_limit(0x6000).unwrap();
+    assert_eq!(Ok(0x6000), client.read_alert_limit());
+    client.write_command(0xaa).unwrap();
+    assert_eq!(b"TH-0001", client.read_serial().unwrap().as_slice());
+    client.write_label(b"boiler").unwrap();
+    assert_eq!(b"boiler", client.read_label().unwrap().as_slice());
+    client.release();
+
+    assert_eq!(0x6000, device.alert_limit);
+    assert_eq!(0xaa, device.command);
+}
+
+#[test]
+fn test_access_kinds() {
+    let mut device = Thermometer {
+        alarms: 0b11,
+        faults: 0x0101,
+        ..Default::default()
+    };
+    let mut client = ThermometerClient::new(Loopback::new(&mut device));
+
+    client.write_alarms(0b01).unwrap();
+    assert_eq!(Ok(0b10), client.read_alarms());
+    assert_eq!(Ok(0x0101), client.read_faults());
+    assert_eq!(Ok(0), client.read_faults());
+    client.write_calibration(0x1234).unwrap();
+    assert_eq!(
+        Err(SMBusProtocolError::RegisterLocked(Thermometer::CALIBRATION)),
+        client.write_calibration(0x4321)
+    );
+    assert_eq!(Ok(0x1234), client.read_calibration());
+    client.release();
+
+    assert!(device.calibration_locked);
+}
+
+#[test]
+fn test_invalid_descriptions() {
+    let duplicate = r#"
+        device = "D"
+        [[register]]
+        name = "a"
+        code = 1
+        width = "byte"
+        [[register]]
+        name = "b"
+        code = 1
+        width = "word"
+        access = "read-only"
+    "#;
+    assert!(matches!(generate(duplicate), Err(Error::Invalid(_))));
+
+    let missing_capacity = r#"
+        device = "D"
+        [[register]]
+        name = "a"
+        code = 1
+        width = "block"
+    "#;
+    assert!(matches!(generate(missing_capacity), Err(Error::Invalid(_))));
+
+    let reset_too_large = r#"
+        device = "D"
+        [[register]]
+        name = "a"
+        code = 1
+        width = "byte"
+        reset = 256
+    "#;
+    assert!(matches!(generate(reset_too_large), Err(Error::Invalid(_))));
+
+    let cleared_block = r#"
+        device = "D"
+        [[register]]
+        name = "a"
+        code = 1
+        width = "block"
+        capacity = 4
+        access = "clear-on-read"
+    "#;
+    assert!(matches!(generate(cleared_block), Err(Error::Invalid(_))));
+
+    let unknown_width = r#"
+        device = "D"
+        [[register]]
+        name = "a"
+        code = 1
+        width = "dword"
+    "#;
+    assert!(matches!(generate(unknown_width), Err(Error::Toml(_))));
+}
@@ -0,0 +1,638 @@
+//! `#[derive(SmbusDevice)]` generates a `CommandHandler` implementation from
+//! `#[smbus(...)]` field attributes:
+//!
+//! ```
+//! use smbus_request_parser::register_map::Block;
+//! use smbus_request_parser_derive::SmbusDevice;
+//!
+//! #[derive(SmbusDevice)]
+//! struct Device {
+//!     #[smbus(receive_byte, send_byte)]
+//!     #[smbus(reg = 0x01, byte, read)]
+//!     status: u8,
+//!     #[smbus(reg = 0x07, word, validate = Device::valid_limit)]
+//!     limit: u16,
+//!     #[smbus(reg = 0x10, block, read)]
+//!     name: [u8; 4],
+//!     #[smbus(reg = 0x11, block)]
+//!     scratch: Block<32>,
+//! }
+//!
+//! impl Device {
+//!     fn valid_limit(limit: &u16) -> bool {
+//!         *limit <= 1000
+//!     }
+//! }
+//! ```
+//!
+//! Registers are readable and writable unless `read` or `write` restricts them,
+//! and the generated `register_policy` lets the parser enforce the declared
+//! access and width. `#[smbus(error = Type)]` on the struct selects the handler
+//! error type, which defaults to `()`. Writes rejected by a `validate` function
+//! fail with `InvalidValue`, so the error type must implement `From<InvalidValue>`
+//! when validation is used.
+//!
+//! `write_one_to_clear` and `clear_on_read` byte and word registers get the
+//! matching `register_map::Access` semantics, and `write_once = locked`
+//! accepts a single write until the `bool` field `locked` is cleared again:
+//!
+//! ```
+//! use smbus_request_parser_derive::SmbusDevice;
+//!
+//! #[derive(SmbusDevice)]
+//! struct Device {
+//!     #[smbus(reg = 0x02, byte, write_one_to_clear)]
+//!     alarms: u8,
+//!     #[smbus(reg = 0x03, word, clear_on_read)]
+//!     faults: u16,
+//!     #[smbus(reg = 0x04, byte, write_once = address_locked)]
+//!     address: u8,
+//!     address_locked: bool,
+//! }
+//! ```
+//!
+//! A command code may be declared readable once and writable once; duplicates
+//! are compile errors:
+//!
+//! ```compile_fail
+//! use smbus_request_parser_derive::SmbusDevice;
+//!
+//! #[derive(SmbusDevice)]
+//! struct Device {
+//!     #[smbus(reg = 0x01, byte)]
+//!     a: u8,
+//!     #[smbus(reg = 0x01, byte, read)]
+//!     b: u8,
+//! }
+//! ```
+//!
+//! So is a writer for a clear-on-read code, whose policy rejects writes:
+//!
+//! ```compile_fail
+//! use smbus_request_parser_derive::SmbusDevice;
+//!
+//! #[derive(SmbusDevice)]
+//! struct Device {
+//!     #[smbus(reg = 0x03, word, clear_on_read)]
+//!     faults: u16,
+//!     #[smbus(reg = 0x03, word, write)]
+//!     mask: u16,
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Ident, LitInt, Path, Type};
+
+#[proc_macro_derive(SmbusDevice, attributes(smbus))]
+pub fn derive_smbus_device(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Width {
+    Byte,
+    Word,
+    Block,
+}
+
+/// Access kinds beyond plain reads and writes.
+#[derive(Clone, PartialEq, Eq)]
+enum Kind {
+    Plain,
+    WriteOneToClear,
+    ClearOnRead,
+    /// Locked by the named `bool` field after the first write.
+    WriteOnce(Ident),
+}
+
+struct Register {
+    field: Ident,
+    code: u8,
+    width: Width,
+    read: bool,
+    write: bool,
+    kind: Kind,
+    validate: Option<Path>,
+    span: Span,
+}
+
+#[derive(Default)]
+struct Device {
+    error: Option<Type>,
+    receive_byte: Option<Ident>,
+    send_byte: Option<Ident>,
+    registers: Vec<Register>,
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let device = parse_device(&input)?;
+    check_duplicates(&device.registers)?;
+    check_access_kinds(&device.registers)?;
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let error = device
+        .error
+        .as_ref()
+        .map(|error| quote!(#error))
+        .unwrap_or_else(|| quote!(()));
+    let unsupported = quote!(::smbus_request_parser::Unsupported.into());
+    let invalid = quote!(::smbus_request_parser::InvalidValue.into());
+
+    let mut hooks = TokenStream2::new();
+
+    if let Some(field) = &device.receive_byte {
+        hooks.extend(quote! {
+            fn handle_read_byte(&mut self) -> Option<u8> {
+                Some(self.#field)
+            }
+        });
+    }
+    if let Some(field) = &device.send_byte {
+        hooks.extend(quote! {
+            fn handle_write_byte(&mut self, data: u8) -> Result<(), Self::Error> {
+                self.#field = data;
+                Ok(())
+            }
+        });
+    }
+
+    let readers = |width: Width| {
+        device
+            .registers
+            .iter()
+            .filter(move |register| register.width == width && register.read)
+            .peekable()
+    };
+    let writers = |width: Width| {
+        device
+            .registers
+            .iter()
+            .filter(move |register| register.width == width && register.write)
+            .peekable()
+    };
+
+    for (width, method, ty) in [
+        (Width::Byte, quote!(handle_read_byte_data), quote!(u8)),
+        (Width::Word, quote!(handle_read_word_data), quote!(u16)),
+    ] {
+        let mut registers = readers(width);
+        if registers.peek().is_none() {
+            continue;
+        }
+        let arms =
+            registers.map(|Register { field, code, .. }| quote!(#code => Some(self.#field),));
+        hooks.extend(quote! {
+            fn #method(&mut self, reg: u8) -> Option<#ty> {
+                match reg {
+                    #(#arms)*
+                    _ => None,
+                }
+            }
+        });
+    }
+
+    let mut blocks = readers(Width::Block);
+    if blocks.peek().is_some() {
+        let arms = blocks.map(|Register { field, code, .. }| {
+            quote!(#code => ::smbus_request_parser::register_map::BlockValue::block_byte(&self.#field, index),)
+        });
+        hooks.extend(quote! {
+            fn handle_read_block_data(&mut self, reg: u8, index: u8) -> Option<u8> {
+                match reg {
+                    #(#arms)*
+                    _ => None,
+                }
+            }
+        });
+    }
+
+    for (width, method, ty) in [
+        (Width::Byte, quote!(handle_write_byte_data), quote!(u8)),
+        (Width::Word, quote!(handle_write_word_data), quote!(u16)),
+    ] {
+        let mut registers = writers(width);
+        if registers.peek().is_none() {
+            continue;
+        }
+        let arms = registers.map(|register| {
+            let Register { field, code, .. } = register;
+            let validate = register.validate.as_ref().map(|validate| {
+                quote! {
+                    if !#validate(&data) {
+                        return Err(#invalid);
+                    }
+                }
+            });
+            let store = match &register.kind {
+                Kind::WriteOneToClear => quote!(self.#field &= !data;),
+                Kind::WriteOnce(locked) => quote! {
+                    if self.#locked {
+                        return Err(#unsupported);
+                    }
+                    self.#field = data;
+                    self.#locked = true;
+                },
+                _ => quote!(self.#field = data;),
+            };
+            quote! {
+                #code => {
+                    #validate
+                    #store
+                    Ok(())
+                }
+            }
+        });
+        hooks.extend(quote! {
+            fn #method(&mut self, reg: u8, data: #ty) -> Result<(), Self::Error> {
+                match reg {
+                    #(#arms)*
+                    _ => Err(#unsupported),
+                }
+            }
+        });
+    }
+
+    let mut blocks = writers(Width::Block);
+    if blocks.peek().is_some() {
+        let arms = blocks.map(|register| {
+            let Register { field, code, .. } = register;
+            let validate = register.validate.as_ref().map(|validate| {
+                quote! {
+                    if !#validate(data) {
+                        return Err(#invalid);
+                    }
+                }
+            });
+            let (check, lock) = match &register.kind {
+                Kind::WriteOnce(locked) => (
+                    quote! {
+                        if self.#locked {
+                            return Err(#unsupported);
+                        }
+                    },
+                    quote!(self.#locked = true;),
+                ),
+                _ => (quote!(), quote!()),
+            };
+            quote! {
+                #code => {
+                    #check
+                    #validate
+                    if ::smbus_request_parser::register_map::BlockValue::write_block(&mut self.#field, data) {
+                        #lock
+                        Ok(())
+                    } else {
+                        Err(#unsupported)
+                    }
+                }
+            }
+        });
+        hooks.extend(quote! {
+            fn handle_write_block_data(
+                &mut self,
+                reg: u8,
+                count: u8,
+                block: &[u8],
+            ) -> Result<(), Self::Error> {
+                let data = &block[..count as usize];
+                match reg {
+                    #(#arms)*
+                    _ => Err(#unsupported),
+                }
+            }
+        });
+    }
+
+    let mut cleared = device
+        .registers
+        .iter()
+        .filter(|register| register.kind == Kind::ClearOnRead)
+        .peekable();
+    if cleared.peek().is_some() {
+        let arms = cleared.map(
+            |Register {
+                 field, code, width, ..
+             }| {
+                let size: u8 = match width {
+                    Width::Word => 2,
+                    _ => 1,
+                };
+                quote!((Some(#code), #size..) => self.#field = 0,)
+            },
+        );
+        hooks.extend(quote! {
+            fn handle_read_complete(&mut self, reg: Option<u8>, count: u8) {
+                match (reg, count) {
+                    #(#arms)*
+                    _ => {}
+                }
+            }
+        });
+    }
+
+    if !device.registers.is_empty() {
+        let arms = policies(&device.registers)
+            .into_iter()
+            .map(|(code, policy)| {
+                let access = match (&policy.kind, policy.read, policy.write) {
+                    (Kind::WriteOneToClear, ..) => quote!(WriteOneToClear),
+                    (Kind::ClearOnRead, ..) => quote!(ClearOnRead),
+                    (Kind::WriteOnce(_), ..) => quote!(WriteOnce),
+                    (Kind::Plain, true, true) => quote!(ReadWrite),
+                    (Kind::Plain, true, false) => quote!(ReadOnly),
+                    _ => quote!(WriteOnly),
+                };
+                let locked = match &policy.kind {
+                    Kind::WriteOnce(locked) => quote!(self.#locked),
+                    _ => quote!(false),
+                };
+                let widths: Vec<_> = policy
+                    .widths
+                    .into_iter()
+                    .map(|width| match width {
+                        Width::Byte => {
+                            quote!(::smbus_request_parser::register_map::AccessWidths::BYTE)
+                        }
+                        Width::Word => {
+                            quote!(::smbus_request_parser::register_map::AccessWidths::WORD)
+                        }
+                        Width::Block => {
+                            quote!(::smbus_request_parser::register_map::AccessWidths::BLOCK)
+                        }
+                    })
+                    .collect();
+                let (first, rest) = widths.split_first().expect("a register has a width");
+                quote! {
+                    #code => Some(::smbus_request_parser::register_map::RegisterPolicy {
+                        widths: #first #(.union(#rest))*,
+                        locked: #locked,
+                        ..::smbus_request_parser::register_map::RegisterPolicy::new(
+                            ::smbus_request_parser::register_map::Access::#access,
+                        )
+                    }),
+                }
+            });
+        hooks.extend(quote! {
+            fn register_policy(
+                &self,
+                reg: u8,
+            ) -> Option<::smbus_request_parser::register_map::RegisterPolicy> {
+                match reg {
+                    #(#arms)*
+                    _ => None,
+                }
+            }
+        });
+    }
+
+    Ok(quote! {
+        impl #impl_generics ::smbus_request_parser::CommandHandler for #name #ty_generics #where_clause {
+            type Error = #error;
+
+            #hooks
+        }
+    })
+}
+
+fn parse_device(input: &DeriveInput) -> syn::Result<Device> {
+    let mut device = Device::default();
+
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("smbus"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("error") {
+                device.error = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `error = Type`"))
+            }
+        })?;
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "SmbusDevice requires a struct with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "SmbusDevice can only be derived for structs",
+            ))
+        }
+    };
+
+    for field in fields {
+        let ident = field.ident.clone().expect("named field");
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("smbus"))
+        {
+            let mut code = None;
+            let mut width = None;
+            let mut read = false;
+            let mut write = false;
+            let mut validate = None;
+            let mut kind = None;
+            let mut register = false;
+
+            attr.parse_nested_meta(|meta| {
+                let path = &meta.path;
+                if path.is_ident("receive_byte") {
+                    set_once(&mut device.receive_byte, ident.clone(), &meta)
+                } else if path.is_ident("send_byte") {
+                    set_once(&mut device.send_byte, ident.clone(), &meta)
+                } else if path.is_ident("reg") {
+                    let lit: LitInt = meta.value()?.parse()?;
+                    code = Some(lit.base10_parse::<u8>()?);
+                    register = true;
+                    Ok(())
+                } else if path.is_ident("byte") {
+                    set_once(&mut width, Width::Byte, &meta)
+                } else if path.is_ident("word") {
+                    set_once(&mut width, Width::Word, &meta)
+                } else if path.is_ident("block") {
+                    set_once(&mut width, Width::Block, &meta)
+                } else if path.is_ident("read") {
+                    read = true;
+                    Ok(())
+                } else if path.is_ident("write") {
+                    write = true;
+                    Ok(())
+                } else if path.is_ident("validate") {
+                    validate = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if path.is_ident("write_one_to_clear") {
+                    set_once(&mut kind, Kind::WriteOneToClear, &meta)
+                } else if path.is_ident("clear_on_read") {
+                    set_once(&mut kind, Kind::ClearOnRead, &meta)
+                } else if path.is_ident("write_once") {
+                    let locked = meta.value()?.parse()?;
+                    set_once(&mut kind, Kind::WriteOnce(locked), &meta)
+                } else {
+                    Err(meta.error("unknown smbus attribute"))
+                }
+            })?;
+
+            if !register {
+                if width.is_some() || read || write || validate.is_some() || kind.is_some() {
+                    return Err(syn::Error::new_spanned(attr, "missing `reg = <code>`"));
+                }
+                continue;
+            }
+            let width = width.ok_or_else(|| {
+                syn::Error::new_spanned(attr, "expected one of `byte`, `word` or `block`")
+            })?;
+            let kind = kind.unwrap_or(Kind::Plain);
+            if kind != Kind::Plain && (read || write) {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "`read` and `write` are implied by the access kind",
+                ));
+            }
+            if matches!(kind, Kind::WriteOneToClear | Kind::ClearOnRead) && width == Width::Block {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "only byte and word registers can be cleared",
+                ));
+            }
+            match kind {
+                Kind::ClearOnRead => read = true,
+                _ if !read && !write => {
+                    read = true;
+                    write = true;
+                }
+                _ => {}
+            }
+            device.registers.push(Register {
+                field: ident.clone(),
+                code: code.expect("reg is set"),
+                width,
+                read,
+                write,
+                kind,
+                validate,
+                span: syn::spanned::Spanned::span(&attr.meta),
+            });
+        }
+    }
+    Ok(device)
+}
+
+fn set_once<T>(
+    slot: &mut Option<T>,
+    value: T,
+    meta: &syn::meta::ParseNestedMeta,
+) -> syn::Result<()> {
+    if slot.is_some() {
+        return Err(meta.error("declared more than once"));
+    }
+    *slot = Some(value);
+    Ok(())
+}
+
+struct Policy {
+    read: bool,
+    write: bool,
+    widths: Vec<Width>,
+    kind: Kind,
+}
+
+/// Access and widths of every declared command code, merged over the fields
+/// sharing it.
+fn policies(registers: &[Register]) -> BTreeMap<u8, Policy> {
+    let mut policies: BTreeMap<u8, Policy> = BTreeMap::new();
+    for register in registers {
+        let policy = policies.entry(register.code).or_insert(Policy {
+            read: false,
+            write: false,
+            widths: Vec::new(),
+            kind: Kind::Plain,
+        });
+        policy.read |= register.read;
+        policy.write |= register.write;
+        if !policy.widths.contains(&register.width) {
+            policy.widths.push(register.width);
+        }
+        if register.kind != Kind::Plain {
+            policy.kind = register.kind.clone();
+        }
+    }
+    policies
+}
+
+/// Fields sharing a command code share its access in the register policy, so
+/// at most one of them has an access kind, and a clear-on-read code has no writer.
+fn check_access_kinds(registers: &[Register]) -> syn::Result<()> {
+    for (i, register) in registers.iter().enumerate() {
+        for previous in registers[..i].iter().filter(|r| r.code == register.code) {
+            let (cleared, other) = match (&previous.kind, &register.kind) {
+                (Kind::Plain, Kind::Plain) => continue,
+                (Kind::ClearOnRead, _) => (previous, register),
+                (_, Kind::ClearOnRead) => (register, previous),
+                (Kind::Plain, _) | (_, Kind::Plain) => continue,
+                _ => {
+                    return Err(syn::Error::new(
+                        register.span,
+                        format!(
+                            "register {:#04x} already has an access kind through field `{}`",
+                            register.code, previous.field
+                        ),
+                    ))
+                }
+            };
+            if other.write {
+                return Err(syn::Error::new(
+                    register.span,
+                    format!(
+                        "register {:#04x} is clear-on-read through field `{}` and cannot be written through field `{}`",
+                        register.code, cleared.field, other.field
+                    ),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_duplicates(registers: &[Register]) -> syn::Result<()> {
+    let mut readers: BTreeMap<u8, &Register> = BTreeMap::new();
+    let mut writers: BTreeMap<u8, &Register> = BTreeMap::new();
+    for register in registers {
+        for (enabled, seen, kind) in [
+            (register.read, &mut readers, "readable"),
+            (register.write, &mut writers, "writable"),
+        ] {
+            if !enabled {
+                continue;
+            }
+            if let Some(previous) = seen.insert(register.code, register) {
+                return Err(syn::Error::new(
+                    register.span,
+                    format!(
+                        "register {:#04x} is already {} through field `{}`",
+                        register.code, kind, previous.field
+                    ),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,216 @@
+use smbus_request_parser::register_map::Block;
+use smbus_request_parser::*;
+use smbus_request_parser_derive::SmbusDevice;
+
+#[derive(Debug, PartialEq, Eq)]
+enum DeviceError {
+    Rejected,
+    Invalid,
+}
+
+impl From<Unsupported> for DeviceError {
+    fn from(_: Unsupported) -> Self {
+        DeviceError::Rejected
+    }
+}
+
+impl From<InvalidValue> for DeviceError {
+    fn from(_: InvalidValue) -> Self {
+        DeviceError::Invalid
+    }
+}
+
+#[derive(Default, SmbusDevice)]
+#[smbus(error = DeviceError)]
+struct Device {
+    #[smbus(receive_byte, send_byte)]
+    #[smbus(reg = 0x01, byte, read)]
+    #[smbus(reg = 0x04, byte, write)]
+    byte_a: u8,
+    #[smbus(reg = 0x02, byte, validate = Device::is_even)]
+    byte_b: u8,
+    #[smbus(reg = 0x07, word, read)]
+    word: u16,
+    #[smbus(reg = 0x09, word, write)]
+    limit: u16,
+    #[smbus(reg = 0x0b, block, read)]
+    serial: [u8; 3],
+    #[smbus(reg = 0x0d, block)]
+    block: Block<8>,
+}
+
+impl Device {
+    fn is_even(value: &u8) -> bool {
+        value.is_multiple_of(2)
+    }
+}
+
+fn transfer<H: CommandHandler>(
+    handler: &mut H,
+    bus_state: &mut SMBusState,
+    command: &[u8],
+    buffer: &mut [u8],
+) -> Result<(), SMBusProtocolError> {
+    let mut event = I2cEvent::Initiated {
+        direction: Direction::MasterToSlave,
+    };
+    handler.handle_i2c_event(&mut event, bus_state)?;
+    for byte in command {
+        event = I2cEvent::ReceivedByte { byte: *byte };
+        handler.handle_i2c_event(&mut event, bus_state)?;
+    }
+    if !buffer.is_empty() {
+        event = I2cEvent::Initiated {
+            direction: Direction::SlaveToMaster,
+        };
+        handler.handle_i2c_event(&mut event, bus_state)?;
+        for byte in buffer.iter_mut() {
+            event = I2cEvent::RequestedByte { byte };
+            handler.handle_i2c_event(&mut event, bus_state)?;
+        }
+    }
+    event = I2cEvent::Stopped;
+    handler.handle_i2c_event(&mut event, bus_state)
+}
+
+#[test]
+fn byte_registers() {
+    let mut device = Device::default();
+    let mut bus_state = SMBusState::default();
+
+    transfer(&mut device, &mut bus_state, &[0x04, 0x42], &mut []).unwrap();
+    assert_eq!(0x42, device.byte_a);
+
+    let mut data = [0];
+    transfer(&mut device, &mut bus_state, &[0x01], &mut data).unwrap();
+    assert_eq!([0x42], data);
+
+    transfer(&mut device, &mut bus_state, &[0x33], &mut []).unwrap();
+    assert_eq!(0x33, device.handle_read_byte().unwrap());
+
+    let result = transfer(&mut device, &mut bus_state, &[0x01, 0x00], &mut []);
+    assert_eq!(Err(SMBusProtocolError::ReadOnlyRegister(0x01)), result);
+
+    let mut data = [0];
+    let result = transfer(&mut device, &mut bus_state, &[0x04], &mut data);
+    assert_eq!(Err(SMBusProtocolError::WriteOnlyRegister(0x04)), result);
+}
+
+#[test]
+fn validation_rejects_writes() {
+    let mut device = Device::default();
+    let mut bus_state = SMBusState::default();
+
+    transfer(&mut device, &mut bus_state, &[0x02, 0x10], &mut []).unwrap();
+    assert_eq!(0x10, device.byte_b);
+
+    let result = transfer(&mut device, &mut bus_state, &[0x02, 0x11], &mut []);
+    assert_eq!(Err(SMBusProtocolError::InvalidWriteRegister(0x02)), result);
+    assert_eq!(0x10, device.byte_b);
+    assert_eq!(
+        Err(DeviceError::Invalid),
+        device.handle_write_byte_data(0x02, 3)
+    );
+    assert_eq!(
+        Err(DeviceError::Rejected),
+        device.handle_write_byte_data(0x03, 2)
+    );
+}
+
+#[test]
+fn word_registers() {
+    let mut device = Device {
+        word: 0xabcd,
+        ..Default::default()
+    };
+    let mut bus_state = SMBusState::default();
+
+    let mut data = [0; 2];
+    transfer(&mut device, &mut bus_state, &[0x07], &mut data).unwrap();
+    assert_eq!([0xcd, 0xab], data);
+
+    transfer(&mut device, &mut bus_state, &[0x09, 0x34, 0x12], &mut []).unwrap();
+    assert_eq!(0x1234, device.limit);
+    assert_eq!(None, device.handle_read_word_data(0x09));
+
+    let result = transfer(&mut device, &mut bus_state, &[0x09, 0x34], &mut []);
+    assert_eq!(Err(SMBusProtocolError::InvalidAccessWidth(0x09)), result);
+}
+
+#[test]
+fn declared_policy() {
+    let device = Device::default();
+    let policy = device.register_policy(0x07).unwrap();
+    assert_eq!(register_map::Access::ReadOnly, policy.access);
+    assert_eq!(register_map::AccessWidths::WORD, policy.widths);
+    assert_eq!(None, device.register_policy(0x03));
+}
+
+#[test]
+fn block_registers() {
+    let mut device = Device {
+        serial: [7, 8, 9],
+        ..Default::default()
+    };
+    let mut bus_state = SMBusState::default();
+
+    let mut data = [0; 4];
+    transfer(&mut device, &mut bus_state, &[0x0b], &mut data).unwrap();
+    assert_eq!([3, 7, 8, 9], data);
+
+    transfer(&mut device, &mut bus_state, &[0x0d, 4, 1, 2, 3, 4], &mut []).unwrap();
+    assert_eq!(&[1, 2, 3, 4], device.block.as_slice());
+
+    let mut data = [0; 5];
+    transfer(&mut device, &mut bus_state, &[0x0d], &mut data).unwrap();
+    assert_eq!([4, 1, 2, 3, 4], data);
+
+    let result = transfer(
+        &mut device,
+        &mut bus_state,
+        &[0x0d, 9, 1, 2, 3, 4, 5, 6, 7, 8, 9],
+        &mut [],
+    );
+    assert_eq!(Err(SMBusProtocolError::InvalidWriteBound(9)), result);
+}
+
+#[derive(Default, SmbusDevice)]
+struct Monitor {
+    #[smbus(reg = 0x20, byte, write_one_to_clear)]
+    alarms: u8,
+    #[smbus(reg = 0x21, word, clear_on_read)]
+    faults: u16,
+    #[smbus(reg = 0x22, byte, write_once = address_locked)]
+    address: u8,
+    address_locked: bool,
+}
+
+#[test]
+fn access_kinds() {
+    let mut monitor = Monitor {
+        alarms: 0xff,
+        faults: 0x8001,
+        ..Default::default()
+    };
+    let mut bus_state = SMBusState::default();
+
+    transfer(&mut monitor, &mut bus_state, &[0x20, 0b1000_0001], &mut []).unwrap();
+    assert_eq!(0b0111_1110, monitor.alarms);
+
+    let result = transfer(&mut monitor, &mut bus_state, &[0x21, 0x00, 0x00], &mut []);
+    assert_eq!(Err(SMBusProtocolError::ReadOnlyRegister(0x21)), result);
+    monitor.handle_read_complete(Some(0x21), 1);
+    assert_eq!(0x8001, monitor.faults);
+    let mut data = [0; 2];
+    transfer(&mut monitor, &mut bus_state, &[0x21], &mut data).unwrap();
+    assert_eq!([0x01, 0x80], data);
+    assert_eq!(0, monitor.faults);
+
+    transfer(&mut monitor, &mut bus_state, &[0x22, 0x5a], &mut []).unwrap();
+    let result = transfer(&mut monitor, &mut bus_state, &[0x22, 0xa5], &mut []);
+    assert_eq!(Err(SMBusProtocolError::RegisterLocked(0x22)), result);
+    assert_eq!(0x5a, monitor.address);
+    monitor.address_locked = false;
+    transfer(&mut monitor, &mut bus_state, &[0x22, 0xa5], &mut []).unwrap();
+    assert_eq!(0xa5, monitor.address);
+}
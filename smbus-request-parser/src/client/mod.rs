@@ -0,0 +1,136 @@
+use crate::{CommandHandler, Direction, I2cEvent, SMBusProtocolError, SMBusState};
+
+#[cfg(test)]
+mod test;
+
+/// Host side of the SMBus protocols, as used by generated typed clients.
+pub trait SMBusMaster {
+    type Error;
+
+    fn read_byte_data(&mut self, reg: u8) -> Result<u8, Self::Error>;
+    fn read_word_data(&mut self, reg: u8) -> Result<u16, Self::Error>;
+
+    /// Read a block into `buffer`, returning the byte count reported by the target.
+    /// At most `buffer.len()` bytes are stored.
+    fn read_block_data(&mut self, reg: u8, buffer: &mut [u8]) -> Result<usize, Self::Error>;
+
+    fn write_byte_data(&mut self, reg: u8, data: u8) -> Result<(), Self::Error>;
+    fn write_word_data(&mut self, reg: u8, data: u16) -> Result<(), Self::Error>;
+    fn write_block_data(&mut self, reg: u8, block: &[u8]) -> Result<(), Self::Error>;
+}
+
+/// [`SMBusMaster`] driving a [`CommandHandler`] directly through [`I2cEvent`]s,
+/// for testing host tooling against device code without hardware.
+#[derive(Debug)]
+pub struct Loopback<'a, H> {
+    handler: &'a mut H,
+    bus_state: SMBusState,
+}
+
+impl<'a, H: CommandHandler> Loopback<'a, H> {
+    pub fn new(handler: &'a mut H) -> Self {
+        Self {
+            handler,
+            bus_state: SMBusState::default(),
+        }
+    }
+
+    fn event(&mut self, mut event: I2cEvent) -> Result<(), SMBusProtocolError> {
+        self.handler
+            .handle_i2c_event(&mut event, &mut self.bus_state)
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), SMBusProtocolError> {
+        self.event(I2cEvent::Initiated {
+            direction: Direction::MasterToSlave,
+        })?;
+        let result = bytes
+            .iter()
+            .try_for_each(|byte| self.event(I2cEvent::ReceivedByte { byte: *byte }));
+        self.finish(result)
+    }
+
+    fn start_read(&mut self, reg: u8) -> Result<(), SMBusProtocolError> {
+        self.event(I2cEvent::Initiated {
+            direction: Direction::MasterToSlave,
+        })?;
+        self.event(I2cEvent::ReceivedByte { byte: reg })?;
+        self.event(I2cEvent::Initiated {
+            direction: Direction::SlaveToMaster,
+        })
+    }
+
+    fn read_next(&mut self) -> Result<u8, SMBusProtocolError> {
+        let mut byte = 0;
+        self.event(I2cEvent::RequestedByte { byte: &mut byte })?;
+        Ok(byte)
+    }
+
+    /// Send the STOP, also after a failed transfer so the next one starts cleanly.
+    fn finish<T>(
+        &mut self,
+        result: Result<T, SMBusProtocolError>,
+    ) -> Result<T, SMBusProtocolError> {
+        match result {
+            Ok(value) => self.event(I2cEvent::Stopped).map(|_| value),
+            Err(e) => {
+                let _ = self.event(I2cEvent::Stopped);
+                Err(e)
+            }
+        }
+    }
+}
+
+impl<'a, H: CommandHandler> SMBusMaster for Loopback<'a, H> {
+    type Error = SMBusProtocolError;
+
+    fn read_byte_data(&mut self, reg: u8) -> Result<u8, Self::Error> {
+        let result = self.start_read(reg).and_then(|_| self.read_next());
+        self.finish(result)
+    }
+
+    fn read_word_data(&mut self, reg: u8) -> Result<u16, Self::Error> {
+        let result = self.start_read(reg).and_then(|_| {
+            let first = self.read_next()?;
+            let second = self.read_next()?;
+            Ok(self
+                .handler
+                .byte_order(reg)
+                .word_from_bytes([first, second]))
+        });
+        self.finish(result)
+    }
+
+    fn read_block_data(&mut self, reg: u8, buffer: &mut [u8]) -> Result<usize, Self::Error> {
+        let result = self.start_read(reg).and_then(|_| {
+            let count = self.read_next()? as usize;
+            for byte in buffer.iter_mut().take(count) {
+                *byte = self.read_next()?;
+            }
+            Ok(count)
+        });
+        self.finish(result)
+    }
+
+    fn write_byte_data(&mut self, reg: u8, data: u8) -> Result<(), Self::Error> {
+        self.write(&[reg, data])
+    }
+
+    fn write_word_data(&mut self, reg: u8, data: u16) -> Result<(), Self::Error> {
+        let [first, second] = self.handler.byte_order(reg).word_to_bytes(data);
+        self.write(&[reg, first, second])
+    }
+
+    fn write_block_data(&mut self, reg: u8, block: &[u8]) -> Result<(), Self::Error> {
+        let count = u8::try_from(block.len())
+            .map_err(|_| SMBusProtocolError::InvalidWriteBlockSize(u8::MAX))?;
+        self.event(I2cEvent::Initiated {
+            direction: Direction::MasterToSlave,
+        })?;
+        let result = [reg, count]
+            .iter()
+            .chain(block)
+            .try_for_each(|byte| self.event(I2cEvent::ReceivedByte { byte: *byte }));
+        self.finish(result)
+    }
+}
@@ -0,0 +1,95 @@
+use crate::client::*;
+use crate::register_map::*;
+use crate::{ByteOrder, CommandHandler, SMBusProtocolError};
+
+static REGISTERS: [RegisterDescriptor; 3] = [
+    RegisterDescriptor {
+        code: 0x01,
+        width: Width::Byte,
+        access: Access::ReadWrite,
+        reset: &[0x42],
+        reserved: 0,
+        offset: 0,
+    },
+    RegisterDescriptor {
+        code: 0x02,
+        width: Width::Word,
+        access: Access::ReadWrite,
+        reset: &[0x34, 0x12],
+        reserved: 0,
+        offset: 1,
+    },
+    RegisterDescriptor {
+        code: 0x10,
+        width: Width::Block(8),
+        access: Access::ReadWrite,
+        reset: b"ACME",
+        reserved: 0,
+        offset: 3,
+    },
+];
+
+#[test]
+fn test_loopback_round_trip() {
+    let mut map = RegisterMap::<12>::new(&REGISTERS).unwrap();
+    let mut master = Loopback::new(&mut map);
+
+    assert_eq!(Ok(0x42), master.read_byte_data(0x01));
+    master.write_byte_data(0x01, 0x17).unwrap();
+    assert_eq!(Ok(0x17), master.read_byte_data(0x01));
+
+    assert_eq!(Ok(0x1234), master.read_word_data(0x02));
+    master.write_word_data(0x02, 0xbeef).unwrap();
+    assert_eq!(Ok(0xbeef), master.read_word_data(0x02));
+
+    let mut buffer = [0; 8];
+    assert_eq!(Ok(4), master.read_block_data(0x10, &mut buffer));
+    assert_eq!(b"ACME", &buffer[..4]);
+    master.write_block_data(0x10, &[1, 2, 3]).unwrap();
+    assert_eq!(Ok(3), master.read_block_data(0x10, &mut buffer));
+    assert_eq!([1, 2, 3], buffer[..3]);
+}
+
+#[test]
+fn test_loopback_recovers_after_error() {
+    let mut map = RegisterMap::<12>::new(&REGISTERS).unwrap();
+    let mut master = Loopback::new(&mut map);
+
+    assert_eq!(
+        Err(SMBusProtocolError::InvalidReadRegister(0x05)),
+        master.read_byte_data(0x05)
+    );
+    assert_eq!(Ok(0x42), master.read_byte_data(0x01));
+}
+
+#[derive(Default)]
+struct BigEndianWord {
+    value: u16,
+}
+
+impl CommandHandler for BigEndianWord {
+    type Error = ();
+
+    fn handle_read_word_data(&mut self, _reg: u8) -> Option<u16> {
+        Some(self.value)
+    }
+
+    fn handle_write_word_data(&mut self, _reg: u8, data: u16) -> Result<(), ()> {
+        self.value = data;
+        Ok(())
+    }
+
+    fn byte_order(&self, _reg: u8) -> ByteOrder {
+        ByteOrder::BigEndian
+    }
+}
+
+#[test]
+fn test_loopback_uses_target_byte_order() {
+    let mut word = BigEndianWord::default();
+    let mut master = Loopback::new(&mut word);
+
+    master.write_word_data(0x02, 0x1234).unwrap();
+    assert_eq!(Ok(0x1234), master.read_word_data(0x02));
+    assert_eq!(0x1234, word.value);
+}
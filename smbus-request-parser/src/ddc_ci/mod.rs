@@ -0,0 +1,212 @@
+//! DDC/CI display control (VESA DDC/CI and MCCS) in [`AddressingMode::Raw`].
+//!
+//! The host writes a request, then reads the reply; without a reply pending
+//! the null message is read.
+
+use crate::{AddressingMode, CommandHandler};
+
+#[cfg(test)]
+mod test;
+
+/// 7-bit address of the display controller.
+pub const ADDRESS: u8 = 0x37;
+/// Source address byte of host requests.
+pub const HOST_ADDRESS: u8 = 0x51;
+/// Address byte the reply checksum starts with.
+const REPLY_CHECKSUM_ADDRESS: u8 = 0x50;
+/// Set in the length byte.
+const LENGTH_FLAG: u8 = 0x80;
+/// Data bytes of a message.
+pub const MAX_DATA: usize = 35;
+/// Bytes of the capabilities string per reply.
+pub const CAPABILITIES_CHUNK: usize = 32;
+
+pub mod opcode {
+    pub const GET_VCP_FEATURE: u8 = 0x01;
+    pub const GET_VCP_FEATURE_REPLY: u8 = 0x02;
+    pub const SET_VCP_FEATURE: u8 = 0x03;
+    pub const SAVE_CURRENT_SETTINGS: u8 = 0x0c;
+    pub const CAPABILITIES_REPLY: u8 = 0xe3;
+    pub const CAPABILITIES_REQUEST: u8 = 0xf3;
+}
+
+/// Common MCCS VCP codes.
+pub mod vcp {
+    pub const NEW_CONTROL_VALUE: u8 = 0x02;
+    pub const RESTORE_FACTORY_DEFAULTS: u8 = 0x04;
+    pub const BRIGHTNESS: u8 = 0x10;
+    pub const CONTRAST: u8 = 0x12;
+    pub const SELECT_COLOR_PRESET: u8 = 0x14;
+    pub const INPUT_SOURCE: u8 = 0x60;
+    pub const AUDIO_VOLUME: u8 = 0x62;
+    pub const POWER_MODE: u8 = 0xd6;
+    pub const VERSION: u8 = 0xdf;
+}
+
+/// XOR checksum of a message, starting with the address byte it is checked against.
+pub fn checksum(address: u8, bytes: &[u8]) -> u8 {
+    bytes.iter().fold(address, |sum, byte| sum ^ byte)
+}
+
+/// A VCP feature as reported by Get VCP Feature.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct VcpValue {
+    pub maximum: u16,
+    pub current: u16,
+    /// Momentary features such as degauss, instead of set parameters.
+    pub momentary: bool,
+}
+
+/// VCP features of an emulated display.
+pub trait VcpHandler {
+    /// `None` for unsupported codes.
+    fn get_vcp(&mut self, code: u8) -> Option<VcpValue>;
+    /// Unsupported codes and values are ignored, as DDC/CI has no error reply.
+    fn set_vcp(&mut self, code: u8, value: u16);
+    /// MCCS capabilities string, e.g. `(prot(monitor)type(lcd)vcp(10 12))`.
+    fn capabilities(&self) -> &[u8];
+
+    fn save_settings(&mut self) {}
+}
+
+/// [`CommandHandler`] framing DDC/CI messages for a [`VcpHandler`].
+#[derive(Debug, Clone)]
+pub struct DdcCi<H> {
+    handler: H,
+    request: [u8; MAX_DATA + 3],
+    len: usize,
+    reply: [u8; MAX_DATA + 3],
+    reply_len: usize,
+}
+
+impl<H: VcpHandler> DdcCi<H> {
+    pub fn new(handler: H) -> Self {
+        let mut display = Self {
+            handler,
+            request: [0; MAX_DATA + 3],
+            len: 0,
+            reply: [0; MAX_DATA + 3],
+            reply_len: 0,
+        };
+        display.set_reply(&[]);
+        display
+    }
+
+    pub fn handler(&self) -> &H {
+        &self.handler
+    }
+
+    pub fn handler_mut(&mut self) -> &mut H {
+        &mut self.handler
+    }
+
+    pub fn into_inner(self) -> H {
+        self.handler
+    }
+
+    /// Frame `data` as the reply; empty data is the null message.
+    fn set_reply(&mut self, data: &[u8]) {
+        let end = 2 + data.len();
+        self.reply[..2].copy_from_slice(&[ADDRESS << 1, LENGTH_FLAG | data.len() as u8]);
+        self.reply[2..end].copy_from_slice(data);
+        self.reply[end] = checksum(REPLY_CHECKSUM_ADDRESS, &self.reply[..end]);
+        self.reply_len = end + 1;
+    }
+
+    /// Handle the request in `self.request`; malformed requests get the null message.
+    fn dispatch(&mut self) {
+        let request = &self.request[..self.len];
+        let [HOST_ADDRESS, length, ..] = *request else {
+            return self.set_reply(&[]);
+        };
+        let data_len = (length & !LENGTH_FLAG) as usize;
+        if length & LENGTH_FLAG == 0
+            || request.len() != data_len + 3
+            || checksum(ADDRESS << 1, request) != 0
+        {
+            return self.set_reply(&[]);
+        }
+        let mut data = [0; MAX_DATA];
+        data[..data_len].copy_from_slice(&request[2..2 + data_len]);
+        match data[..data_len] {
+            [opcode::GET_VCP_FEATURE, code] => {
+                let (result, value) = match self.handler.get_vcp(code) {
+                    Some(value) => (0x00, value),
+                    None => (
+                        0x01,
+                        VcpValue {
+                            maximum: 0,
+                            current: 0,
+                            momentary: false,
+                        },
+                    ),
+                };
+                let [max_high, max_low] = value.maximum.to_be_bytes();
+                let [high, low] = value.current.to_be_bytes();
+                let reply = [
+                    opcode::GET_VCP_FEATURE_REPLY,
+                    result,
+                    code,
+                    value.momentary as u8,
+                    max_high,
+                    max_low,
+                    high,
+                    low,
+                ];
+                self.set_reply(&reply);
+            }
+            [opcode::SET_VCP_FEATURE, code, high, low] => {
+                self.handler.set_vcp(code, u16::from_be_bytes([high, low]));
+                self.set_reply(&[]);
+            }
+            [opcode::SAVE_CURRENT_SETTINGS] => {
+                self.handler.save_settings();
+                self.set_reply(&[]);
+            }
+            [opcode::CAPABILITIES_REQUEST, high, low] => {
+                let capabilities = self.handler.capabilities();
+                let offset = (u16::from_be_bytes([high, low]) as usize).min(capabilities.len());
+                let end = capabilities.len().min(offset + CAPABILITIES_CHUNK);
+                let chunk_len = end - offset;
+                let mut reply = [0; 3 + CAPABILITIES_CHUNK];
+                reply[..3].copy_from_slice(&[opcode::CAPABILITIES_REPLY, high, low]);
+                reply[3..3 + chunk_len].copy_from_slice(&capabilities[offset..end]);
+                self.set_reply(&reply[..3 + chunk_len]);
+            }
+            _ => self.set_reply(&[]),
+        }
+    }
+}
+
+impl<H: VcpHandler> CommandHandler for DdcCi<H> {
+    type Error = ();
+
+    fn addressing_mode(&self) -> AddressingMode {
+        AddressingMode::Raw
+    }
+
+    fn handle_raw_write(&mut self, index: u8, data: u8) -> Result<(), ()> {
+        let index = index as usize;
+        if index >= self.request.len() {
+            return Err(());
+        }
+        self.request[index] = data;
+        self.len = index + 1;
+        Ok(())
+    }
+
+    fn handle_raw_read(&mut self, index: u8) -> Option<u8> {
+        self.reply[..self.reply_len].get(index as usize).copied()
+    }
+
+    fn on_stop(&mut self) {
+        if self.len > 0 {
+            self.dispatch();
+            self.len = 0;
+        }
+    }
+
+    fn on_abort(&mut self) {
+        self.len = 0;
+    }
+}
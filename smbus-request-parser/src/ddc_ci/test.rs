@@ -0,0 +1,136 @@
+use crate::ddc_ci::*;
+use crate::test::{read, write};
+use crate::*;
+
+const CAPABILITIES: &[u8] = b"(prot(monitor)type(lcd)model(TEST)cmds(01 02 03 0C F3)vcp(10 12 60))";
+
+#[derive(Default)]
+struct Monitor {
+    brightness: u16,
+    saved: bool,
+}
+
+impl VcpHandler for Monitor {
+    fn get_vcp(&mut self, code: u8) -> Option<VcpValue> {
+        (code == vcp::BRIGHTNESS).then_some(VcpValue {
+            maximum: 100,
+            current: self.brightness,
+            momentary: false,
+        })
+    }
+
+    fn set_vcp(&mut self, code: u8, value: u16) {
+        if code == vcp::BRIGHTNESS && value <= 100 {
+            self.brightness = value;
+        }
+    }
+
+    fn capabilities(&self) -> &[u8] {
+        CAPABILITIES
+    }
+
+    fn save_settings(&mut self) {
+        self.saved = true;
+    }
+}
+
+/// Request bytes following the address byte.
+fn request(data: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![HOST_ADDRESS, 0x80 | data.len() as u8];
+    bytes.extend_from_slice(data);
+    bytes.push(checksum(ADDRESS << 1, &bytes));
+    bytes
+}
+
+fn reply(display: &mut DdcCi<Monitor>, len: usize) -> Vec<u8> {
+    let mut bus_state = SMBusState::default();
+    let mut bytes = vec![0; len];
+    read(display, &mut bus_state, &[], &mut bytes).unwrap();
+    assert_eq!(0, checksum(0x50, &bytes));
+    bytes
+}
+
+#[test]
+fn test_null_message() {
+    let mut display = DdcCi::new(Monitor::default());
+    assert_eq!(vec![0x6e, 0x80, 0xbe], reply(&mut display, 3));
+
+    let mut bus_state = SMBusState::default();
+    let mut bytes = [0; 4];
+    let result = read(&mut display, &mut bus_state, &[], &mut bytes);
+    assert_eq!(Err(SMBusProtocolError::InvalidReadBound(3)), result);
+}
+
+#[test]
+fn test_vcp_features() {
+    let mut display = DdcCi::new(Monitor::default());
+    let mut bus_state = SMBusState::default();
+
+    let set = request(&[opcode::SET_VCP_FEATURE, vcp::BRIGHTNESS, 0x00, 0x32]);
+    assert_eq!(vec![0x51, 0x84, 0x03, 0x10, 0x00, 0x32, 0x9a], set);
+    write(&mut display, &mut bus_state, &set).unwrap();
+    assert_eq!(50, display.handler().brightness);
+
+    let get = request(&[opcode::GET_VCP_FEATURE, vcp::BRIGHTNESS]);
+    write(&mut display, &mut bus_state, &get).unwrap();
+    let bytes = reply(&mut display, 11);
+    assert_eq!(
+        vec![0x6e, 0x88, 0x02, 0x00, 0x10, 0x00, 0x00, 0x64, 0x00, 0x32],
+        bytes[..10]
+    );
+
+    let get = request(&[opcode::GET_VCP_FEATURE, vcp::INPUT_SOURCE]);
+    write(&mut display, &mut bus_state, &get).unwrap();
+    assert_eq!(&[0x02, 0x01, 0x60], &reply(&mut display, 11)[2..5]);
+
+    write(
+        &mut display,
+        &mut bus_state,
+        &request(&[opcode::SAVE_CURRENT_SETTINGS]),
+    )
+    .unwrap();
+    assert!(display.handler().saved);
+    assert_eq!(vec![0x6e, 0x80, 0xbe], reply(&mut display, 3));
+}
+
+#[test]
+fn test_invalid_requests() {
+    let mut display = DdcCi::new(Monitor::default());
+    let mut bus_state = SMBusState::default();
+
+    let get = request(&[opcode::GET_VCP_FEATURE, vcp::BRIGHTNESS]);
+    write(&mut display, &mut bus_state, &get).unwrap();
+    let mut set = request(&[opcode::SET_VCP_FEATURE, vcp::BRIGHTNESS, 0x00, 0x32]);
+    *set.last_mut().unwrap() ^= 1;
+    write(&mut display, &mut bus_state, &set).unwrap();
+    assert_eq!(0, display.handler().brightness);
+    assert_eq!(vec![0x6e, 0x80, 0xbe], reply(&mut display, 3));
+
+    let mut get = request(&[opcode::GET_VCP_FEATURE, vcp::BRIGHTNESS]);
+    get[1] = 0x83;
+    write(&mut display, &mut bus_state, &get).unwrap();
+    assert_eq!(vec![0x6e, 0x80, 0xbe], reply(&mut display, 3));
+}
+
+#[test]
+fn test_capabilities() {
+    let mut display = DdcCi::new(Monitor::default());
+    let mut bus_state = SMBusState::default();
+    let mut capabilities = Vec::new();
+
+    loop {
+        let [high, low] = (capabilities.len() as u16).to_be_bytes();
+        let request = request(&[opcode::CAPABILITIES_REQUEST, high, low]);
+        write(&mut display, &mut bus_state, &request).unwrap();
+        let mut header = [0; 2];
+        read(&mut display, &mut bus_state, &[], &mut header).unwrap();
+        let len = (header[1] & 0x7f) as usize;
+        let bytes = reply(&mut display, len + 3);
+        assert_eq!(&[opcode::CAPABILITIES_REPLY, high, low], &bytes[2..5]);
+        if len == 3 {
+            break;
+        }
+        capabilities.extend_from_slice(&bytes[5..len + 2]);
+    }
+    assert_eq!(CAPABILITIES, &capabilities[..]);
+}
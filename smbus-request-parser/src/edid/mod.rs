@@ -0,0 +1,120 @@
+//! E-DDC EDID target with segment pointer, in [`AddressingMode::Raw`].
+//!
+//! [`Edid`] answers at [`EDID_ADDRESS`] and [`Edid::segment_pointer`] at
+//! [`SEGMENT_ADDRESS`]. The segment pointer only applies to the transaction it
+//! was written in, so the STOP of every transaction must reach both handlers.
+
+use crate::{AddressingMode, CommandHandler};
+
+#[cfg(test)]
+mod test;
+
+pub const EDID_ADDRESS: u8 = 0x50;
+pub const SEGMENT_ADDRESS: u8 = 0x30;
+/// Bytes of an EDID block.
+pub const BLOCK_SIZE: usize = 128;
+/// Bytes addressed by the word offset within one segment.
+pub const SEGMENT_SIZE: usize = 256;
+
+/// [`CommandHandler`] serving an EDID blob: a write sets the word offset and
+/// reads continue from it, wrapping within the segment.
+#[derive(Debug, Clone)]
+pub struct Edid<'a> {
+    blob: &'a [u8],
+    segment: u8,
+    offset: u8,
+}
+
+impl<'a> Edid<'a> {
+    /// `blob` holds the base block and its extensions.
+    pub fn new(blob: &'a [u8]) -> Self {
+        Self {
+            blob,
+            segment: 0,
+            offset: 0,
+        }
+    }
+
+    /// Replace the blob, e.g. to emulate a different display after hotplug.
+    pub fn set_blob(&mut self, blob: &'a [u8]) {
+        self.blob = blob;
+        self.segment = 0;
+        self.offset = 0;
+    }
+
+    pub fn segment(&self) -> u8 {
+        self.segment
+    }
+
+    pub fn offset(&self) -> u8 {
+        self.offset
+    }
+
+    /// Handler of the segment pointer at [`SEGMENT_ADDRESS`].
+    pub fn segment_pointer(&mut self) -> SegmentPointer<'_, 'a> {
+        SegmentPointer(self)
+    }
+
+    fn segments(&self) -> usize {
+        self.blob.len().div_ceil(SEGMENT_SIZE)
+    }
+}
+
+impl CommandHandler for Edid<'_> {
+    type Error = ();
+
+    fn addressing_mode(&self) -> AddressingMode {
+        AddressingMode::Raw
+    }
+
+    fn handle_raw_write(&mut self, index: u8, data: u8) -> Result<(), ()> {
+        if index > 0 {
+            return Err(());
+        }
+        self.offset = data;
+        Ok(())
+    }
+
+    fn handle_raw_read(&mut self, _index: u8) -> Option<u8> {
+        let address = self.segment as usize * SEGMENT_SIZE + self.offset as usize;
+        let data = self.blob.get(address).copied()?;
+        self.offset = self.offset.wrapping_add(1);
+        Some(data)
+    }
+
+    fn on_stop(&mut self) {
+        self.segment = 0;
+    }
+
+    fn on_abort(&mut self) {
+        self.segment = 0;
+    }
+}
+
+/// Write-only segment pointer of an [`Edid`]; segments beyond the blob are NACKed.
+#[derive(Debug)]
+pub struct SegmentPointer<'e, 'a>(&'e mut Edid<'a>);
+
+impl CommandHandler for SegmentPointer<'_, '_> {
+    type Error = ();
+
+    fn addressing_mode(&self) -> AddressingMode {
+        AddressingMode::Raw
+    }
+
+    fn handle_raw_write(&mut self, index: u8, data: u8) -> Result<(), ()> {
+        if index > 0 || data as usize >= self.0.segments() {
+            return Err(());
+        }
+        self.0.segment = data;
+        Ok(())
+    }
+
+    fn on_stop(&mut self) {
+        self.0.segment = 0;
+    }
+
+    fn on_abort(&mut self) {
+        self.0.segment = 0;
+    }
+}
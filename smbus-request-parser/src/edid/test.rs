@@ -0,0 +1,135 @@
+use crate::edid::*;
+use crate::test::{read, write};
+use crate::*;
+
+/// Base block and three extensions, every byte holding its block number.
+fn blob() -> [u8; 4 * BLOCK_SIZE] {
+    let mut blob = [0; 4 * BLOCK_SIZE];
+    for (i, byte) in blob.iter_mut().enumerate() {
+        *byte = (i / BLOCK_SIZE) as u8;
+    }
+    blob[0x7e] = 3;
+    blob
+}
+
+fn event(
+    handler: &mut impl CommandHandler,
+    bus_state: &mut SMBusState,
+    mut event: I2cEvent,
+) -> Result<(), SMBusProtocolError> {
+    handler.handle_i2c_event(&mut event, bus_state)
+}
+
+/// E-DDC read: segment write, offset write and read in one transaction.
+fn segment_read(
+    edid: &mut Edid,
+    segment: u8,
+    offset: u8,
+    buffer: &mut [u8],
+) -> Result<(), SMBusProtocolError> {
+    let mut segment_state = SMBusState::default();
+    let mut edid_state = SMBusState::default();
+    let write = Direction::MasterToSlave;
+    let mut result = event(
+        &mut edid.segment_pointer(),
+        &mut segment_state,
+        I2cEvent::Initiated { direction: write },
+    )
+    .and(event(
+        &mut edid.segment_pointer(),
+        &mut segment_state,
+        I2cEvent::ReceivedByte { byte: segment },
+    ));
+    result = result
+        .and(event(
+            edid,
+            &mut edid_state,
+            I2cEvent::Initiated { direction: write },
+        ))
+        .and(event(
+            edid,
+            &mut edid_state,
+            I2cEvent::ReceivedByte { byte: offset },
+        ))
+        .and(event(
+            edid,
+            &mut edid_state,
+            I2cEvent::Initiated {
+                direction: Direction::SlaveToMaster,
+            },
+        ));
+    for byte in buffer {
+        result = result.and(event(
+            edid,
+            &mut edid_state,
+            I2cEvent::RequestedByte { byte },
+        ));
+    }
+    result
+        .and(event(edid, &mut edid_state, I2cEvent::Stopped))
+        .and(event(
+            &mut edid.segment_pointer(),
+            &mut segment_state,
+            I2cEvent::Stopped,
+        ))
+}
+
+#[test]
+fn test_base_block() {
+    let blob = blob();
+    let mut edid = Edid::new(&blob);
+    let mut bus_state = SMBusState::default();
+
+    let mut block = [0xff; BLOCK_SIZE];
+    read(&mut edid, &mut bus_state, &[0x00], &mut block).unwrap();
+    assert_eq!(blob[..BLOCK_SIZE], block);
+    assert_eq!(0x80, edid.offset());
+
+    // Current address read continues with the first extension.
+    read(&mut edid, &mut bus_state, &[], &mut block).unwrap();
+    assert_eq!([1; BLOCK_SIZE], block);
+
+    // The offset wraps within the segment.
+    let mut bytes = [0; 2];
+    read(&mut edid, &mut bus_state, &[0xff], &mut bytes).unwrap();
+    assert_eq!([1, 0], bytes);
+
+    let result = write(&mut edid, &mut bus_state, &[0x00, 0x12]);
+    assert_eq!(Err(SMBusProtocolError::InvalidWriteBound(1)), result);
+}
+
+#[test]
+fn test_segments() {
+    let blob = blob();
+    let mut edid = Edid::new(&blob);
+    let mut bus_state = SMBusState::default();
+
+    let mut block = [0; BLOCK_SIZE];
+    segment_read(&mut edid, 1, 0x80, &mut block).unwrap();
+    assert_eq!([3; BLOCK_SIZE], block);
+    assert_eq!(0, edid.segment());
+
+    // Without a segment write, reads address segment 0 again.
+    read(&mut edid, &mut bus_state, &[0x80], &mut block).unwrap();
+    assert_eq!([1; BLOCK_SIZE], block);
+
+    let result = segment_read(&mut edid, 2, 0x00, &mut block);
+    assert_eq!(Err(SMBusProtocolError::InvalidWriteBound(0)), result);
+    assert_eq!(0, edid.segment());
+}
+
+#[test]
+fn test_short_blob() {
+    let short = [0u8; BLOCK_SIZE];
+    let mut edid = Edid::new(&short);
+    let mut bus_state = SMBusState::default();
+
+    let mut bytes = [0; 2];
+    let result = read(&mut edid, &mut bus_state, &[0x7f], &mut bytes);
+    assert_eq!(Err(SMBusProtocolError::InvalidReadBound(1)), result);
+
+    let larger = blob();
+    edid.set_blob(&larger);
+    read(&mut edid, &mut bus_state, &[0x7e], &mut bytes).unwrap();
+    assert_eq!([3, 0], bytes);
+}
@@ -0,0 +1,267 @@
+//! IPMB (Intelligent Platform Management Bus) responder.
+//!
+//! Requests are master writes in [`AddressingMode::Raw`]; the response is
+//! formatted as a [`Frame`] which the firmware writes to the requester as bus master.
+
+use crate::{AddressingMode, CommandHandler};
+
+#[cfg(test)]
+mod test;
+
+/// Bytes of an IPMB message including the address byte.
+pub const MAX_MESSAGE: usize = 32;
+/// Response data after the completion code that fits in a message.
+pub const MAX_RESPONSE_DATA: usize = MAX_MESSAGE - 8;
+
+/// Network functions of requests; responses use the odd function following them.
+pub mod net_fn {
+    pub const CHASSIS: u8 = 0x00;
+    pub const BRIDGE: u8 = 0x02;
+    pub const SENSOR_EVENT: u8 = 0x04;
+    pub const APP: u8 = 0x06;
+    pub const FIRMWARE: u8 = 0x08;
+    pub const STORAGE: u8 = 0x0a;
+    pub const TRANSPORT: u8 = 0x0c;
+    pub const GROUP_EXTENSION: u8 = 0x2c;
+    pub const OEM_GROUP: u8 = 0x2e;
+}
+
+/// Commands of [`net_fn::APP`] answered by [`Ipmb`].
+pub mod app {
+    pub const GET_DEVICE_ID: u8 = 0x01;
+}
+
+pub mod completion {
+    pub const SUCCESS: u8 = 0x00;
+    pub const NODE_BUSY: u8 = 0xc0;
+    pub const INVALID_COMMAND: u8 = 0xc1;
+    pub const INVALID_COMMAND_FOR_LUN: u8 = 0xc2;
+    pub const TIMEOUT: u8 = 0xc3;
+    pub const OUT_OF_SPACE: u8 = 0xc4;
+    pub const REQUEST_DATA_TRUNCATED: u8 = 0xc6;
+    pub const REQUEST_DATA_LENGTH_INVALID: u8 = 0xc7;
+    pub const REQUEST_DATA_FIELD_LENGTH_EXCEEDED: u8 = 0xc8;
+    pub const PARAMETER_OUT_OF_RANGE: u8 = 0xc9;
+    pub const CANNOT_RETURN_REQUESTED_BYTES: u8 = 0xca;
+    pub const REQUESTED_DATA_NOT_PRESENT: u8 = 0xcb;
+    pub const INVALID_DATA_FIELD: u8 = 0xcc;
+    pub const COMMAND_ILLEGAL: u8 = 0xcd;
+    pub const UNSPECIFIED_ERROR: u8 = 0xff;
+}
+
+/// Two's complement checksum making the sum of `bytes` and the checksum zero.
+pub fn checksum(bytes: &[u8]) -> u8 {
+    bytes
+        .iter()
+        .fold(0u8, |sum, byte| sum.wrapping_add(*byte))
+        .wrapping_neg()
+}
+
+/// Answer to Get Device ID.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct DeviceId {
+    pub device_id: u8,
+    /// Device revision in bits 3:0, bit 7 set when the device provides SDRs.
+    pub device_revision: u8,
+    /// Bits 6:0, bit 7 set while a firmware update is in progress.
+    pub firmware_major: u8,
+    /// BCD.
+    pub firmware_minor: u8,
+    /// BCD, 0x02 for IPMI 2.0.
+    pub ipmi_version: u8,
+    pub additional_support: u8,
+    /// 20-bit IANA enterprise number.
+    pub manufacturer_id: u32,
+    pub product_id: u16,
+}
+
+impl DeviceId {
+    fn to_bytes(self) -> [u8; 11] {
+        let manufacturer = self.manufacturer_id.to_le_bytes();
+        let product = self.product_id.to_le_bytes();
+        [
+            self.device_id,
+            self.device_revision,
+            self.firmware_major,
+            self.firmware_minor,
+            self.ipmi_version,
+            self.additional_support,
+            manufacturer[0],
+            manufacturer[1],
+            manufacturer[2] & 0x0f,
+            product[0],
+            product[1],
+        ]
+    }
+}
+
+/// A request with valid checksums.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Request<'a> {
+    pub net_fn: u8,
+    /// Responder LUN.
+    pub lun: u8,
+    pub command: u8,
+    pub data: &'a [u8],
+}
+
+/// IPMI commands of a device on the IPMB.
+pub trait IpmiDevice {
+    fn device_id(&self) -> DeviceId;
+
+    /// Handle a request other than Get Device ID, writing the response data
+    /// after the completion code to `response`. Returns its length, or the
+    /// completion code of a failure.
+    fn handle(&mut self, _request: &Request, _response: &mut [u8]) -> Result<usize, u8> {
+        Err(completion::INVALID_COMMAND)
+    }
+}
+
+/// An IPMB message as written by the bus master after the address byte.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    address: u8,
+    bytes: [u8; MAX_MESSAGE],
+    len: usize,
+}
+
+impl Frame {
+    /// 7-bit address to send the frame to.
+    pub fn destination_address(&self) -> u8 {
+        self.address
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+}
+
+/// [`CommandHandler`] receiving IPMB requests at the 7-bit `address` for an [`IpmiDevice`].
+/// Requests arriving while a response is pending are dropped.
+#[derive(Debug, Clone)]
+pub struct Ipmb<D> {
+    device: D,
+    address: u8,
+    request: [u8; MAX_MESSAGE - 1],
+    len: usize,
+    response: Option<Frame>,
+}
+
+impl<D: IpmiDevice> Ipmb<D> {
+    pub fn new(device: D, address: u8) -> Self {
+        Self {
+            device,
+            address,
+            request: [0; MAX_MESSAGE - 1],
+            len: 0,
+            response: None,
+        }
+    }
+
+    pub fn device(&self) -> &D {
+        &self.device
+    }
+
+    pub fn device_mut(&mut self) -> &mut D {
+        &mut self.device
+    }
+
+    pub fn into_inner(self) -> D {
+        self.device
+    }
+
+    /// Response to the last request, to be written to its requester.
+    pub fn take_response(&mut self) -> Option<Frame> {
+        self.response.take()
+    }
+
+    /// Byte of the responder address as it appears on the bus.
+    fn slave_address(&self) -> u8 {
+        self.address << 1
+    }
+
+    /// Answer the request in `self.request`; requests with a bad second checksum
+    /// and responses addressed to us are dropped.
+    fn dispatch(&mut self) {
+        let message = &self.request[..self.len];
+        let [net_fn_lun, _, requester, sequence_lun, command, ref data @ .., _] = *message else {
+            return;
+        };
+        let net_fn = net_fn_lun >> 2;
+        if checksum(&message[2..]) != 0 || net_fn & 1 != 0 {
+            return;
+        }
+        let request = Request {
+            net_fn,
+            lun: net_fn_lun & 0x03,
+            command,
+            data,
+        };
+
+        // Header, completion code, data and checksum.
+        let mut bytes = [0; MAX_MESSAGE];
+        let response = &mut bytes[6..6 + MAX_RESPONSE_DATA];
+        let result = match (net_fn, command) {
+            (net_fn::APP, app::GET_DEVICE_ID) => {
+                let id = self.device.device_id().to_bytes();
+                response[..id.len()].copy_from_slice(&id);
+                Ok(id.len())
+            }
+            _ => self.device.handle(&request, response),
+        };
+        let (code, data_len) = match result {
+            Ok(len) => (completion::SUCCESS, len.min(MAX_RESPONSE_DATA)),
+            Err(code) => (code, 0),
+        };
+        let response_net_fn = (net_fn | 1) << 2 | sequence_lun & 0x03;
+        bytes[..6].copy_from_slice(&[
+            response_net_fn,
+            checksum(&[requester, response_net_fn]),
+            self.slave_address(),
+            sequence_lun & !0x03 | request.lun,
+            command,
+            code,
+        ]);
+        let end = 6 + data_len;
+        bytes[end] = checksum(&bytes[2..end]);
+        self.response = Some(Frame {
+            address: requester >> 1,
+            bytes,
+            len: end + 1,
+        });
+    }
+}
+
+impl<D: IpmiDevice> CommandHandler for Ipmb<D> {
+    type Error = ();
+
+    fn addressing_mode(&self) -> AddressingMode {
+        AddressingMode::Raw
+    }
+
+    fn handle_raw_write(&mut self, index: u8, data: u8) -> Result<(), ()> {
+        let index = index as usize;
+        if index == 0 {
+            self.len = 0;
+        }
+        if self.response.is_some() || index >= self.request.len() {
+            return Err(());
+        }
+        self.request[index] = data;
+        self.len = index + 1;
+        // The first checksum covers our address and the network function.
+        if index == 1 && checksum(&[self.slave_address(), self.request[0], data]) != 0 {
+            return Err(());
+        }
+        Ok(())
+    }
+
+    fn on_stop(&mut self) {
+        self.dispatch();
+        self.len = 0;
+    }
+
+    fn on_abort(&mut self) {
+        self.len = 0;
+    }
+}
@@ -0,0 +1,165 @@
+use crate::ipmb::*;
+use crate::test::{read, write};
+use crate::*;
+
+/// 7-bit address of the satellite controller.
+const SATELLITE: u8 = 0x36;
+/// Address byte of the BMC.
+const BMC: u8 = 0x20;
+
+struct Controller {
+    fan_speed: u8,
+}
+
+impl IpmiDevice for Controller {
+    fn device_id(&self) -> DeviceId {
+        DeviceId {
+            device_id: 0x12,
+            device_revision: 0x01,
+            firmware_major: 0x02,
+            firmware_minor: 0x15,
+            ipmi_version: 0x02,
+            additional_support: 0x01,
+            manufacturer_id: 0x00_a0_15,
+            product_id: 0x0102,
+        }
+    }
+
+    fn handle(&mut self, request: &Request, response: &mut [u8]) -> Result<usize, u8> {
+        match (request.net_fn, request.command, request.data) {
+            (net_fn::OEM_GROUP, 0x01, []) => {
+                response[0] = self.fan_speed;
+                Ok(1)
+            }
+            (net_fn::OEM_GROUP, 0x02, [speed]) => {
+                self.fan_speed = *speed;
+                Ok(0)
+            }
+            (net_fn::OEM_GROUP, 0x02, _) => Err(completion::REQUEST_DATA_LENGTH_INVALID),
+            _ => Err(completion::INVALID_COMMAND),
+        }
+    }
+}
+
+/// Request bytes following the address byte.
+fn request(net_fn: u8, sequence: u8, command: u8, data: &[u8]) -> Vec<u8> {
+    let header = net_fn << 2;
+    let mut bytes = vec![
+        header,
+        checksum(&[SATELLITE << 1, header]),
+        BMC,
+        sequence << 2,
+        command,
+    ];
+    bytes.extend_from_slice(data);
+    bytes.push(checksum(&bytes[2..]));
+    bytes
+}
+
+#[test]
+fn test_checksum() {
+    assert_eq!(0xc8, checksum(&[0x20, 0x18]));
+    assert_eq!(0, checksum(&[]));
+}
+
+#[test]
+fn test_get_device_id() {
+    let mut ipmb = Ipmb::new(Controller { fan_speed: 0 }, SATELLITE);
+    let mut bus_state = SMBusState::default();
+
+    write(
+        &mut ipmb,
+        &mut bus_state,
+        &request(net_fn::APP, 5, app::GET_DEVICE_ID, &[]),
+    )
+    .unwrap();
+    let response = ipmb.take_response().unwrap();
+    assert_eq!(BMC >> 1, response.destination_address());
+    let bytes = response.bytes();
+    assert_eq!(
+        &[0x1c, checksum(&[BMC, 0x1c]), 0x6c, 0x14, 0x01, 0x00],
+        &bytes[..6]
+    );
+    assert_eq!(
+        &[0x12, 0x01, 0x02, 0x15, 0x02, 0x01, 0x15, 0xa0, 0x00, 0x02, 0x01],
+        &bytes[6..17]
+    );
+    assert_eq!(18, bytes.len());
+    assert_eq!(0, checksum(&bytes[2..]));
+    assert!(ipmb.take_response().is_none());
+}
+
+#[test]
+fn test_device_commands() {
+    let mut ipmb = Ipmb::new(Controller { fan_speed: 0 }, SATELLITE);
+    let mut bus_state = SMBusState::default();
+
+    write(
+        &mut ipmb,
+        &mut bus_state,
+        &request(net_fn::OEM_GROUP, 1, 0x02, &[80]),
+    )
+    .unwrap();
+    assert_eq!(80, ipmb.device().fan_speed);
+    let response = ipmb.take_response().unwrap();
+    assert_eq!(&[0x02, completion::SUCCESS], &response.bytes()[4..6]);
+
+    write(
+        &mut ipmb,
+        &mut bus_state,
+        &request(net_fn::OEM_GROUP, 2, 0x01, &[]),
+    )
+    .unwrap();
+    let response = ipmb.take_response().unwrap();
+    assert_eq!(&[0xbc], &response.bytes()[..1]);
+    assert_eq!(
+        &[0x08, 0x01, completion::SUCCESS, 80],
+        &response.bytes()[3..7]
+    );
+
+    write(
+        &mut ipmb,
+        &mut bus_state,
+        &request(net_fn::OEM_GROUP, 3, 0x02, &[1, 2]),
+    )
+    .unwrap();
+    let response = ipmb.take_response().unwrap();
+    assert_eq!(completion::REQUEST_DATA_LENGTH_INVALID, response.bytes()[5]);
+    write(
+        &mut ipmb,
+        &mut bus_state,
+        &request(net_fn::STORAGE, 4, 0x10, &[]),
+    )
+    .unwrap();
+    let response = ipmb.take_response().unwrap();
+    assert_eq!(completion::INVALID_COMMAND, response.bytes()[5]);
+    assert_eq!(7, response.bytes().len());
+}
+
+#[test]
+fn test_invalid_frames() {
+    let mut ipmb = Ipmb::new(Controller { fan_speed: 0 }, SATELLITE);
+    let mut bus_state = SMBusState::default();
+
+    let mut bytes = request(net_fn::APP, 1, app::GET_DEVICE_ID, &[]);
+    bytes[1] ^= 1;
+    let result = write(&mut ipmb, &mut bus_state, &bytes);
+    assert_eq!(Err(SMBusProtocolError::InvalidWriteBound(1)), result);
+    assert!(ipmb.take_response().is_none());
+
+    let mut bytes = request(net_fn::APP, 1, app::GET_DEVICE_ID, &[]);
+    *bytes.last_mut().unwrap() ^= 1;
+    write(&mut ipmb, &mut bus_state, &bytes).unwrap();
+    assert!(ipmb.take_response().is_none());
+
+    // Requests are dropped while a response is pending.
+    let bytes = request(net_fn::APP, 1, app::GET_DEVICE_ID, &[]);
+    write(&mut ipmb, &mut bus_state, &bytes).unwrap();
+    let result = write(&mut ipmb, &mut bus_state, &bytes);
+    assert_eq!(Err(SMBusProtocolError::InvalidWriteBound(0)), result);
+    assert!(ipmb.take_response().is_some());
+
+    let mut byte = [0];
+    let result = read(&mut ipmb, &mut bus_state, &[], &mut byte);
+    assert_eq!(Err(SMBusProtocolError::InvalidReadBound(0)), result);
+}
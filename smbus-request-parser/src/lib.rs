@@ -3,152 +3,923 @@
 #[cfg(test)]
 mod test;
 
+pub mod client;
+pub mod ddc_ci;
+pub mod edid;
+pub mod ipmb;
+pub mod mctp;
+pub mod nvme_mi;
+pub mod paged;
+pub mod pec;
+pub mod pmbus;
+pub mod register_map;
+pub mod router;
+pub mod sbs;
+pub mod shadow;
+
+use register_map::{Access, AccessWidths, RegisterPolicy};
+
+#[cfg(feature = "derive")]
+pub use smbus_request_parser_derive::SmbusDevice;
+
+/// Error produced by the default implementation of every write hook, i.e. for
+/// SMBus protocols a [`CommandHandler`] does not implement.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Unsupported;
+
+impl From<Unsupported> for () {
+    fn from(_: Unsupported) {}
+}
+
+/// Error for a write to a supported register whose value the handler rejected.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct InvalidValue;
+
+impl From<InvalidValue> for () {
+    fn from(_: InvalidValue) {}
+}
+
+/// Every hook has a default which rejects the request, so a handler only
+/// implements the protocols its device supports.
 pub trait CommandHandler {
-    type Error;
+    type Error: From<Unsupported>;
+
+    fn handle_read_byte(&mut self) -> Option<u8> {
+        None
+    }
+
+    fn handle_read_byte_data(&mut self, _reg: u8) -> Option<u8> {
+        None
+    }
 
-    fn handle_read_byte(&self) -> Option<u8>;
-    fn handle_read_byte_data(&self, reg: u8) -> Option<u8>;
-    fn handle_read_word_data(&self, reg: u8) -> Option<u16>;
-    fn handle_read_block_data(&self, reg: u8, index: u8) -> Option<u8>;
+    fn handle_read_word_data(&mut self, _reg: u8) -> Option<u16> {
+        None
+    }
+
+    fn handle_read_block_data(&mut self, _reg: u8, _index: u8) -> Option<u8> {
+        None
+    }
+
+    /// Called at the end of every read transfer with the register that was read
+    /// (`None` for a receive byte and in the wide and raw addressing modes) and
+    /// the number of bytes the master consumed.
+    /// Destructive reads (clear-on-read, FIFO pops, ...) should be committed here.
+    fn handle_read_complete(&mut self, _reg: Option<u8>, _count: u8) {}
+
+    /// Block write-block read process call: `request` is the written block,
+    /// `index` 0 asks for the byte count of the response, 1.. for its bytes.
+    fn handle_block_process_call(&mut self, _reg: u8, _request: &[u8], _index: u8) -> Option<u8> {
+        None
+    }
+
+    fn handle_write_byte(&mut self, _data: u8) -> Result<(), Self::Error> {
+        Err(Unsupported.into())
+    }
+
+    fn handle_write_byte_data(&mut self, _reg: u8, _data: u8) -> Result<(), Self::Error> {
+        Err(Unsupported.into())
+    }
+
+    fn handle_write_word_data(&mut self, _reg: u8, _data: u16) -> Result<(), Self::Error> {
+        Err(Unsupported.into())
+    }
 
-    fn handle_write_byte(&mut self, data: u8) -> Result<(), Self::Error>;
-    fn handle_write_byte_data(&mut self, reg: u8, data: u8) -> Result<(), Self::Error>;
-    fn handle_write_word_data(&mut self, reg: u8, data: u16) -> Result<(), Self::Error>;
     fn handle_write_block_data(
         &mut self,
-        reg: u8,
-        count: u8,
-        block: &[u8],
-    ) -> Result<(), Self::Error>;
+        _reg: u8,
+        _count: u8,
+        _block: &[u8],
+    ) -> Result<(), Self::Error> {
+        Err(Unsupported.into())
+    }
+
+    /// Return `true` to receive block writes to `reg` incrementally through the
+    /// `handle_write_block_{start,chunk,commit,abort}` hooks instead of having
+    /// them buffered in [`SMBusState`]. Streamed blocks may be up to 255 bytes long.
+    fn is_streamed_block_write(&self, _reg: u8) -> bool {
+        false
+    }
+
+    fn handle_write_block_start(&mut self, _reg: u8, _count: u8) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn handle_write_block_chunk(
+        &mut self,
+        _reg: u8,
+        _offset: u8,
+        _chunk: &[u8],
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn handle_write_block_commit(&mut self, _reg: u8) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn handle_write_block_abort(&mut self, _reg: u8) {}
+
+    /// Called on the START that opens a transaction, not on repeated starts.
+    fn on_start(&mut self) {}
+
+    /// Access rules the parser enforces for `reg` before any read or write hook
+    /// is called. `None` leaves all checks to the hooks.
+    fn register_policy(&self, _reg: u8) -> Option<RegisterPolicy> {
+        None
+    }
+
+    /// How the first bytes of a write select what is accessed. With
+    /// [`AddressingMode::Wide`] the memory hooks are used instead of the register hooks,
+    /// with [`AddressingMode::Sequential`] every data byte goes to the byte data hooks.
+    fn addressing_mode(&self) -> AddressingMode {
+        AddressingMode::Command
+    }
+
+    /// Read the byte at `address` of a wide addressed device. The address
+    /// auto-increments after every byte.
+    fn handle_read_memory(&mut self, _address: u16) -> Option<u8> {
+        None
+    }
+
+    /// Write `data` to `address` of a wide addressed device. Bytes are applied
+    /// as they are received and the address auto-increments after every byte.
+    fn handle_write_memory(&mut self, _address: u16, _data: u8) -> Result<(), Self::Error> {
+        Err(Unsupported.into())
+    }
+
+    /// Byte `index` of a write in [`AddressingMode::Raw`]; the transfer ends at [`CommandHandler::on_stop`].
+    fn handle_raw_write(&mut self, _index: u8, _data: u8) -> Result<(), Self::Error> {
+        Err(Unsupported.into())
+    }
+
+    /// Byte `index` of a read in [`AddressingMode::Raw`].
+    fn handle_raw_read(&mut self, _index: u8) -> Option<u8> {
+        None
+    }
+
+    /// 7-bit address of a device using Packet Error Checking. Writes may then
+    /// carry a PEC byte after the data, which is checked and stripped before the
+    /// write is dispatched or committed. A block write carries one when it is a
+    /// byte longer than its count, any other write when it is a byte longer than
+    /// the widths of its register allow. Four bytes which are not a block of two
+    /// are a word with PEC. Reads in [`AddressingMode::Command`] send the PEC
+    /// after their data, for masters which read one more byte.
+    fn pec_address(&self) -> Option<u8> {
+        None
+    }
+
+    /// With a [`CommandHandler::pec_address`], reject every write without a PEC
+    /// byte instead of accepting it unchecked.
+    fn pec_required(&self) -> bool {
+        false
+    }
+
+    /// Byte order of word transfers on `reg`, the only multi-byte values the
+    /// parser assembles; blocks are passed on as they are. SMBus is LSB first.
+    fn byte_order(&self, _reg: u8) -> ByteOrder {
+        ByteOrder::LittleEndian
+    }
+
+    /// Called before the first byte of a register read is fetched, so that
+    /// multi-byte values can be latched for the whole transfer. In
+    /// [`AddressingMode::Wide`] `reg` is the low byte of the start address, in
+    /// [`AddressingMode::Raw`] it is 0.
+    fn on_read_begin(&mut self, _reg: u8) {}
+
+    /// Called after a transaction completed successfully.
+    fn on_stop(&mut self) {}
+
+    /// Called once when a transaction fails. No `on_stop` follows for it.
+    fn on_abort(&mut self) {}
+
+    /// Called when a transaction fails its PEC check, before [`CommandHandler::on_abort`].
+    fn on_pec_error(&mut self) {}
 
     fn handle_i2c_event(
         &mut self,
         event: &mut I2cEvent,
-        mut bus_state: &mut SMBusState,
+        bus_state: &mut SMBusState,
     ) -> Result<(), SMBusProtocolError> {
-        match event {
-            I2cEvent::Initiated { direction } => bus_state.direction = Some(*direction),
-            I2cEvent::ReceivedByte { byte } => {
-                if bus_state.index >= RECEIVE_BUFFER_SIZE {
-                    let err = Err(SMBusProtocolError::InvalidWriteBound(bus_state.index - 2));
+        if let I2cEvent::Initiated { .. } = event {
+            if !bus_state.open {
+                bus_state.aborted = false;
+                self.on_start();
+            }
+        }
+        let stopped = matches!(event, I2cEvent::Stopped);
+        let aborted = bus_state.aborted;
+        let open = bus_state.open || matches!(event, I2cEvent::Initiated { .. });
+        let result = process_event(self, event, bus_state);
+        if result.is_err() && !aborted {
+            if result == Err(SMBusProtocolError::InvalidPec) {
+                self.on_pec_error();
+            }
+            self.on_abort();
+        }
+        bus_state.aborted = aborted || result.is_err();
+        // Error paths reset the state, but the transaction stays open until STOP.
+        bus_state.open = open && !stopped;
+        if stopped {
+            if !bus_state.aborted {
+                self.on_stop();
+            }
+            bus_state.aborted = false;
+        }
+        result
+    }
+}
+
+fn process_event<H: CommandHandler + ?Sized>(
+    handler: &mut H,
+    event: &mut I2cEvent,
+    bus_state: &mut SMBusState,
+) -> Result<(), SMBusProtocolError> {
+    match handler.addressing_mode() {
+        AddressingMode::Command => {}
+        AddressingMode::Wide(order) => {
+            return process_memory_event(handler, event, bus_state, order)
+        }
+        AddressingMode::Sequential { last, wrap_to } => {
+            return process_sequential_event(handler, event, bus_state, last, wrap_to)
+        }
+        AddressingMode::Raw => return process_raw_event(handler, event, bus_state),
+    }
+    match event {
+        I2cEvent::Initiated { direction } => {
+            if let Some(stream) = bus_state.stream.take() {
+                handler.handle_write_block_abort(stream.register);
+                *bus_state = SMBusState::default();
+                return Err(SMBusProtocolError::IncompleteWriteBlock(stream.offset));
+            }
+            if *direction == Direction::SlaveToMaster && bus_state.index > 0 {
+                bus_state.read_register = Some(bus_state.received_data[0]);
+            }
+            if *direction == Direction::SlaveToMaster && bus_state.index >= 2 {
+                let count = bus_state.received_data[1];
+                if count as usize + 2 != bus_state.index as usize {
+                    return Err(SMBusProtocolError::InvalidWriteBlockSize(count));
+                }
+                bus_state.current_transfer = Some(StatefulTransfer::ProcessCall);
+                handler.on_read_begin(bus_state.received_data[0]);
+            }
+            if *direction == Direction::SlaveToMaster {
+                if let Some(address) = handler.pec_address() {
+                    let written = &bus_state.received_data[..bus_state.index as usize];
+                    let crc = match written.is_empty() {
+                        true => 0,
+                        false => pec::update(pec::compute(&[pec::write_address(address)]), written),
+                    };
+                    bus_state.read_pec = Some(pec::update(crc, &[pec::read_address(address)]));
+                }
+            }
+            bus_state.direction = Some(*direction);
+        }
+        I2cEvent::ReceivedByte { byte } => {
+            if bus_state.aborted {
+                return Ok(());
+            }
+            // With PEC the byte past the widths of the register may be the PEC.
+            let pec = handler.pec_address().is_some() as u8;
+            if bus_state.index >= 2 + pec
+                && bus_state.stream.is_none()
+                && bus_state.direction == Some(Direction::MasterToSlave)
+            {
+                let register = bus_state.received_data[0];
+                let wider = match bus_state.index - pec {
+                    2 => AccessWidths::WORD.union(AccessWidths::BLOCK),
+                    _ => AccessWidths::BLOCK,
+                };
+                if !allowed_widths(handler, register).intersects(wider) {
+                    return Err(SMBusProtocolError::InvalidAccessWidth(register));
+                }
+            }
+            // The stream starts with the byte count, so empty blocks are streamed too.
+            if bus_state.index == 1
+                && bus_state.stream.is_none()
+                && bus_state.direction == Some(Direction::MasterToSlave)
+                && handler.is_streamed_block_write(bus_state.received_data[0])
+            {
+                let register = bus_state.received_data[0];
+                let count = *byte;
+                if let Err(e) = check_width(handler, register, AccessWidths::BLOCK)
+                    .and_then(|_| check_write(handler, register, None))
+                {
+                    *bus_state = SMBusState::default();
+                    return Err(e);
+                }
+                if let Err(_e) = handler.handle_write_block_start(register, count) {
                     *bus_state = SMBusState::default();
-                    return err;
+                    return Err(SMBusProtocolError::InvalidWriteRegister(register));
                 }
-                bus_state.received_data[bus_state.index as usize] = *byte;
-                bus_state.index += 1;
+                let address = handler.pec_address().map_or(0, pec::write_address);
+                bus_state.stream = Some(BlockStream {
+                    register,
+                    count,
+                    offset: 0,
+                    crc: pec::update(pec::compute(&[address]), &[register, count]),
+                    pec: None,
+                });
+                return Ok(());
             }
-            I2cEvent::RequestedByte { byte } => {
-                if bus_state.direction != Some(Direction::SlaveToMaster) {
-                    return Err(SMBusProtocolError::WrongDirection(bus_state.direction));
+            if let Some(stream) = bus_state.stream.as_mut() {
+                if stream.offset == stream.count
+                    && stream.pec.is_none()
+                    && handler.pec_address().is_some()
+                {
+                    stream.pec = Some(*byte);
+                    return Ok(());
                 }
-                match bus_state.index {
-                    0 => {
-                        if bus_state.direction == Some(Direction::SlaveToMaster) {
-                            if let Some(data) = self.handle_read_byte() {
-                                **byte = data;
-                            } else {
-                                return Err(SMBusProtocolError::ReadByteUnsupported);
-                            }
-                        } else {
-                            return Err(SMBusProtocolError::WrongDirection(bus_state.direction));
-                        }
+                if stream.offset >= stream.count {
+                    let (register, count) = (stream.register, stream.count);
+                    handler.handle_write_block_abort(register);
+                    *bus_state = SMBusState::default();
+                    return Err(SMBusProtocolError::InvalidWriteBound(count));
+                }
+                if let Err(_e) =
+                    handler.handle_write_block_chunk(stream.register, stream.offset, &[*byte])
+                {
+                    let (register, offset) = (stream.register, stream.offset);
+                    handler.handle_write_block_abort(register);
+                    *bus_state = SMBusState::default();
+                    return Err(SMBusProtocolError::InvalidWriteBound(offset));
+                }
+                stream.crc = pec::update(stream.crc, &[*byte]);
+                stream.offset += 1;
+                return Ok(());
+            }
+            let capacity = match handler.pec_address() {
+                Some(_) => RECEIVE_BUFFER_SIZE,
+                None => RECEIVE_BUFFER_SIZE - 1,
+            };
+            if bus_state.index >= capacity {
+                let err = Err(SMBusProtocolError::InvalidWriteBound(bus_state.index - 2));
+                *bus_state = SMBusState::default();
+                return err;
+            }
+            bus_state.received_data[bus_state.index as usize] = *byte;
+            bus_state.index += 1;
+        }
+        I2cEvent::RequestedByte { byte } => {
+            if bus_state.direction != Some(Direction::SlaveToMaster) {
+                return Err(SMBusProtocolError::WrongDirection(bus_state.direction));
+            }
+            if let Some(StatefulTransfer::ProcessCall) = bus_state.current_transfer {
+                let register = bus_state.received_data[0];
+                if bus_state.bytes_read == 0 {
+                    check_read(handler, register)?;
+                }
+                let request = &bus_state.received_data[2..bus_state.index as usize];
+                match handler.handle_block_process_call(register, request, bus_state.bytes_read) {
+                    Some(data) => **byte = data,
+                    None if bus_state.bytes_read == 0 => {
+                        return Err(SMBusProtocolError::InvalidReadRegister(register))
                     }
-                    1 => {
-                        let register = bus_state.received_data[0];
-                        if let Some(data) = self.handle_read_byte_data(register) {
-                            bus_state.current_transfer = Some(StatefulTransfer::Byte(data));
-                            **byte = data;
-                        } else if let Some(data) = self.handle_read_word_data(register) {
-                            bus_state.current_transfer = Some(StatefulTransfer::Word(data));
-                            **byte = data as u8;
-                        } else if let Some(data) = self.handle_read_block_data(register, 0) {
-                            bus_state.current_transfer = Some(StatefulTransfer::Block(data));
+                    None if send_read_pec(bus_state, byte) => return Ok(()),
+                    None => return Err(SMBusProtocolError::InvalidReadBound(bus_state.bytes_read)),
+                }
+                update_read_pec(bus_state, **byte);
+                bus_state.bytes_read = bus_state.bytes_read.saturating_add(1);
+                return Ok(());
+            }
+            // The byte after a receive byte is its PEC.
+            if bus_state.index == 1
+                && bus_state.read_register.is_none()
+                && send_read_pec(bus_state, byte)
+            {
+                return Ok(());
+            }
+            match bus_state.index {
+                0 => {
+                    if bus_state.direction == Some(Direction::SlaveToMaster) {
+                        if let Some(data) = handler.handle_read_byte() {
                             **byte = data;
                         } else {
-                            return Err(SMBusProtocolError::InvalidReadRegister(register));
+                            return Err(SMBusProtocolError::ReadByteUnsupported);
                         }
+                    } else {
+                        return Err(SMBusProtocolError::WrongDirection(bus_state.direction));
                     }
-                    2 => {
-                        let first_byte = bus_state.received_data[0];
-                        match bus_state.current_transfer {
-                            Some(StatefulTransfer::Byte(_)) => {}
-                            Some(StatefulTransfer::Word(data)) => {
-                                **byte = (data >> 8) as u8;
-                                bus_state.current_transfer = None;
-                            }
-                            Some(StatefulTransfer::Block(_)) => {
-                                if let Some(data) = self.handle_read_block_data(first_byte, 1) {
-                                    **byte = data;
-                                } else {
-                                    return Err(SMBusProtocolError::InvalidReadBound(1));
-                                }
+                }
+                1 => {
+                    let register = bus_state.received_data[0];
+                    check_read(handler, register)?;
+                    handler.on_read_begin(register);
+                    let widths = allowed_widths(handler, register);
+                    if let Some(data) = widths
+                        .contains(AccessWidths::BYTE)
+                        .then(|| handler.handle_read_byte_data(register))
+                        .flatten()
+                    {
+                        bus_state.current_transfer = Some(StatefulTransfer::Byte(data));
+                        **byte = data;
+                    } else if let Some(data) = widths
+                        .contains(AccessWidths::WORD)
+                        .then(|| handler.handle_read_word_data(register))
+                        .flatten()
+                    {
+                        bus_state.current_transfer = Some(StatefulTransfer::Word(data));
+                        **byte = handler.byte_order(register).word_to_bytes(data)[0];
+                    } else if let Some(data) = widths
+                        .contains(AccessWidths::BLOCK)
+                        .then(|| handler.handle_read_block_data(register, 0))
+                        .flatten()
+                    {
+                        bus_state.current_transfer = Some(StatefulTransfer::Block(data));
+                        **byte = data;
+                    } else {
+                        return Err(SMBusProtocolError::InvalidReadRegister(register));
+                    }
+                }
+                2 => {
+                    let first_byte = bus_state.received_data[0];
+                    match bus_state.current_transfer {
+                        Some(StatefulTransfer::Byte(_)) => {
+                            if !send_read_pec(bus_state, byte) {
+                                check_width(handler, first_byte, AccessWidths::WORD)?;
                             }
-                            _ => return Err(SMBusProtocolError::InvalidReadBound(2)),
+                            return Ok(());
                         }
-                    }
-                    n => {
-                        if let Some(StatefulTransfer::Block(_)) = bus_state.current_transfer {
-                            if let Some(data) =
-                                self.handle_read_block_data(bus_state.received_data[0], n - 1)
-                            {
+                        Some(StatefulTransfer::Word(data)) => {
+                            **byte = handler.byte_order(first_byte).word_to_bytes(data)[1];
+                            bus_state.current_transfer = None;
+                        }
+                        Some(StatefulTransfer::Block(_)) => {
+                            if let Some(data) = handler.handle_read_block_data(first_byte, 1) {
                                 **byte = data;
+                            } else if send_read_pec(bus_state, byte) {
+                                return Ok(());
+                            } else {
+                                return Err(SMBusProtocolError::InvalidReadBound(1));
                             }
                         }
+                        _ => return Err(SMBusProtocolError::InvalidReadBound(2)),
                     }
                 }
-                bus_state.index += 1;
-            }
-            I2cEvent::Stopped => {
-                if bus_state.direction == Some(Direction::MasterToSlave) {
-                    match bus_state.index {
-                        0 => return Err(SMBusProtocolError::QuickCommandUnsupported),
-                        1 => {
-                            if let Err(_e) = self.handle_write_byte(bus_state.received_data[0]) {
-                                return Err(SMBusProtocolError::WriteByteUnsupported);
+                n => {
+                    let register = bus_state.received_data[0];
+                    if let Some(StatefulTransfer::Block(_)) = bus_state.current_transfer {
+                        match handler.handle_read_block_data(register, n - 1) {
+                            Some(data) => **byte = data,
+                            // Reads past the end of the block are not counted as consumed.
+                            None => {
+                                send_read_pec(bus_state, byte);
+                                return Ok(());
                             }
                         }
-                        2 => {
-                            if let Err(_e) = self.handle_write_byte_data(
-                                bus_state.received_data[0],
-                                bus_state.received_data[1],
-                            ) {
-                                return Err(SMBusProtocolError::InvalidWriteRegister(
-                                    bus_state.received_data[0],
-                                ));
-                            }
-                        }
-                        3 => {
-                            let data: u16 = bus_state.received_data[1] as u16
-                                | (bus_state.received_data[2] as u16) << 8;
-                            if let Err(_e) =
-                                self.handle_write_word_data(bus_state.received_data[0], data)
-                            {
-                                return Err(SMBusProtocolError::InvalidWriteRegister(
-                                    bus_state.received_data[0],
-                                ));
-                            };
+                    } else {
+                        if !send_read_pec(bus_state, byte) {
+                            check_width(handler, register, AccessWidths::BLOCK)?;
                         }
-                        4..=RECEIVE_BUFFER_SIZE => {
-                            let reg = bus_state.received_data[0];
-                            let count = bus_state.received_data[1];
-                            if count > 32 {
-                                return Err(SMBusProtocolError::InvalidWriteBlockSize(count));
-                            }
-                            let slice = &bus_state.received_data[2usize..count as usize + 2];
-                            if let Err(_e) = self.handle_write_block_data(reg, count, slice) {
-                                return Err(SMBusProtocolError::InvalidWriteBound(count));
-                            }
-                        }
-                        n => return Err(SMBusProtocolError::InvalidWriteBound(n)),
-                    };
+                        return Ok(());
+                    }
                 }
-                bus_state.received_data.iter_mut().for_each(|x| *x = 0);
-                bus_state.index = 0;
-                bus_state.direction = None;
             }
+            update_read_pec(bus_state, **byte);
+            bus_state.index += 1;
+            bus_state.bytes_read = bus_state.bytes_read.saturating_add(1);
         }
+        I2cEvent::Stopped => {
+            let result = if bus_state.aborted {
+                if let Some(stream) = bus_state.stream.take() {
+                    handler.handle_write_block_abort(stream.register);
+                }
+                Ok(())
+            } else if let Some(stream) = bus_state.stream.take() {
+                finish_stream(handler, stream)
+            } else if bus_state.direction == Some(Direction::MasterToSlave) {
+                dispatch_write(handler, bus_state)
+            } else {
+                finish_read(handler, bus_state)
+            };
+            *bus_state = SMBusState::default();
+            return result;
+        }
+    }
+    Ok(())
+}
+
+/// Event handling for [`AddressingMode::Wide`]: two address bytes followed by
+/// data, with the pointer kept across transactions for current address reads.
+fn process_memory_event<H: CommandHandler + ?Sized>(
+    handler: &mut H,
+    event: &mut I2cEvent,
+    bus_state: &mut SMBusState,
+    order: ByteOrder,
+) -> Result<(), SMBusProtocolError> {
+    match event {
+        I2cEvent::Initiated { direction } => {
+            if *direction == Direction::SlaveToMaster && bus_state.index == 1 {
+                return Err(SMBusProtocolError::IncompleteAddress);
+            }
+            bus_state.direction = Some(*direction);
+        }
+        I2cEvent::ReceivedByte { byte } => {
+            if bus_state.aborted {
+                return Ok(());
+            }
+            if bus_state.direction != Some(Direction::MasterToSlave) {
+                return Err(SMBusProtocolError::WrongDirection(bus_state.direction));
+            }
+            match bus_state.index {
+                0 => bus_state.received_data[0] = *byte,
+                1 => {
+                    bus_state.pointer = order.word_from_bytes([bus_state.received_data[0], *byte]);
+                }
+                _ => {
+                    let address = bus_state.pointer;
+                    if let Err(_e) = handler.handle_write_memory(address, *byte) {
+                        return Err(SMBusProtocolError::InvalidMemoryAddress(address));
+                    }
+                    bus_state.pointer = address.wrapping_add(1);
+                }
+            }
+            bus_state.index = bus_state.index.saturating_add(1);
+        }
+        I2cEvent::RequestedByte { byte } => {
+            if bus_state.direction != Some(Direction::SlaveToMaster) {
+                return Err(SMBusProtocolError::WrongDirection(bus_state.direction));
+            }
+            let address = bus_state.pointer;
+            if bus_state.bytes_read == 0 {
+                handler.on_read_begin(address as u8);
+            }
+            match handler.handle_read_memory(address) {
+                Some(data) => **byte = data,
+                None => return Err(SMBusProtocolError::InvalidMemoryAddress(address)),
+            }
+            bus_state.pointer = address.wrapping_add(1);
+            bus_state.bytes_read = bus_state.bytes_read.saturating_add(1);
+        }
+        I2cEvent::Stopped => {
+            if bus_state.direction == Some(Direction::SlaveToMaster) && !bus_state.aborted {
+                handler.handle_read_complete(None, bus_state.bytes_read);
+            }
+            let incomplete = bus_state.direction == Some(Direction::MasterToSlave)
+                && bus_state.index == 1
+                && !bus_state.aborted;
+            *bus_state = SMBusState {
+                pointer: bus_state.pointer,
+                ..SMBusState::default()
+            };
+            if incomplete {
+                return Err(SMBusProtocolError::IncompleteAddress);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Pointer value of a [`AddressingMode::Sequential`] device which advanced past
+/// its last register without wrapping.
+const PAST_LAST_REGISTER: u16 = 0x100;
+
+fn next_register(pointer: u16, last: u8, wrap_to: Option<u8>) -> u16 {
+    match (pointer == last as u16, wrap_to) {
+        (true, Some(first)) => first as u16,
+        (true, None) => PAST_LAST_REGISTER,
+        (false, _) => (pointer + 1) & 0xff,
+    }
+}
+
+/// Event handling for [`AddressingMode::Sequential`]: a register pointer followed
+/// by data bytes for consecutive registers.
+fn process_sequential_event<H: CommandHandler + ?Sized>(
+    handler: &mut H,
+    event: &mut I2cEvent,
+    bus_state: &mut SMBusState,
+    last: u8,
+    wrap_to: Option<u8>,
+) -> Result<(), SMBusProtocolError> {
+    match event {
+        I2cEvent::Initiated { direction } => {
+            bus_state.direction = Some(*direction);
+        }
+        I2cEvent::ReceivedByte { byte } => {
+            if bus_state.aborted {
+                return Ok(());
+            }
+            if bus_state.direction != Some(Direction::MasterToSlave) {
+                return Err(SMBusProtocolError::WrongDirection(bus_state.direction));
+            }
+            if bus_state.index == 0 {
+                bus_state.pointer = *byte as u16;
+            } else {
+                let Ok(reg) = u8::try_from(bus_state.pointer) else {
+                    return Err(SMBusProtocolError::InvalidWriteBound(bus_state.index - 1));
+                };
+                check_width(handler, reg, AccessWidths::BYTE)?;
+                check_write(handler, reg, Some(*byte as u16))?;
+                if let Err(_e) = handler.handle_write_byte_data(reg, *byte) {
+                    return Err(SMBusProtocolError::InvalidWriteRegister(reg));
+                }
+                bus_state.pointer = next_register(bus_state.pointer, last, wrap_to);
+            }
+            bus_state.index = bus_state.index.saturating_add(1);
+        }
+        I2cEvent::RequestedByte { byte } => {
+            if bus_state.direction != Some(Direction::SlaveToMaster) {
+                return Err(SMBusProtocolError::WrongDirection(bus_state.direction));
+            }
+            let Ok(reg) = u8::try_from(bus_state.pointer) else {
+                return Err(SMBusProtocolError::InvalidReadBound(bus_state.bytes_read));
+            };
+            check_read(handler, reg)?;
+            check_width(handler, reg, AccessWidths::BYTE)?;
+            if bus_state.bytes_read == 0 {
+                bus_state.read_register = Some(reg);
+            }
+            handler.on_read_begin(reg);
+            match handler.handle_read_byte_data(reg) {
+                Some(data) => **byte = data,
+                None => return Err(SMBusProtocolError::InvalidReadRegister(reg)),
+            }
+            bus_state.pointer = next_register(bus_state.pointer, last, wrap_to);
+            bus_state.bytes_read = bus_state.bytes_read.saturating_add(1);
+        }
+        I2cEvent::Stopped => {
+            if !bus_state.aborted {
+                if let Some(first) = bus_state.read_register {
+                    let mut reg = first as u16;
+                    for _ in 0..bus_state.bytes_read {
+                        handler.handle_read_complete(Some(reg as u8), 1);
+                        reg = next_register(reg, last, wrap_to);
+                    }
+                }
+            }
+            *bus_state = SMBusState {
+                pointer: bus_state.pointer,
+                ..SMBusState::default()
+            };
+        }
+    }
+    Ok(())
+}
+
+/// Event handling for [`AddressingMode::Raw`]: bytes are passed through with
+/// their index in the transfer.
+fn process_raw_event<H: CommandHandler + ?Sized>(
+    handler: &mut H,
+    event: &mut I2cEvent,
+    bus_state: &mut SMBusState,
+) -> Result<(), SMBusProtocolError> {
+    match event {
+        I2cEvent::Initiated { direction } => {
+            bus_state.direction = Some(*direction);
+            bus_state.index = 0;
+            bus_state.bytes_read = 0;
+        }
+        I2cEvent::ReceivedByte { byte } => {
+            if bus_state.aborted {
+                return Ok(());
+            }
+            if bus_state.direction != Some(Direction::MasterToSlave) {
+                return Err(SMBusProtocolError::WrongDirection(bus_state.direction));
+            }
+            let index = bus_state.index;
+            if index == u8::MAX || handler.handle_raw_write(index, *byte).is_err() {
+                return Err(SMBusProtocolError::InvalidWriteBound(index));
+            }
+            bus_state.index += 1;
+        }
+        I2cEvent::RequestedByte { byte } => {
+            if bus_state.direction != Some(Direction::SlaveToMaster) {
+                return Err(SMBusProtocolError::WrongDirection(bus_state.direction));
+            }
+            let index = bus_state.bytes_read;
+            if index == 0 {
+                handler.on_read_begin(0);
+            }
+            match handler.handle_raw_read(index) {
+                Some(data) if index < u8::MAX => **byte = data,
+                _ => return Err(SMBusProtocolError::InvalidReadBound(index)),
+            }
+            bus_state.bytes_read += 1;
+        }
+        I2cEvent::Stopped => {
+            if bus_state.direction == Some(Direction::SlaveToMaster) && !bus_state.aborted {
+                handler.handle_read_complete(None, bus_state.bytes_read);
+            }
+            *bus_state = SMBusState::default();
+        }
+    }
+    Ok(())
+}
+
+fn finish_stream<H: CommandHandler + ?Sized>(
+    handler: &mut H,
+    stream: BlockStream,
+) -> Result<(), SMBusProtocolError> {
+    if stream.offset != stream.count {
+        handler.handle_write_block_abort(stream.register);
+        return Err(SMBusProtocolError::IncompleteWriteBlock(stream.offset));
+    }
+    if stream.pec.is_none() && handler.pec_required() {
+        handler.handle_write_block_abort(stream.register);
+        return Err(SMBusProtocolError::MissingPec);
+    }
+    if stream.pec.is_some_and(|pec| pec != stream.crc) {
+        handler.handle_write_block_abort(stream.register);
+        return Err(SMBusProtocolError::InvalidPec);
+    }
+    if let Err(_e) = handler.handle_write_block_commit(stream.register) {
+        return Err(SMBusProtocolError::InvalidWriteRegister(stream.register));
+    }
+    Ok(())
+}
+
+/// Continue the PEC of a read over a data byte sent to the master.
+fn update_read_pec(bus_state: &mut SMBusState, byte: u8) {
+    if let Some(crc) = bus_state.read_pec.as_mut() {
+        *crc = pec::update(*crc, &[byte]);
+    }
+}
+
+/// Send the PEC of a read once its data is exhausted; `false` without PEC or
+/// once it has been sent.
+fn send_read_pec(bus_state: &mut SMBusState, byte: &mut u8) -> bool {
+    match bus_state.read_pec.take() {
+        Some(crc) => {
+            *byte = crc;
+            true
+        }
+        None => false,
+    }
+}
+
+fn finish_read<H: CommandHandler + ?Sized>(
+    handler: &mut H,
+    bus_state: &SMBusState,
+) -> Result<(), SMBusProtocolError> {
+    if bus_state.direction != Some(Direction::SlaveToMaster) {
+        return Ok(());
+    }
+    if let (Some(StatefulTransfer::Word(_)), Some(register)) =
+        (&bus_state.current_transfer, bus_state.read_register)
+    {
+        check_width(handler, register, AccessWidths::BYTE)?;
+    }
+    handler.handle_read_complete(bus_state.read_register, bus_state.bytes_read);
+    Ok(())
+}
+
+fn allowed_widths<H: CommandHandler + ?Sized>(handler: &H, reg: u8) -> AccessWidths {
+    handler
+        .register_policy(reg)
+        .map_or(AccessWidths::ANY, |policy| policy.widths)
+}
+
+fn check_width<H: CommandHandler + ?Sized>(
+    handler: &H,
+    reg: u8,
+    width: AccessWidths,
+) -> Result<(), SMBusProtocolError> {
+    if allowed_widths(handler, reg).contains(width) {
         Ok(())
+    } else {
+        Err(SMBusProtocolError::InvalidAccessWidth(reg))
+    }
+}
+
+fn check_read<H: CommandHandler + ?Sized>(handler: &H, reg: u8) -> Result<(), SMBusProtocolError> {
+    match handler.register_policy(reg) {
+        Some(policy) if !policy.access.is_readable() => {
+            Err(SMBusProtocolError::WriteOnlyRegister(reg))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// `data` is the written byte or word; blocks are not checked against reserved bits.
+fn check_write<H: CommandHandler + ?Sized>(
+    handler: &H,
+    reg: u8,
+    data: Option<u16>,
+) -> Result<(), SMBusProtocolError> {
+    let Some(policy) = handler.register_policy(reg) else {
+        return Ok(());
+    };
+    if !policy.access.is_writable() {
+        return Err(SMBusProtocolError::ReadOnlyRegister(reg));
+    }
+    if policy.access == Access::WriteOnce && policy.locked {
+        return Err(SMBusProtocolError::RegisterLocked(reg));
+    }
+    if data.is_some_and(|data| data & policy.reserved != 0) {
+        return Err(SMBusProtocolError::ReservedBitsSet(reg));
+    }
+    Ok(())
+}
+
+/// Whether the last byte of a write is a PEC, see [`CommandHandler::pec_address`].
+fn has_pec<H: CommandHandler + ?Sized>(handler: &H, bus_state: &SMBusState) -> bool {
+    let length = bus_state.index as usize;
+    let widths = allowed_widths(handler, bus_state.received_data[0]);
+    let count = bus_state.received_data[1] as usize;
+    match length {
+        0 | 1 => false,
+        _ if handler.pec_required() => true,
+        2 => !widths.contains(AccessWidths::BYTE),
+        3 if widths.contains(AccessWidths::WORD) => false,
+        3 => !(widths.contains(AccessWidths::BLOCK) && count == 1),
+        // Four bytes are a block of two, or else a word or a block of one with PEC.
+        4 if widths.contains(AccessWidths::BLOCK) && count == 2 => false,
+        4 => widths.contains(AccessWidths::WORD) || count == 1,
+        _ => length == count + 3,
+    }
+}
+
+fn dispatch_write<H: CommandHandler + ?Sized>(
+    handler: &mut H,
+    bus_state: &SMBusState,
+) -> Result<(), SMBusProtocolError> {
+    let mut length = bus_state.index;
+    if let Some(address) = handler.pec_address() {
+        if has_pec(handler, bus_state) {
+            length -= 1;
+            let crc = pec::update(
+                pec::compute(&[pec::write_address(address)]),
+                &bus_state.received_data[..length as usize],
+            );
+            if crc != bus_state.received_data[length as usize] {
+                return Err(SMBusProtocolError::InvalidPec);
+            }
+        } else if length > 0 && handler.pec_required() {
+            return Err(SMBusProtocolError::MissingPec);
+        }
+    }
+    match length {
+        0 => return Err(SMBusProtocolError::QuickCommandUnsupported),
+        1 => {
+            if let Err(_e) = handler.handle_write_byte(bus_state.received_data[0]) {
+                return Err(SMBusProtocolError::WriteByteUnsupported);
+            }
+        }
+        2 => {
+            check_width(handler, bus_state.received_data[0], AccessWidths::BYTE)?;
+            check_write(
+                handler,
+                bus_state.received_data[0],
+                Some(bus_state.received_data[1] as u16),
+            )?;
+            if let Err(_e) = handler
+                .handle_write_byte_data(bus_state.received_data[0], bus_state.received_data[1])
+            {
+                return Err(SMBusProtocolError::InvalidWriteRegister(
+                    bus_state.received_data[0],
+                ));
+            }
+        }
+        // Three bytes are a word write, or a one byte block write to a
+        // register which only accepts blocks.
+        3 if bus_state.received_data[1] == 1
+            && !allowed_widths(handler, bus_state.received_data[0])
+                .contains(AccessWidths::WORD) =>
+        {
+            dispatch_block_write(handler, bus_state, length)?;
+        }
+        3 => {
+            let data = handler
+                .byte_order(bus_state.received_data[0])
+                .word_from_bytes([bus_state.received_data[1], bus_state.received_data[2]]);
+            check_width(handler, bus_state.received_data[0], AccessWidths::WORD)?;
+            check_write(handler, bus_state.received_data[0], Some(data))?;
+            if let Err(_e) = handler.handle_write_word_data(bus_state.received_data[0], data) {
+                return Err(SMBusProtocolError::InvalidWriteRegister(
+                    bus_state.received_data[0],
+                ));
+            };
+        }
+        4..=RECEIVE_BUFFER_SIZE => dispatch_block_write(handler, bus_state, length)?,
+        n => return Err(SMBusProtocolError::InvalidWriteBound(n)),
+    };
+    Ok(())
+}
+
+/// `length` excludes the PEC, which [`dispatch_write`] has checked.
+fn dispatch_block_write<H: CommandHandler + ?Sized>(
+    handler: &mut H,
+    bus_state: &SMBusState,
+    length: u8,
+) -> Result<(), SMBusProtocolError> {
+    let reg = bus_state.received_data[0];
+    let count = bus_state.received_data[1];
+    check_width(handler, reg, AccessWidths::BLOCK)?;
+    check_write(handler, reg, None)?;
+    if count > 32 {
+        return Err(SMBusProtocolError::InvalidWriteBlockSize(count));
+    }
+    let end = count as usize + 2;
+    if (length as usize) < end {
+        return Err(SMBusProtocolError::IncompleteWriteBlock(length - 2));
+    }
+    if length as usize > end {
+        return Err(SMBusProtocolError::InvalidWriteBound(count));
     }
+    let slice = &bus_state.received_data[2usize..end];
+    if let Err(_e) = handler.handle_write_block_data(reg, count, slice) {
+        return Err(SMBusProtocolError::InvalidWriteBound(count));
+    }
+    Ok(())
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -157,6 +928,47 @@ pub enum Direction {
     SlaveToMaster,
 }
 
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum AddressingMode {
+    /// SMBus: the first byte of a write is the command code.
+    #[default]
+    Command,
+    /// EEPROM style: the first two bytes of a write form a 16-bit address in the
+    /// given byte order, most devices send the high byte first.
+    Wide(ByteOrder),
+    /// I2C register file: the first byte of a write sets a register pointer,
+    /// every further byte written or read accesses the register at the pointer
+    /// and advances it. After `last` the pointer wraps to `wrap_to`, with `None`
+    /// accesses past `last` are rejected. The pointer is kept across transactions.
+    Sequential { last: u8, wrap_to: Option<u8> },
+    /// Plain I2C framing without command codes: every byte goes to
+    /// `handle_raw_write`/`handle_raw_read` with its index in the transfer.
+    Raw,
+}
+
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum ByteOrder {
+    #[default]
+    LittleEndian,
+    BigEndian,
+}
+
+impl ByteOrder {
+    pub fn word_from_bytes(self, bytes: [u8; 2]) -> u16 {
+        match self {
+            ByteOrder::LittleEndian => u16::from_le_bytes(bytes),
+            ByteOrder::BigEndian => u16::from_be_bytes(bytes),
+        }
+    }
+
+    pub fn word_to_bytes(self, word: u16) -> [u8; 2] {
+        match self {
+            ByteOrder::LittleEndian => word.to_le_bytes(),
+            ByteOrder::BigEndian => word.to_be_bytes(),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum I2cEvent<'a> {
     Initiated { direction: Direction },
@@ -170,9 +982,22 @@ enum StatefulTransfer {
     Byte(u8),
     Word(u16),
     Block(u8),
+    /// Response of a block process call to the block held in `received_data`.
+    ProcessCall,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct BlockStream {
+    register: u8,
+    count: u8,
+    offset: u8,
+    /// PEC of the bytes received so far.
+    crc: u8,
+    pec: Option<u8>,
 }
 
-const RECEIVE_BUFFER_SIZE: u8 = 34;
+/// Command code, byte count, 32 data bytes and a PEC.
+const RECEIVE_BUFFER_SIZE: u8 = 35;
 
 #[derive(Debug)]
 pub struct SMBusState {
@@ -180,6 +1005,14 @@ pub struct SMBusState {
     received_data: [u8; RECEIVE_BUFFER_SIZE as usize],
     direction: Option<Direction>,
     current_transfer: Option<StatefulTransfer>,
+    stream: Option<BlockStream>,
+    read_register: Option<u8>,
+    bytes_read: u8,
+    /// PEC of a read in progress, until it has been sent.
+    read_pec: Option<u8>,
+    aborted: bool,
+    open: bool,
+    pointer: u16,
 }
 
 impl Default for SMBusState {
@@ -189,6 +1022,13 @@ impl Default for SMBusState {
             received_data: [0; RECEIVE_BUFFER_SIZE as usize],
             direction: None,
             current_transfer: None,
+            stream: None,
+            read_register: None,
+            bytes_read: 0,
+            read_pec: None,
+            aborted: false,
+            open: false,
+            pointer: 0,
         }
     }
 }
@@ -203,7 +1043,21 @@ pub enum SMBusProtocolError {
     InvalidWriteBound(u8),
     InvalidReadBound(u8),
     InvalidWriteBlockSize(u8),
+    IncompleteWriteBlock(u8),
     InvalidReadBlockSize(u8),
     InvalidReadRegister(u8),
     InvalidWriteRegister(u8),
+    ReadOnlyRegister(u8),
+    WriteOnlyRegister(u8),
+    RegisterLocked(u8),
+    ReservedBitsSet(u8),
+    /// The transfer width is not one the register accepts.
+    InvalidAccessWidth(u8),
+    /// A wide addressed transfer ended after the first address byte.
+    IncompleteAddress,
+    InvalidMemoryAddress(u16),
+    /// The PEC byte of a write does not match its contents.
+    InvalidPec,
+    /// A write to a handler which requires PEC came without one.
+    MissingPec,
 }
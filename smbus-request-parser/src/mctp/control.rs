@@ -0,0 +1,192 @@
+//! MCTP control messages (DSP0236) answered by [`Mctp::handle_control`].
+
+use super::{Mctp, Packets, BROADCAST_EID, NULL_EID};
+
+pub const MESSAGE_TYPE: u8 = 0x00;
+/// Bytes of a control response, enough for one packet.
+pub const RESPONSE_SIZE: usize = super::BASELINE_MTU;
+/// Message type of [`ControlEndpoint::versions`] for the MCTP base specification.
+pub const BASE_SPECIFICATION: u8 = 0xff;
+
+/// Bits of the byte after the message type.
+pub mod header {
+    pub const REQUEST: u8 = 1 << 7;
+    pub const DATAGRAM: u8 = 1 << 6;
+    pub const INSTANCE_ID: u8 = 0x1f;
+}
+
+pub mod command {
+    pub const SET_ENDPOINT_ID: u8 = 0x01;
+    pub const GET_ENDPOINT_ID: u8 = 0x02;
+    pub const GET_ENDPOINT_UUID: u8 = 0x03;
+    pub const GET_MCTP_VERSION_SUPPORT: u8 = 0x04;
+    pub const GET_MESSAGE_TYPE_SUPPORT: u8 = 0x05;
+}
+
+pub mod completion {
+    pub const SUCCESS: u8 = 0x00;
+    pub const ERROR: u8 = 0x01;
+    pub const ERROR_INVALID_DATA: u8 = 0x02;
+    pub const ERROR_INVALID_LENGTH: u8 = 0x03;
+    pub const ERROR_NOT_READY: u8 = 0x04;
+    pub const ERROR_UNSUPPORTED_CMD: u8 = 0x05;
+    /// Get MCTP Version Support for a message type without versions.
+    pub const MESSAGE_TYPE_NOT_SUPPORTED: u8 = 0x80;
+}
+
+/// Operations of Set Endpoint ID.
+pub mod operation {
+    pub const SET_EID: u8 = 0;
+    pub const FORCE_EID: u8 = 1;
+    pub const RESET_EID: u8 = 2;
+    pub const SET_DISCOVERED_FLAG: u8 = 3;
+}
+
+/// Version number as reported by Get MCTP Version Support.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Version(pub [u8; 4]);
+
+impl Version {
+    /// Version of DSP0236 this responder implements.
+    pub const V1_3_1: Self = Self::new(1, 3, 1);
+
+    /// Single digit `major.minor.update`, without alpha.
+    pub const fn new(major: u8, minor: u8, update: u8) -> Self {
+        Self([0xf0 | major, 0xf0 | minor, 0xf0 | update, 0x00])
+    }
+}
+
+/// Identity of an endpoint reported to the bus owner.
+pub trait ControlEndpoint {
+    fn uuid(&self) -> [u8; 16];
+
+    /// Message types supported besides control messages.
+    fn message_types(&self) -> &[u8] {
+        &[]
+    }
+
+    /// Versions supported of `message_type`, or of the base specification
+    /// for [`BASE_SPECIFICATION`].
+    fn versions(&self, message_type: u8) -> &[Version] {
+        match message_type {
+            BASE_SPECIFICATION | MESSAGE_TYPE => &[Version::V1_3_1],
+            _ => &[],
+        }
+    }
+}
+
+/// Write `items` as a count followed by the items, as many as fit in `response`.
+fn list<'a>(response: &mut [u8], items: impl Iterator<Item = &'a [u8]>) -> usize {
+    let mut len = 1;
+    let mut count = 0;
+    for item in items {
+        if len + item.len() > response.len() {
+            break;
+        }
+        response[len..len + item.len()].copy_from_slice(item);
+        len += item.len();
+        count += 1;
+    }
+    response[0] = count;
+    len
+}
+
+impl<const N: usize> Mctp<N> {
+    /// Answer the pending message if it is a control request: the request is
+    /// released and the packets of the response, written to `buffer`, are returned.
+    /// Other messages are left pending; control datagrams and responses are dropped.
+    pub fn handle_control<'b, E: ControlEndpoint>(
+        &mut self,
+        endpoint: &E,
+        buffer: &'b mut [u8; RESPONSE_SIZE],
+    ) -> Option<Packets<'b>> {
+        let message = self.message()?;
+        if message.message_type() != MESSAGE_TYPE {
+            return None;
+        }
+        let (source, tag) = (message.source, message.tag);
+        let request = message.body();
+        let respond = request.len() >= 2
+            && request[0] & header::REQUEST != 0
+            && request[0] & header::DATAGRAM == 0
+            && message.tag_owner;
+        let mut eid = None;
+        let mut len = 0;
+        if respond {
+            buffer[..3].copy_from_slice(&[
+                MESSAGE_TYPE,
+                request[0] & header::INSTANCE_ID,
+                request[1],
+            ]);
+            let (completion, data) = buffer[3..].split_first_mut().unwrap();
+            let (code, data_len) =
+                self.control_command(endpoint, request[1], &request[2..], data, &mut eid);
+            *completion = code;
+            len = 4 + data_len;
+        }
+        self.release();
+        if let Some(eid) = eid {
+            self.set_eid(eid);
+        }
+        respond.then(|| self.transmit(source, tag, false, &buffer[..len]))
+    }
+
+    /// Completion code and response data length of a control command.
+    fn control_command<E: ControlEndpoint>(
+        &self,
+        endpoint: &E,
+        command: u8,
+        request: &[u8],
+        response: &mut [u8],
+        eid: &mut Option<u8>,
+    ) -> (u8, usize) {
+        match command {
+            command::SET_ENDPOINT_ID => {
+                let [operation, new_eid, ..] = *request else {
+                    return (completion::ERROR_INVALID_LENGTH, 0);
+                };
+                let setting = match operation & 0x03 {
+                    operation::SET_EID | operation::FORCE_EID => {
+                        if new_eid == NULL_EID || new_eid == BROADCAST_EID {
+                            return (completion::ERROR_INVALID_DATA, 0);
+                        }
+                        *eid = Some(new_eid);
+                        new_eid
+                    }
+                    operation::SET_DISCOVERED_FLAG => self.eid(),
+                    _ => return (completion::ERROR_INVALID_DATA, 0),
+                };
+                // Assignment accepted, no EID pool.
+                response[..3].copy_from_slice(&[0x00, setting, 0x00]);
+                (completion::SUCCESS, 3)
+            }
+            command::GET_ENDPOINT_ID => {
+                // Simple endpoint with a dynamic EID, no medium specific information.
+                response[..3].copy_from_slice(&[self.eid(), 0x00, 0x00]);
+                (completion::SUCCESS, 3)
+            }
+            command::GET_ENDPOINT_UUID => {
+                response[..16].copy_from_slice(&endpoint.uuid());
+                (completion::SUCCESS, 16)
+            }
+            command::GET_MCTP_VERSION_SUPPORT => {
+                let [message_type, ..] = *request else {
+                    return (completion::ERROR_INVALID_LENGTH, 0);
+                };
+                let versions = endpoint.versions(message_type);
+                if versions.is_empty() {
+                    return (completion::MESSAGE_TYPE_NOT_SUPPORTED, 0);
+                }
+                (
+                    completion::SUCCESS,
+                    list(response, versions.iter().map(|version| &version.0[..])),
+                )
+            }
+            command::GET_MESSAGE_TYPE_SUPPORT => {
+                let types = endpoint.message_types();
+                (completion::SUCCESS, list(response, types.chunks(1)))
+            }
+            _ => (completion::ERROR_UNSUPPORTED_CMD, 0),
+        }
+    }
+}
@@ -0,0 +1,346 @@
+//! MCTP over SMBus (DSP0237).
+//!
+//! Packets are block writes to [`COMMAND_CODE`] with a PEC. The binding reassembles
+//! them into messages; responses are returned as packets for the firmware to send
+//! as bus master.
+
+use crate::{
+    pec,
+    register_map::{Access, AccessWidths, RegisterPolicy},
+    CommandHandler,
+};
+
+pub mod control;
+
+#[cfg(test)]
+mod test;
+
+pub const COMMAND_CODE: u8 = 0x0f;
+/// Version of the MCTP transport header.
+pub const HEADER_VERSION: u8 = 0x01;
+/// Payload bytes of a packet at the baseline transmission unit.
+pub const BASELINE_MTU: usize = 64;
+pub const NULL_EID: u8 = 0x00;
+pub const BROADCAST_EID: u8 = 0xff;
+
+/// Source address byte and transport header.
+const PACKET_HEADER: usize = 5;
+/// Command code, byte count, header, payload and PEC.
+const MAX_PACKET: usize = 2 + PACKET_HEADER + BASELINE_MTU + 1;
+
+/// Bits of the last transport header byte.
+pub mod flags {
+    pub const SOM: u8 = 1 << 7;
+    pub const EOM: u8 = 1 << 6;
+    pub const SEQUENCE_SHIFT: u8 = 4;
+    pub const TAG_OWNER: u8 = 1 << 3;
+    pub const TAG: u8 = 0x07;
+}
+
+/// Integrity check bit of the message type byte.
+pub const INTEGRITY_CHECK: u8 = 0x80;
+
+/// The other end of a message.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Peer {
+    /// 7-bit SMBus address.
+    pub address: u8,
+    pub eid: u8,
+}
+
+/// A reassembled message.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Message<'a> {
+    pub source: Peer,
+    pub destination_eid: u8,
+    pub tag: u8,
+    pub tag_owner: bool,
+    /// Message type byte followed by the message body.
+    pub payload: &'a [u8],
+}
+
+impl Message<'_> {
+    pub fn message_type(&self) -> u8 {
+        self.payload[0] & !INTEGRITY_CHECK
+    }
+
+    pub fn integrity_check(&self) -> bool {
+        self.payload[0] & INTEGRITY_CHECK != 0
+    }
+
+    pub fn body(&self) -> &[u8] {
+        &self.payload[1..]
+    }
+}
+
+/// Message being reassembled, or complete when `ready`.
+#[derive(Debug, Copy, Clone)]
+struct Assembly {
+    source: Peer,
+    destination_eid: u8,
+    tag: u8,
+    tag_owner: bool,
+    next_sequence: u8,
+    len: usize,
+    ready: bool,
+}
+
+/// MCTP over SMBus endpoint receiving messages of up to `N` bytes.
+#[derive(Debug, Clone)]
+pub struct Mctp<const N: usize> {
+    address: u8,
+    eid: u8,
+    packet: [u8; MAX_PACKET],
+    packet_len: usize,
+    assembly: Option<Assembly>,
+    buffer: [u8; N],
+}
+
+impl<const N: usize> Mctp<N> {
+    /// Endpoint at the 7-bit SMBus `address`, with `eid` or [`NULL_EID`] until one is assigned.
+    pub fn new(address: u8, eid: u8) -> Self {
+        Self {
+            address,
+            eid,
+            packet: [0; MAX_PACKET],
+            packet_len: 0,
+            assembly: None,
+            buffer: [0; N],
+        }
+    }
+
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    pub fn eid(&self) -> u8 {
+        self.eid
+    }
+
+    pub fn set_eid(&mut self, eid: u8) {
+        self.eid = eid;
+    }
+
+    /// Oldest complete message; further messages are dropped until it is released.
+    pub fn message(&self) -> Option<Message<'_>> {
+        let assembly = self.assembly.filter(|assembly| assembly.ready)?;
+        Some(Message {
+            source: assembly.source,
+            destination_eid: assembly.destination_eid,
+            tag: assembly.tag,
+            tag_owner: assembly.tag_owner,
+            payload: &self.buffer[..assembly.len],
+        })
+    }
+
+    /// Free the buffer of the message returned by [`Mctp::message`].
+    pub fn release(&mut self) {
+        if self.assembly.is_some_and(|assembly| assembly.ready) {
+            self.assembly = None;
+        }
+    }
+
+    /// Packets carrying `payload` to `destination`, which start with a
+    /// message type byte.
+    pub fn transmit<'a>(
+        &self,
+        destination: Peer,
+        tag: u8,
+        tag_owner: bool,
+        payload: &'a [u8],
+    ) -> Packets<'a> {
+        Packets {
+            source: Peer {
+                address: self.address,
+                eid: self.eid,
+            },
+            destination,
+            tag: tag & flags::TAG,
+            tag_owner,
+            payload,
+            sequence: 0,
+            first: true,
+        }
+    }
+
+    /// Packets carrying the response `payload` to `request`.
+    pub fn respond<'a>(&self, request: &Message, payload: &'a [u8]) -> Packets<'a> {
+        self.transmit(request.source, request.tag, false, payload)
+    }
+
+    fn accepts(&self, eid: u8) -> bool {
+        eid == self.eid || eid == NULL_EID || eid == BROADCAST_EID
+    }
+
+    /// Add the packet in `self.packet` to the message; malformed, misdirected
+    /// and out of sequence packets are dropped.
+    fn receive_packet(&mut self) -> Result<(), ()> {
+        let packet = &self.packet[..self.packet_len];
+        if packet.len() <= PACKET_HEADER || packet[0] & 1 == 0 {
+            return Err(());
+        }
+        let header = &packet[1..PACKET_HEADER];
+        if header[0] & 0x0f != HEADER_VERSION || !self.accepts(header[1]) {
+            return Err(());
+        }
+        let source = Peer {
+            address: packet[0] >> 1,
+            eid: header[2],
+        };
+        let bits = header[3];
+        let sequence = (bits >> flags::SEQUENCE_SHIFT) & 0x03;
+        let tag = bits & flags::TAG;
+        let tag_owner = bits & flags::TAG_OWNER != 0;
+        let payload = &packet[PACKET_HEADER..];
+
+        let mut assembly = match self.assembly {
+            Some(assembly) if assembly.ready => return Err(()),
+            _ if bits & flags::SOM != 0 => Assembly {
+                source,
+                destination_eid: header[1],
+                tag,
+                tag_owner,
+                next_sequence: sequence,
+                len: 0,
+                ready: false,
+            },
+            Some(assembly)
+                if assembly.source.eid == source.eid
+                    && assembly.tag == tag
+                    && assembly.tag_owner == tag_owner =>
+            {
+                assembly
+            }
+            _ => return Err(()),
+        };
+        let end = assembly.len + payload.len();
+        if assembly.next_sequence != sequence || end > N {
+            self.assembly = None;
+            return Err(());
+        }
+        self.buffer[assembly.len..end].copy_from_slice(payload);
+        assembly.len = end;
+        assembly.next_sequence = (sequence + 1) & 0x03;
+        assembly.ready = bits & flags::EOM != 0;
+        self.assembly = Some(assembly);
+        Ok(())
+    }
+}
+
+impl<const N: usize> CommandHandler for Mctp<N> {
+    type Error = ();
+
+    fn is_streamed_block_write(&self, reg: u8) -> bool {
+        reg == COMMAND_CODE
+    }
+
+    fn handle_write_block_start(&mut self, reg: u8, count: u8) -> Result<(), ()> {
+        if reg != COMMAND_CODE || count as usize > PACKET_HEADER + BASELINE_MTU {
+            return Err(());
+        }
+        self.packet_len = count as usize;
+        Ok(())
+    }
+
+    fn handle_write_block_chunk(&mut self, _reg: u8, offset: u8, data: &[u8]) -> Result<(), ()> {
+        let start = offset as usize;
+        self.packet[start..start + data.len()].copy_from_slice(data);
+        Ok(())
+    }
+
+    fn handle_write_block_commit(&mut self, _reg: u8) -> Result<(), ()> {
+        self.receive_packet()
+    }
+
+    fn pec_address(&self) -> Option<u8> {
+        Some(self.address)
+    }
+
+    fn pec_required(&self) -> bool {
+        true
+    }
+
+    fn register_policy(&self, reg: u8) -> Option<RegisterPolicy> {
+        (reg == COMMAND_CODE).then_some(RegisterPolicy {
+            widths: AccessWidths::BLOCK,
+            ..RegisterPolicy::new(Access::WriteOnly)
+        })
+    }
+}
+
+/// One MCTP packet as written by the bus master after the address byte,
+/// from the command code to the PEC.
+#[derive(Debug, Clone)]
+pub struct Packet {
+    bytes: [u8; MAX_PACKET],
+    len: usize,
+}
+
+impl Packet {
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+}
+
+/// Iterator splitting a message into [`Packet`]s of at most [`BASELINE_MTU`] payload bytes.
+#[derive(Debug, Clone)]
+pub struct Packets<'a> {
+    source: Peer,
+    destination: Peer,
+    tag: u8,
+    tag_owner: bool,
+    payload: &'a [u8],
+    sequence: u8,
+    first: bool,
+}
+
+impl Packets<'_> {
+    /// 7-bit address to send the packets to.
+    pub fn destination_address(&self) -> u8 {
+        self.destination.address
+    }
+}
+
+impl Iterator for Packets<'_> {
+    type Item = Packet;
+
+    fn next(&mut self) -> Option<Packet> {
+        if self.payload.is_empty() {
+            return None;
+        }
+        let (chunk, rest) = self.payload.split_at(self.payload.len().min(BASELINE_MTU));
+        let mut bits = self.sequence << flags::SEQUENCE_SHIFT | self.tag;
+        if self.first {
+            bits |= flags::SOM;
+        }
+        if rest.is_empty() {
+            bits |= flags::EOM;
+        }
+        if self.tag_owner {
+            bits |= flags::TAG_OWNER;
+        }
+        let mut bytes = [0; MAX_PACKET];
+        let header = [
+            COMMAND_CODE,
+            (PACKET_HEADER + chunk.len()) as u8,
+            pec::read_address(self.source.address),
+            HEADER_VERSION,
+            self.destination.eid,
+            self.source.eid,
+            bits,
+        ];
+        bytes[..header.len()].copy_from_slice(&header);
+        let end = header.len() + chunk.len();
+        bytes[header.len()..end].copy_from_slice(chunk);
+        let crc = pec::compute(&[pec::write_address(self.destination.address)]);
+        bytes[end] = pec::update(crc, &bytes[..end]);
+
+        self.payload = rest;
+        self.sequence = (self.sequence + 1) & 0x03;
+        self.first = false;
+        Some(Packet {
+            bytes,
+            len: end + 1,
+        })
+    }
+}
@@ -0,0 +1,278 @@
+use crate::mctp::control::*;
+use crate::mctp::*;
+use crate::test::{read, write};
+use crate::*;
+
+const DEVICE: Peer = Peer {
+    address: 0x1d,
+    eid: 0x09,
+};
+const BMC: Peer = Peer {
+    address: 0x10,
+    eid: 0x08,
+};
+
+fn send<const N: usize>(
+    target: &mut Mctp<N>,
+    mut packets: Packets,
+) -> Result<(), SMBusProtocolError> {
+    let mut bus_state = SMBusState::default();
+    packets.try_for_each(|packet| write(target, &mut bus_state, packet.bytes()))
+}
+
+#[test]
+fn test_packet_format() {
+    let bmc = Mctp::<64>::new(BMC.address, BMC.eid);
+    let mut packets = bmc.transmit(DEVICE, 3, true, &[0x7e, 0x01]);
+    assert_eq!(DEVICE.address, packets.destination_address());
+
+    let packet = packets.next().unwrap();
+    let expected = [0x0f, 7, 0x21, 0x01, 0x09, 0x08, 0xcb, 0x7e, 0x01];
+    assert_eq!(&expected[..], &packet.bytes()[..9]);
+    let crc = pec::update(pec::compute(&[0x3a]), &expected);
+    assert_eq!(&[crc][..], &packet.bytes()[9..]);
+    assert!(packets.next().is_none());
+}
+
+#[test]
+fn test_reassembly() {
+    let bmc = Mctp::<256>::new(BMC.address, BMC.eid);
+    let mut device = Mctp::<256>::new(DEVICE.address, DEVICE.eid);
+    let mut payload = [0u8; 150];
+    for (i, byte) in payload.iter_mut().enumerate() {
+        *byte = i as u8;
+    }
+    payload[0] = 0x85;
+
+    assert_eq!(3, bmc.transmit(DEVICE, 1, true, &payload).count());
+    send(&mut device, bmc.transmit(DEVICE, 1, true, &payload)).unwrap();
+    let message = device.message().unwrap();
+    assert_eq!(BMC, message.source);
+    assert_eq!((1, true), (message.tag, message.tag_owner));
+    assert_eq!(0x05, message.message_type());
+    assert!(message.integrity_check());
+    assert_eq!(&payload[..], message.payload);
+
+    // Dropped while the previous message is pending.
+    let result = send(&mut device, bmc.transmit(DEVICE, 2, true, &[0x7e]));
+    assert_eq!(
+        Err(SMBusProtocolError::InvalidWriteRegister(COMMAND_CODE)),
+        result
+    );
+    device.release();
+    assert_eq!(None, device.message());
+
+    let mut bus_state = SMBusState::default();
+    let packets: Vec<Packet> = bmc.transmit(DEVICE, 1, true, &payload).collect();
+    write(&mut device, &mut bus_state, packets[0].bytes()).unwrap();
+    let result = write(&mut device, &mut bus_state, packets[2].bytes());
+    assert!(result.is_err());
+    assert_eq!(None, device.message());
+}
+
+#[test]
+fn test_filtering() {
+    let bmc = Mctp::<64>::new(BMC.address, BMC.eid);
+    let mut device = Mctp::<64>::new(DEVICE.address, NULL_EID);
+    let mut bus_state = SMBusState::default();
+
+    let other = Peer {
+        eid: 0x20,
+        ..DEVICE
+    };
+    assert!(send(&mut device, bmc.transmit(other, 0, true, &[0x00])).is_err());
+    assert_eq!(None, device.message());
+
+    let null = Peer {
+        eid: NULL_EID,
+        ..DEVICE
+    };
+    let packet = bmc
+        .transmit(null, 0, true, &[0x00, 0x80, 0x02])
+        .next()
+        .unwrap();
+    let mut bytes = [0; 16];
+    let len = packet.bytes().len();
+    bytes[..len].copy_from_slice(packet.bytes());
+    bytes[len - 1] ^= 0xff;
+    let result = write(&mut device, &mut bus_state, &bytes[..len]);
+    assert_eq!(Err(SMBusProtocolError::InvalidPec), result);
+    assert_eq!(None, device.message());
+
+    write(&mut device, &mut bus_state, packet.bytes()).unwrap();
+    assert_eq!(&[0x80, 0x02][..], device.message().unwrap().body());
+
+    let mut response = [0; 1];
+    let result = read(&mut device, &mut bus_state, &[COMMAND_CODE], &mut response);
+    assert_eq!(
+        Err(SMBusProtocolError::WriteOnlyRegister(COMMAND_CODE)),
+        result
+    );
+}
+
+#[test]
+fn test_packet_without_pec_is_dropped() {
+    let bmc = Mctp::<64>::new(BMC.address, BMC.eid);
+    let mut device = Mctp::<64>::new(DEVICE.address, DEVICE.eid);
+    let mut bus_state = SMBusState::default();
+    let packet = bmc.transmit(DEVICE, 0, true, &[0x7e]).next().unwrap();
+    let len = packet.bytes().len();
+
+    let result = write(&mut device, &mut bus_state, &packet.bytes()[..len - 1]);
+    assert_eq!(Err(SMBusProtocolError::MissingPec), result);
+    assert_eq!(None, device.message());
+
+    let mut bytes = [0; 16];
+    bytes[..len].copy_from_slice(packet.bytes());
+    let result = write(&mut device, &mut bus_state, &bytes[..len + 1]);
+    assert_eq!(
+        Err(SMBusProtocolError::InvalidWriteBound(len as u8 - 3)),
+        result
+    );
+    assert_eq!(None, device.message());
+
+    write(&mut device, &mut bus_state, packet.bytes()).unwrap();
+    assert_eq!(&[0x7e][..], device.message().unwrap().payload);
+}
+
+#[test]
+fn test_response() {
+    let mut bmc = Mctp::<64>::new(BMC.address, BMC.eid);
+    let mut device = Mctp::<64>::new(DEVICE.address, DEVICE.eid);
+
+    send(
+        &mut device,
+        bmc.transmit(DEVICE, 5, true, &[0x00, 0x80, 0x02]),
+    )
+    .unwrap();
+    let request = device.message().unwrap();
+    let packets = device.respond(&request, &[0x00, 0x00, 0x02, 0x00]);
+    assert_eq!(BMC.address, packets.destination_address());
+    send(&mut bmc, packets).unwrap();
+    device.release();
+
+    let response = bmc.message().unwrap();
+    assert_eq!(DEVICE, response.source);
+    assert_eq!((5, false), (response.tag, response.tag_owner));
+    assert_eq!(&[0x00, 0x02, 0x00][..], response.body());
+}
+
+struct Drive;
+
+impl ControlEndpoint for Drive {
+    fn uuid(&self) -> [u8; 16] {
+        [0x5a; 16]
+    }
+
+    fn message_types(&self) -> &[u8] {
+        &[0x04]
+    }
+}
+
+/// Send the control `request` from the BMC and return the device's response payload.
+fn control(device: &mut Mctp<64>, request: &[u8]) -> Vec<u8> {
+    let mut bmc = Mctp::<64>::new(BMC.address, BMC.eid);
+    let destination = Peer {
+        eid: device.eid(),
+        ..DEVICE
+    };
+    send(device, bmc.transmit(destination, 2, true, request)).unwrap();
+    let mut buffer = [0; RESPONSE_SIZE];
+    let packets = device.handle_control(&Drive, &mut buffer).unwrap();
+    assert_eq!(None, device.message());
+    send(&mut bmc, packets).unwrap();
+    let response = bmc.message().unwrap();
+    assert_eq!((2, false), (response.tag, response.tag_owner));
+    assert_eq!(device.eid(), response.source.eid);
+    response.payload.to_vec()
+}
+
+#[test]
+fn test_control_endpoint_id() {
+    let mut device = Mctp::<64>::new(DEVICE.address, NULL_EID);
+
+    let response = control(&mut device, &[0x00, 0x81, command::GET_ENDPOINT_ID]);
+    assert_eq!(vec![0x00, 0x01, 0x02, 0x00, NULL_EID, 0x00, 0x00], response);
+
+    let request = [
+        0x00,
+        0x82,
+        command::SET_ENDPOINT_ID,
+        operation::SET_EID,
+        0x0a,
+    ];
+    let response = control(&mut device, &request);
+    assert_eq!(vec![0x00, 0x02, 0x01, 0x00, 0x00, 0x0a, 0x00], response);
+    assert_eq!(0x0a, device.eid());
+
+    let request = [
+        0x00,
+        0x83,
+        command::SET_ENDPOINT_ID,
+        operation::SET_EID,
+        0xff,
+    ];
+    let response = control(&mut device, &request);
+    assert_eq!(
+        vec![0x00, 0x03, 0x01, completion::ERROR_INVALID_DATA],
+        response
+    );
+    let response = control(&mut device, &[0x00, 0x84, command::SET_ENDPOINT_ID, 0x00]);
+    assert_eq!(
+        vec![0x00, 0x04, 0x01, completion::ERROR_INVALID_LENGTH],
+        response
+    );
+    assert_eq!(0x0a, device.eid());
+}
+
+#[test]
+fn test_control_discovery() {
+    let mut device = Mctp::<64>::new(DEVICE.address, DEVICE.eid);
+
+    let response = control(&mut device, &[0x00, 0x80, command::GET_ENDPOINT_UUID]);
+    assert_eq!(&[0x00, 0x00, 0x03, 0x00], &response[..4]);
+    assert_eq!(&[0x5a; 16][..], &response[4..]);
+
+    let request = [0x00, 0x80, command::GET_MCTP_VERSION_SUPPORT, 0xff];
+    let response = control(&mut device, &request);
+    assert_eq!(
+        vec![0x00, 0x00, 0x04, 0x00, 1, 0xf1, 0xf3, 0xf1, 0x00],
+        response
+    );
+    let request = [0x00, 0x80, command::GET_MCTP_VERSION_SUPPORT, 0x04];
+    let response = control(&mut device, &request);
+    assert_eq!(
+        vec![0x00, 0x00, 0x04, completion::MESSAGE_TYPE_NOT_SUPPORTED],
+        response
+    );
+
+    let response = control(
+        &mut device,
+        &[0x00, 0x80, command::GET_MESSAGE_TYPE_SUPPORT],
+    );
+    assert_eq!(vec![0x00, 0x00, 0x05, 0x00, 1, 0x04], response);
+
+    let response = control(&mut device, &[0x00, 0x80, 0x7f]);
+    assert_eq!(
+        vec![0x00, 0x00, 0x7f, completion::ERROR_UNSUPPORTED_CMD],
+        response
+    );
+}
+
+#[test]
+fn test_control_ignores_other_messages() {
+    let bmc = Mctp::<64>::new(BMC.address, BMC.eid);
+    let mut device = Mctp::<64>::new(DEVICE.address, DEVICE.eid);
+    let mut buffer = [0; RESPONSE_SIZE];
+
+    send(&mut device, bmc.transmit(DEVICE, 0, true, &[0x04, 0x01])).unwrap();
+    assert!(device.handle_control(&Drive, &mut buffer).is_none());
+    assert_eq!(0x04, device.message().unwrap().message_type());
+    device.release();
+
+    // A datagram gets no response.
+    let request = [0x00, 0xc0, command::GET_ENDPOINT_ID];
+    send(&mut device, bmc.transmit(DEVICE, 0, true, &request)).unwrap();
+    assert!(device.handle_control(&Drive, &mut buffer).is_none());
+    assert_eq!(None, device.message());
+}
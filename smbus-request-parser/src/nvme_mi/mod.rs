@@ -0,0 +1,198 @@
+//! NVMe-MI Basic Management Command over SMBus.
+//!
+//! The host reads the data structure with block reads at the offsets of
+//! [`STATUS`] and [`IDENTIFICATION`]; each block is followed by its PEC.
+
+use crate::{pec, CommandHandler};
+
+#[cfg(test)]
+mod test;
+
+/// Default 7-bit address of the Basic Management Command.
+pub const ADDRESS: u8 = 0x6a;
+
+/// Offsets of the data structure, used as command codes.
+pub const STATUS: u8 = 0x00;
+pub const IDENTIFICATION: u8 = 0x08;
+pub const VENDOR_SPECIFIC: u8 = 0x20;
+
+/// Send Byte data clearing [`flags::SMBUS_ARBITRATION`].
+pub const CLEAR_ARBITRATION: u8 = 0xff;
+
+/// Bits of the Status Flags.
+pub mod flags {
+    /// Set after a read of the status block up to its PEC, see [`super::CLEAR_ARBITRATION`].
+    pub const SMBUS_ARBITRATION: u8 = 1 << 7;
+    pub const DRIVE_NOT_READY: u8 = 1 << 6;
+    pub const DRIVE_FUNCTIONAL: u8 = 1 << 5;
+    pub const RESET_NOT_REQUIRED: u8 = 1 << 4;
+    pub const PCIE_LINK_ACTIVE: u8 = 1 << 3;
+    /// Reserved bits, reported as 1.
+    pub const RESERVED: u8 = 0x07;
+}
+
+/// Critical warnings of the SMART / Health Information log; the data
+/// structure reports them inverted, a cleared bit meaning the warning is active.
+pub mod warning {
+    pub const SPARE: u8 = 1 << 0;
+    pub const TEMPERATURE: u8 = 1 << 1;
+    pub const RELIABILITY: u8 = 1 << 2;
+    pub const READ_ONLY: u8 = 1 << 3;
+    pub const VOLATILE_BACKUP: u8 = 1 << 4;
+    pub const PMR_READ_ONLY: u8 = 1 << 5;
+}
+
+/// Composite temperature.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Temperature {
+    Celsius(i16),
+    NoData,
+    SensorFailure,
+}
+
+impl Temperature {
+    /// Encoding of the data structure, saturating at 127 and -60 °C.
+    pub const fn byte(self) -> u8 {
+        match self {
+            Temperature::Celsius(celsius) if celsius >= 127 => 0x7f,
+            Temperature::Celsius(celsius) if celsius <= -60 => 0xc4,
+            Temperature::Celsius(celsius) => celsius as i8 as u8,
+            Temperature::NoData => 0x80,
+            Temperature::SensorFailure => 0x81,
+        }
+    }
+}
+
+/// Drive health reported in the status block.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Status {
+    pub ready: bool,
+    pub functional: bool,
+    pub reset_not_required: bool,
+    pub pcie_link_active: bool,
+    /// Active [`warning`]s.
+    pub critical_warnings: u8,
+    pub temperature: Temperature,
+    /// Percentage of the drive life used, may exceed 100.
+    pub drive_life_used: u8,
+}
+
+/// Drive answering the Basic Management Command.
+pub trait Drive {
+    fn status(&mut self) -> Status;
+    /// PCI vendor ID.
+    fn vendor_id(&self) -> u16;
+    /// ASCII, padded with spaces.
+    fn serial_number(&self) -> [u8; 20];
+
+    /// Byte at `offset` of the vendor specific area from [`VENDOR_SPECIFIC`].
+    fn vendor_specific(&mut self, _offset: u8) -> Option<u8> {
+        None
+    }
+}
+
+/// [`CommandHandler`] serving the Basic Management Command data structure of a [`Drive`]
+/// at the 7-bit `address`.
+#[derive(Debug, Clone)]
+pub struct BasicManagement<D> {
+    drive: D,
+    address: u8,
+    arbitration: bool,
+    /// Data structure up to the vendor specific area, captured when a read begins.
+    data: [u8; VENDOR_SPECIFIC as usize],
+}
+
+impl<D: Drive> BasicManagement<D> {
+    pub fn new(drive: D, address: u8) -> Self {
+        Self {
+            drive,
+            address,
+            arbitration: false,
+            data: [0; VENDOR_SPECIFIC as usize],
+        }
+    }
+
+    pub fn drive(&self) -> &D {
+        &self.drive
+    }
+
+    pub fn drive_mut(&mut self) -> &mut D {
+        &mut self.drive
+    }
+
+    pub fn into_inner(self) -> D {
+        self.drive
+    }
+
+    /// PEC of a block read of `block` at `offset`, count byte included.
+    fn block_pec(&self, offset: u8, block: &[u8]) -> u8 {
+        let header = [
+            pec::write_address(self.address),
+            offset,
+            pec::read_address(self.address),
+        ];
+        pec::update(pec::compute(&header), block)
+    }
+
+    fn refresh(&mut self) {
+        let status = self.drive.status();
+        let mut flags = flags::RESERVED;
+        for (set, flag) in [
+            (self.arbitration, flags::SMBUS_ARBITRATION),
+            (!status.ready, flags::DRIVE_NOT_READY),
+            (status.functional, flags::DRIVE_FUNCTIONAL),
+            (status.reset_not_required, flags::RESET_NOT_REQUIRED),
+            (status.pcie_link_active, flags::PCIE_LINK_ACTIVE),
+        ] {
+            if set {
+                flags |= flag;
+            }
+        }
+        let data = &mut self.data;
+        data[..7].copy_from_slice(&[
+            6,
+            flags,
+            !status.critical_warnings,
+            status.temperature.byte(),
+            status.drive_life_used,
+            0,
+            0,
+        ]);
+        data[8] = 22;
+        data[9..11].copy_from_slice(&self.drive.vendor_id().to_be_bytes());
+        data[11..31].copy_from_slice(&self.drive.serial_number());
+        self.data[7] = self.block_pec(STATUS, &self.data[..7]);
+        self.data[31] = self.block_pec(IDENTIFICATION, &self.data[8..31]);
+    }
+}
+
+impl<D: Drive> CommandHandler for BasicManagement<D> {
+    type Error = ();
+
+    fn on_read_begin(&mut self, _reg: u8) {
+        self.refresh();
+    }
+
+    fn handle_read_block_data(&mut self, reg: u8, index: u8) -> Option<u8> {
+        let offset = reg.checked_add(index)?;
+        match self.data.get(offset as usize) {
+            Some(byte) => Some(*byte),
+            None => self.drive.vendor_specific(offset - VENDOR_SPECIFIC),
+        }
+    }
+
+    fn handle_read_complete(&mut self, reg: Option<u8>, count: u8) {
+        // The status block is complete with its count, data and PEC bytes.
+        if reg == Some(STATUS) && count >= 8 {
+            self.arbitration = true;
+        }
+    }
+
+    fn handle_write_byte(&mut self, data: u8) -> Result<(), ()> {
+        if data != CLEAR_ARBITRATION {
+            return Err(());
+        }
+        self.arbitration = false;
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+use crate::nvme_mi::*;
+use crate::test::{read, write};
+use crate::*;
+
+struct Ssd {
+    status: Status,
+}
+
+impl Drive for Ssd {
+    fn status(&mut self) -> Status {
+        self.status
+    }
+
+    fn vendor_id(&self) -> u16 {
+        0x144d
+    }
+
+    fn serial_number(&self) -> [u8; 20] {
+        *b"S4EWNX0R123456      "
+    }
+
+    fn vendor_specific(&mut self, offset: u8) -> Option<u8> {
+        (offset < 4).then_some(0xa0 + offset)
+    }
+}
+
+fn ssd() -> BasicManagement<Ssd> {
+    BasicManagement::new(
+        Ssd {
+            status: Status {
+                ready: true,
+                functional: true,
+                reset_not_required: true,
+                pcie_link_active: true,
+                critical_warnings: warning::TEMPERATURE,
+                temperature: Temperature::Celsius(45),
+                drive_life_used: 3,
+            },
+        },
+        ADDRESS,
+    )
+}
+
+#[test]
+fn test_temperature() {
+    assert_eq!(0x2d, Temperature::Celsius(45).byte());
+    assert_eq!(0xf6, Temperature::Celsius(-10).byte());
+    assert_eq!(0x7f, Temperature::Celsius(130).byte());
+    assert_eq!(0xc4, Temperature::Celsius(-75).byte());
+    assert_eq!(0x80, Temperature::NoData.byte());
+    assert_eq!(0x81, Temperature::SensorFailure.byte());
+}
+
+#[test]
+fn test_status_block() {
+    let mut ssd = ssd();
+    let mut bus_state = SMBusState::default();
+
+    let mut block = [0; 8];
+    read(&mut ssd, &mut bus_state, &[STATUS], &mut block).unwrap();
+    assert_eq!([6, 0x3f, 0xfd, 45, 3, 0, 0], block[..7]);
+    let expected = pec::update(pec::compute(&[0xd4, 0x00, 0xd5]), &block[..7]);
+    assert_eq!(expected, block[7]);
+
+    ssd.drive_mut().status.ready = false;
+    ssd.drive_mut().status.temperature = Temperature::SensorFailure;
+    read(&mut ssd, &mut bus_state, &[STATUS], &mut block).unwrap();
+    let status_flags = flags::SMBUS_ARBITRATION
+        | flags::DRIVE_NOT_READY
+        | flags::DRIVE_FUNCTIONAL
+        | flags::RESET_NOT_REQUIRED
+        | flags::PCIE_LINK_ACTIVE
+        | flags::RESERVED;
+    assert_eq!([6, status_flags, 0xfd, 0x81], block[..4]);
+    let expected = pec::update(pec::compute(&[0xd4, 0x00, 0xd5]), &block[..7]);
+    assert_eq!(expected, block[7]);
+}
+
+#[test]
+fn test_arbitration() {
+    let mut ssd = ssd();
+    let mut bus_state = SMBusState::default();
+    let mut block = [0; 8];
+
+    read(&mut ssd, &mut bus_state, &[STATUS], &mut block[..4]).unwrap();
+    read(&mut ssd, &mut bus_state, &[STATUS], &mut block).unwrap();
+    assert_eq!(0, block[1] & flags::SMBUS_ARBITRATION);
+    read(&mut ssd, &mut bus_state, &[STATUS], &mut block).unwrap();
+    assert_ne!(0, block[1] & flags::SMBUS_ARBITRATION);
+
+    write(&mut ssd, &mut bus_state, &[CLEAR_ARBITRATION]).unwrap();
+    read(&mut ssd, &mut bus_state, &[STATUS], &mut block).unwrap();
+    assert_eq!(0, block[1] & flags::SMBUS_ARBITRATION);
+    assert_eq!(
+        Err(SMBusProtocolError::WriteByteUnsupported),
+        write(&mut ssd, &mut bus_state, &[0x00])
+    );
+}
+
+#[test]
+fn test_identification_and_vendor_area() {
+    let mut ssd = ssd();
+    let mut bus_state = SMBusState::default();
+
+    let mut block = [0; 24];
+    read(&mut ssd, &mut bus_state, &[IDENTIFICATION], &mut block).unwrap();
+    assert_eq!([22, 0x14, 0x4d], block[..3]);
+    assert_eq!(b"S4EWNX0R123456      ", &block[3..23]);
+    let expected = pec::update(pec::compute(&[0xd4, 0x08, 0xd5]), &block[..23]);
+    assert_eq!(expected, block[23]);
+
+    let mut data = [0; 3];
+    read(&mut ssd, &mut bus_state, &[VENDOR_SPECIFIC + 1], &mut data).unwrap();
+    assert_eq!([0xa1, 0xa2, 0xa3], data);
+}
@@ -0,0 +1,498 @@
+use crate::{
+    register_map::{Access, AccessWidths, RegisterPolicy},
+    ByteOrder, CommandHandler, Unsupported,
+};
+
+#[cfg(test)]
+mod test;
+
+/// Command code of the PAGE register.
+pub const PAGE: u8 = 0x00;
+
+/// PAGE value addressing every page at once. Writes are applied to all pages,
+/// reads are rejected.
+pub const ALL_PAGES: u8 = 0xff;
+
+/// A [`CommandHandler`] whose registers are multiplexed behind a PAGE register.
+/// Used through [`Paged`], which keeps the page selection.
+pub trait PagedHandler {
+    type Error: From<Unsupported>;
+
+    /// Number of pages; valid pages are `0..page_count()`.
+    fn page_count(&self) -> u8;
+
+    fn handle_read_byte(&mut self, _page: u8) -> Option<u8> {
+        None
+    }
+
+    fn handle_read_byte_data(&mut self, _page: u8, _reg: u8) -> Option<u8> {
+        None
+    }
+
+    fn handle_read_word_data(&mut self, _page: u8, _reg: u8) -> Option<u16> {
+        None
+    }
+
+    fn handle_read_block_data(&mut self, _page: u8, _reg: u8, _index: u8) -> Option<u8> {
+        None
+    }
+
+    fn handle_read_complete(&mut self, _page: u8, _reg: Option<u8>, _count: u8) {}
+
+    fn handle_block_process_call(
+        &mut self,
+        _page: u8,
+        _reg: u8,
+        _request: &[u8],
+        _index: u8,
+    ) -> Option<u8> {
+        None
+    }
+
+    fn handle_write_byte(&mut self, _page: u8, _data: u8) -> Result<(), Self::Error> {
+        Err(Unsupported.into())
+    }
+
+    fn handle_write_byte_data(
+        &mut self,
+        _page: u8,
+        _reg: u8,
+        _data: u8,
+    ) -> Result<(), Self::Error> {
+        Err(Unsupported.into())
+    }
+
+    fn handle_write_word_data(
+        &mut self,
+        _page: u8,
+        _reg: u8,
+        _data: u16,
+    ) -> Result<(), Self::Error> {
+        Err(Unsupported.into())
+    }
+
+    fn handle_write_block_data(
+        &mut self,
+        _page: u8,
+        _reg: u8,
+        _count: u8,
+        _block: &[u8],
+    ) -> Result<(), Self::Error> {
+        Err(Unsupported.into())
+    }
+
+    fn is_streamed_block_write(&self, _page: u8, _reg: u8) -> bool {
+        false
+    }
+
+    fn handle_write_block_start(
+        &mut self,
+        _page: u8,
+        _reg: u8,
+        _count: u8,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn handle_write_block_chunk(
+        &mut self,
+        _page: u8,
+        _reg: u8,
+        _offset: u8,
+        _chunk: &[u8],
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn handle_write_block_commit(&mut self, _page: u8, _reg: u8) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn handle_write_block_abort(&mut self, _page: u8, _reg: u8) {}
+
+    fn register_policy(&self, _page: u8, _reg: u8) -> Option<RegisterPolicy> {
+        None
+    }
+
+    fn byte_order(&self, _page: u8, _reg: u8) -> ByteOrder {
+        ByteOrder::LittleEndian
+    }
+
+    /// Shared by all pages, as they are behind one device address.
+    fn pec_address(&self) -> Option<u8> {
+        None
+    }
+
+    fn pec_required(&self) -> bool {
+        false
+    }
+
+    fn on_read_begin(&mut self, _page: u8, _reg: u8) {}
+
+    fn on_start(&mut self) {}
+
+    fn on_stop(&mut self) {}
+
+    fn on_abort(&mut self) {}
+
+    fn on_pec_error(&mut self) {}
+}
+
+/// One handler per page, e.g. a [`RegisterMap`](crate::register_map::RegisterMap)
+/// for each page of the device. [`ALL_PAGES`] limits a device to 255 pages,
+/// larger arrays fail to compile:
+///
+/// ```compile_fail
+/// use smbus_request_parser::{paged::Paged, register_map::RegisterMap, CommandHandler};
+///
+/// let pages: [RegisterMap<'static, 0>; 256] =
+///     core::array::from_fn(|_| RegisterMap::new(&[]).unwrap());
+/// let mut device = Paged::new(pages);
+/// device.handle_write_byte_data(0x00, 0x01).unwrap();
+/// ```
+impl<H: CommandHandler, const P: usize> PagedHandler for [H; P] {
+    type Error = H::Error;
+
+    fn page_count(&self) -> u8 {
+        const { assert!(P <= u8::MAX as usize, "at most 255 pages are addressable") };
+        P as u8
+    }
+
+    fn handle_read_byte(&mut self, page: u8) -> Option<u8> {
+        self.get_mut(page as usize)?.handle_read_byte()
+    }
+
+    fn handle_read_byte_data(&mut self, page: u8, reg: u8) -> Option<u8> {
+        self.get_mut(page as usize)?.handle_read_byte_data(reg)
+    }
+
+    fn handle_read_word_data(&mut self, page: u8, reg: u8) -> Option<u16> {
+        self.get_mut(page as usize)?.handle_read_word_data(reg)
+    }
+
+    fn handle_read_block_data(&mut self, page: u8, reg: u8, index: u8) -> Option<u8> {
+        self.get_mut(page as usize)?
+            .handle_read_block_data(reg, index)
+    }
+
+    fn handle_read_complete(&mut self, page: u8, reg: Option<u8>, count: u8) {
+        if let Some(handler) = self.get_mut(page as usize) {
+            handler.handle_read_complete(reg, count);
+        }
+    }
+
+    fn handle_block_process_call(
+        &mut self,
+        page: u8,
+        reg: u8,
+        request: &[u8],
+        index: u8,
+    ) -> Option<u8> {
+        self.get_mut(page as usize)?
+            .handle_block_process_call(reg, request, index)
+    }
+
+    fn handle_write_byte(&mut self, page: u8, data: u8) -> Result<(), Self::Error> {
+        page_mut(self, page)?.handle_write_byte(data)
+    }
+
+    fn handle_write_byte_data(&mut self, page: u8, reg: u8, data: u8) -> Result<(), Self::Error> {
+        page_mut(self, page)?.handle_write_byte_data(reg, data)
+    }
+
+    fn handle_write_word_data(&mut self, page: u8, reg: u8, data: u16) -> Result<(), Self::Error> {
+        page_mut(self, page)?.handle_write_word_data(reg, data)
+    }
+
+    fn handle_write_block_data(
+        &mut self,
+        page: u8,
+        reg: u8,
+        count: u8,
+        block: &[u8],
+    ) -> Result<(), Self::Error> {
+        page_mut(self, page)?.handle_write_block_data(reg, count, block)
+    }
+
+    fn is_streamed_block_write(&self, page: u8, reg: u8) -> bool {
+        self.get(page as usize)
+            .is_some_and(|handler| handler.is_streamed_block_write(reg))
+    }
+
+    fn handle_write_block_start(
+        &mut self,
+        page: u8,
+        reg: u8,
+        count: u8,
+    ) -> Result<(), Self::Error> {
+        page_mut(self, page)?.handle_write_block_start(reg, count)
+    }
+
+    fn handle_write_block_chunk(
+        &mut self,
+        page: u8,
+        reg: u8,
+        offset: u8,
+        chunk: &[u8],
+    ) -> Result<(), Self::Error> {
+        page_mut(self, page)?.handle_write_block_chunk(reg, offset, chunk)
+    }
+
+    fn handle_write_block_commit(&mut self, page: u8, reg: u8) -> Result<(), Self::Error> {
+        page_mut(self, page)?.handle_write_block_commit(reg)
+    }
+
+    fn handle_write_block_abort(&mut self, page: u8, reg: u8) {
+        if let Some(handler) = self.get_mut(page as usize) {
+            handler.handle_write_block_abort(reg);
+        }
+    }
+
+    fn register_policy(&self, page: u8, reg: u8) -> Option<RegisterPolicy> {
+        self.get(page as usize)?.register_policy(reg)
+    }
+
+    fn byte_order(&self, page: u8, reg: u8) -> ByteOrder {
+        self.get(page as usize)
+            .map_or(ByteOrder::LittleEndian, |handler| handler.byte_order(reg))
+    }
+
+    fn pec_address(&self) -> Option<u8> {
+        self.first()?.pec_address()
+    }
+
+    fn pec_required(&self) -> bool {
+        self.first().is_some_and(|handler| handler.pec_required())
+    }
+
+    fn on_read_begin(&mut self, page: u8, reg: u8) {
+        if let Some(handler) = self.get_mut(page as usize) {
+            handler.on_read_begin(reg);
+        }
+    }
+
+    fn on_start(&mut self) {
+        self.iter_mut().for_each(H::on_start);
+    }
+
+    fn on_stop(&mut self) {
+        self.iter_mut().for_each(H::on_stop);
+    }
+
+    fn on_abort(&mut self) {
+        self.iter_mut().for_each(H::on_abort);
+    }
+
+    fn on_pec_error(&mut self) {
+        self.iter_mut().for_each(H::on_pec_error);
+    }
+}
+
+fn page_mut<H: CommandHandler>(pages: &mut [H], page: u8) -> Result<&mut H, H::Error> {
+    pages.get_mut(page as usize).ok_or(Unsupported.into())
+}
+
+/// Adapter keeping the PAGE selection of a [`PagedHandler`] and exposing it as a
+/// [`CommandHandler`]. PAGE itself is a read/write byte register at [`PAGE`].
+#[derive(Debug, Clone)]
+pub struct Paged<H> {
+    handler: H,
+    page: u8,
+}
+
+impl<H: PagedHandler> Paged<H> {
+    pub fn new(handler: H) -> Self {
+        Self { handler, page: 0 }
+    }
+
+    pub fn page(&self) -> u8 {
+        self.page
+    }
+
+    pub fn handler(&self) -> &H {
+        &self.handler
+    }
+
+    pub fn handler_mut(&mut self) -> &mut H {
+        &mut self.handler
+    }
+
+    pub fn into_inner(self) -> H {
+        self.handler
+    }
+
+    /// Page to read from, `None` while all pages are selected.
+    fn read_page(&self) -> Option<u8> {
+        (self.page != ALL_PAGES).then_some(self.page)
+    }
+
+    /// Apply `write` to the selected page, or to every page for [`ALL_PAGES`].
+    /// All pages are written even if one of them fails; the first error is returned.
+    fn write_pages(
+        &mut self,
+        mut write: impl FnMut(&mut H, u8) -> Result<(), H::Error>,
+    ) -> Result<(), H::Error> {
+        if self.page != ALL_PAGES {
+            return write(&mut self.handler, self.page);
+        }
+        let mut result = Ok(());
+        for page in 0..self.handler.page_count() {
+            let written = write(&mut self.handler, page);
+            if result.is_ok() {
+                result = written;
+            }
+        }
+        result
+    }
+}
+
+impl<H: PagedHandler> CommandHandler for Paged<H> {
+    type Error = H::Error;
+
+    fn handle_read_byte(&mut self) -> Option<u8> {
+        let page = self.read_page()?;
+        self.handler.handle_read_byte(page)
+    }
+
+    fn handle_read_byte_data(&mut self, reg: u8) -> Option<u8> {
+        if reg == PAGE {
+            return Some(self.page);
+        }
+        let page = self.read_page()?;
+        self.handler.handle_read_byte_data(page, reg)
+    }
+
+    fn handle_read_word_data(&mut self, reg: u8) -> Option<u16> {
+        let page = self.read_page()?;
+        self.handler.handle_read_word_data(page, reg)
+    }
+
+    fn handle_read_block_data(&mut self, reg: u8, index: u8) -> Option<u8> {
+        let page = self.read_page()?;
+        self.handler.handle_read_block_data(page, reg, index)
+    }
+
+    fn handle_read_complete(&mut self, reg: Option<u8>, count: u8) {
+        if reg == Some(PAGE) {
+            return;
+        }
+        if let Some(page) = self.read_page() {
+            self.handler.handle_read_complete(page, reg, count);
+        }
+    }
+
+    fn handle_block_process_call(&mut self, reg: u8, request: &[u8], index: u8) -> Option<u8> {
+        let page = self.read_page()?;
+        self.handler
+            .handle_block_process_call(page, reg, request, index)
+    }
+
+    fn handle_write_byte(&mut self, data: u8) -> Result<(), Self::Error> {
+        self.write_pages(|handler, page| handler.handle_write_byte(page, data))
+    }
+
+    fn handle_write_byte_data(&mut self, reg: u8, data: u8) -> Result<(), Self::Error> {
+        if reg == PAGE {
+            if data != ALL_PAGES && data >= self.handler.page_count() {
+                return Err(Unsupported.into());
+            }
+            self.page = data;
+            return Ok(());
+        }
+        self.write_pages(|handler, page| handler.handle_write_byte_data(page, reg, data))
+    }
+
+    fn handle_write_word_data(&mut self, reg: u8, data: u16) -> Result<(), Self::Error> {
+        self.write_pages(|handler, page| handler.handle_write_word_data(page, reg, data))
+    }
+
+    fn handle_write_block_data(
+        &mut self,
+        reg: u8,
+        count: u8,
+        block: &[u8],
+    ) -> Result<(), Self::Error> {
+        self.write_pages(|handler, page| handler.handle_write_block_data(page, reg, count, block))
+    }
+
+    /// While all pages are selected the streaming decision of page 0 applies,
+    /// and the stream is written to every page.
+    fn is_streamed_block_write(&self, reg: u8) -> bool {
+        let page = self.read_page().unwrap_or(0);
+        self.handler.is_streamed_block_write(page, reg)
+    }
+
+    fn handle_write_block_start(&mut self, reg: u8, count: u8) -> Result<(), Self::Error> {
+        self.write_pages(|handler, page| handler.handle_write_block_start(page, reg, count))
+    }
+
+    fn handle_write_block_chunk(
+        &mut self,
+        reg: u8,
+        offset: u8,
+        chunk: &[u8],
+    ) -> Result<(), Self::Error> {
+        self.write_pages(|handler, page| handler.handle_write_block_chunk(page, reg, offset, chunk))
+    }
+
+    fn handle_write_block_commit(&mut self, reg: u8) -> Result<(), Self::Error> {
+        self.write_pages(|handler, page| handler.handle_write_block_commit(page, reg))
+    }
+
+    fn handle_write_block_abort(&mut self, reg: u8) {
+        let _ = self.write_pages(|handler, page| {
+            handler.handle_write_block_abort(page, reg);
+            Ok(())
+        });
+    }
+
+    /// While all pages are selected the policy of page 0 applies.
+    fn register_policy(&self, reg: u8) -> Option<RegisterPolicy> {
+        if reg == PAGE {
+            return Some(RegisterPolicy {
+                widths: AccessWidths::BYTE,
+                ..RegisterPolicy::new(Access::ReadWrite)
+            });
+        }
+        let page = self.read_page().unwrap_or(0);
+        self.handler.register_policy(page, reg)
+    }
+
+    fn byte_order(&self, reg: u8) -> ByteOrder {
+        let page = self.read_page().unwrap_or(0);
+        self.handler.byte_order(page, reg)
+    }
+
+    fn pec_address(&self) -> Option<u8> {
+        self.handler.pec_address()
+    }
+
+    fn pec_required(&self) -> bool {
+        self.handler.pec_required()
+    }
+
+    fn on_read_begin(&mut self, reg: u8) {
+        if reg == PAGE {
+            return;
+        }
+        if let Some(page) = self.read_page() {
+            self.handler.on_read_begin(page, reg);
+        }
+    }
+
+    fn on_start(&mut self) {
+        self.handler.on_start();
+    }
+
+    fn on_stop(&mut self) {
+        self.handler.on_stop();
+    }
+
+    fn on_abort(&mut self) {
+        self.handler.on_abort();
+    }
+
+    fn on_pec_error(&mut self) {
+        self.handler.on_pec_error();
+    }
+}
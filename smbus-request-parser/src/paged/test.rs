@@ -0,0 +1,146 @@
+use crate::paged::*;
+use crate::register_map::*;
+use crate::test::{read, write};
+use crate::*;
+
+static REGISTERS: [RegisterDescriptor; 2] = [
+    RegisterDescriptor {
+        code: 0x21,
+        width: Width::Word,
+        access: Access::ReadWrite,
+        reset: &[0x00, 0x10],
+        reserved: 0,
+        offset: 0,
+    },
+    RegisterDescriptor {
+        code: 0x8b,
+        width: Width::Word,
+        access: Access::ReadOnly,
+        reset: &[],
+        reserved: 0,
+        offset: 2,
+    },
+];
+
+fn rails() -> Paged<[RegisterMap<'static, 4>; 2]> {
+    let map = RegisterMap::new(&REGISTERS).unwrap();
+    Paged::new([map.clone(), map])
+}
+
+#[test]
+fn test_page_selects_register_map() {
+    let mut rails = rails();
+    let mut bus_state = SMBusState::default();
+    rails.handler_mut()[1].set_word(0x8b, 0x0ccc).unwrap();
+
+    write(&mut rails, &mut bus_state, &[PAGE, 1]).unwrap();
+    assert_eq!(1, rails.page());
+    let mut page = [0];
+    read(&mut rails, &mut bus_state, &[PAGE], &mut page).unwrap();
+    assert_eq!([1], page);
+
+    let mut word = [0; 2];
+    read(&mut rails, &mut bus_state, &[0x8b], &mut word).unwrap();
+    assert_eq!([0xcc, 0x0c], word);
+
+    write(&mut rails, &mut bus_state, &[0x21, 0x00, 0x0c]).unwrap();
+    assert_eq!(Some(0x1000), rails.handler()[0].word(0x21));
+    assert_eq!(Some(0x0c00), rails.handler()[1].word(0x21));
+}
+
+#[test]
+fn test_invalid_page() {
+    let mut rails = rails();
+    let mut bus_state = SMBusState::default();
+
+    let result = write(&mut rails, &mut bus_state, &[PAGE, 2]);
+    assert_eq!(Err(SMBusProtocolError::InvalidWriteRegister(PAGE)), result);
+    assert_eq!(0, rails.page());
+
+    let result = write(&mut rails, &mut bus_state, &[PAGE, 0x01, 0x00]);
+    assert_eq!(Err(SMBusProtocolError::InvalidAccessWidth(PAGE)), result);
+}
+
+#[test]
+fn test_all_pages() {
+    let mut rails = rails();
+    let mut bus_state = SMBusState::default();
+
+    write(&mut rails, &mut bus_state, &[PAGE, ALL_PAGES]).unwrap();
+    write(&mut rails, &mut bus_state, &[0x21, 0x34, 0x12]).unwrap();
+    assert_eq!(Some(0x1234), rails.handler()[0].word(0x21));
+    assert_eq!(Some(0x1234), rails.handler()[1].word(0x21));
+
+    let mut word = [0; 2];
+    let result = read(&mut rails, &mut bus_state, &[0x21], &mut word);
+    assert_eq!(Err(SMBusProtocolError::InvalidReadRegister(0x21)), result);
+
+    let result = write(&mut rails, &mut bus_state, &[0x8b, 0x00, 0x00]);
+    assert_eq!(Err(SMBusProtocolError::ReadOnlyRegister(0x8b)), result);
+}
+
+/// Rail with a streamed firmware upload and a measurement latched per read.
+#[derive(Default)]
+struct Rail {
+    measurement: u16,
+    latched: Option<u16>,
+    upload: Vec<u8>,
+    committed: bool,
+}
+
+impl CommandHandler for Rail {
+    type Error = ();
+
+    fn handle_read_word_data(&mut self, reg: u8) -> Option<u16> {
+        (reg == 0x8c).then_some(self.latched?)
+    }
+
+    fn is_streamed_block_write(&self, reg: u8) -> bool {
+        reg == 0x40
+    }
+
+    fn handle_write_block_start(&mut self, _reg: u8, _count: u8) -> Result<(), ()> {
+        self.upload.clear();
+        Ok(())
+    }
+
+    fn handle_write_block_chunk(&mut self, _reg: u8, _offset: u8, chunk: &[u8]) -> Result<(), ()> {
+        self.upload.extend_from_slice(chunk);
+        Ok(())
+    }
+
+    fn handle_write_block_commit(&mut self, _reg: u8) -> Result<(), ()> {
+        self.committed = true;
+        Ok(())
+    }
+
+    fn on_read_begin(&mut self, reg: u8) {
+        if reg == 0x8c {
+            self.latched = Some(self.measurement);
+        }
+    }
+
+    fn on_stop(&mut self) {
+        self.latched = None;
+    }
+}
+
+#[test]
+fn test_streamed_write_and_latched_read() {
+    let mut rails = Paged::new([Rail::default(), Rail::default()]);
+    let mut bus_state = SMBusState::default();
+    rails.handler_mut()[1].measurement = 0x0ccc;
+
+    write(&mut rails, &mut bus_state, &[PAGE, 1]).unwrap();
+    let mut upload = vec![0x40, 40];
+    upload.extend(0..40);
+    write(&mut rails, &mut bus_state, &upload).unwrap();
+    assert!(rails.handler()[1].committed);
+    assert_eq!(&upload[2..], rails.handler()[1].upload.as_slice());
+    assert!(!rails.handler()[0].committed);
+
+    let mut word = [0; 2];
+    read(&mut rails, &mut bus_state, &[0x8c], &mut word).unwrap();
+    assert_eq!([0xcc, 0x0c], word);
+    assert_eq!(None, rails.handler()[1].latched);
+}
@@ -0,0 +1,35 @@
+//! SMBus Packet Error Code, a CRC-8 with polynomial x^8 + x^2 + x + 1.
+
+/// Continue the PEC `crc` over `bytes`.
+pub const fn update(mut crc: u8, bytes: &[u8]) -> u8 {
+    let mut i = 0;
+    while i < bytes.len() {
+        crc ^= bytes[i];
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+            bit += 1;
+        }
+        i += 1;
+    }
+    crc
+}
+
+/// PEC of `bytes`, which start with the address byte of the transfer.
+pub const fn compute(bytes: &[u8]) -> u8 {
+    update(0, bytes)
+}
+
+/// Address byte of a write to the 7-bit `address`.
+pub const fn write_address(address: u8) -> u8 {
+    address << 1
+}
+
+/// Address byte of a read from the 7-bit `address`.
+pub const fn read_address(address: u8) -> u8 {
+    address << 1 | 1
+}
@@ -0,0 +1,181 @@
+//! Standard PMBus command codes (PMBus 1.3, part II, appendix I).
+
+use crate::register_map::{Access, AccessWidths};
+
+/// SMBus protocol a command is transferred with.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Protocol {
+    SendByte,
+    Byte,
+    Word,
+    Block,
+    /// Block write-block read process call.
+    BlockProcessCall,
+}
+
+impl Protocol {
+    pub const fn widths(self) -> AccessWidths {
+        match self {
+            Protocol::SendByte => AccessWidths::NONE,
+            Protocol::Byte => AccessWidths::BYTE,
+            Protocol::Word => AccessWidths::WORD,
+            Protocol::Block | Protocol::BlockProcessCall => AccessWidths::BLOCK,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct CommandInfo {
+    pub code: u8,
+    pub name: &'static str,
+    pub protocol: Protocol,
+    pub access: Access,
+}
+
+macro_rules! commands {
+    ($($name:ident = $code:literal, $protocol:ident, $access:ident;)*) => {
+        $(pub const $name: u8 = $code;)*
+
+        /// Every standard command, ordered by code.
+        pub static COMMANDS: &[CommandInfo] = &[
+            $(CommandInfo {
+                code: $code,
+                name: stringify!($name),
+                protocol: Protocol::$protocol,
+                access: Access::$access,
+            },)*
+        ];
+    };
+}
+
+commands! {
+    PAGE = 0x00, Byte, ReadWrite;
+    OPERATION = 0x01, Byte, ReadWrite;
+    ON_OFF_CONFIG = 0x02, Byte, ReadWrite;
+    CLEAR_FAULTS = 0x03, SendByte, WriteOnly;
+    PHASE = 0x04, Byte, ReadWrite;
+    PAGE_PLUS_WRITE = 0x05, Block, WriteOnly;
+    PAGE_PLUS_READ = 0x06, BlockProcessCall, ReadWrite;
+    WRITE_PROTECT = 0x10, Byte, ReadWrite;
+    STORE_DEFAULT_ALL = 0x11, SendByte, WriteOnly;
+    RESTORE_DEFAULT_ALL = 0x12, SendByte, WriteOnly;
+    STORE_USER_ALL = 0x15, SendByte, WriteOnly;
+    RESTORE_USER_ALL = 0x16, SendByte, WriteOnly;
+    CAPABILITY = 0x19, Byte, ReadOnly;
+    QUERY = 0x1a, BlockProcessCall, ReadWrite;
+    SMBALERT_MASK = 0x1b, Word, ReadWrite;
+    VOUT_MODE = 0x20, Byte, ReadWrite;
+    VOUT_COMMAND = 0x21, Word, ReadWrite;
+    VOUT_TRIM = 0x22, Word, ReadWrite;
+    VOUT_CAL_OFFSET = 0x23, Word, ReadWrite;
+    VOUT_MAX = 0x24, Word, ReadWrite;
+    VOUT_MARGIN_HIGH = 0x25, Word, ReadWrite;
+    VOUT_MARGIN_LOW = 0x26, Word, ReadWrite;
+    VOUT_TRANSITION_RATE = 0x27, Word, ReadWrite;
+    VOUT_DROOP = 0x28, Word, ReadWrite;
+    VOUT_SCALE_LOOP = 0x29, Word, ReadWrite;
+    VOUT_SCALE_MONITOR = 0x2a, Word, ReadWrite;
+    VOUT_MIN = 0x2b, Word, ReadWrite;
+    COEFFICIENTS = 0x30, BlockProcessCall, ReadWrite;
+    POUT_MAX = 0x31, Word, ReadWrite;
+    MAX_DUTY = 0x32, Word, ReadWrite;
+    FREQUENCY_SWITCH = 0x33, Word, ReadWrite;
+    VIN_ON = 0x35, Word, ReadWrite;
+    VIN_OFF = 0x36, Word, ReadWrite;
+    INTERLEAVE = 0x37, Word, ReadWrite;
+    IOUT_CAL_GAIN = 0x38, Word, ReadWrite;
+    IOUT_CAL_OFFSET = 0x39, Word, ReadWrite;
+    VOUT_OV_FAULT_LIMIT = 0x40, Word, ReadWrite;
+    VOUT_OV_FAULT_RESPONSE = 0x41, Byte, ReadWrite;
+    VOUT_OV_WARN_LIMIT = 0x42, Word, ReadWrite;
+    VOUT_UV_WARN_LIMIT = 0x43, Word, ReadWrite;
+    VOUT_UV_FAULT_LIMIT = 0x44, Word, ReadWrite;
+    VOUT_UV_FAULT_RESPONSE = 0x45, Byte, ReadWrite;
+    IOUT_OC_FAULT_LIMIT = 0x46, Word, ReadWrite;
+    IOUT_OC_FAULT_RESPONSE = 0x47, Byte, ReadWrite;
+    IOUT_OC_LV_FAULT_LIMIT = 0x48, Word, ReadWrite;
+    IOUT_OC_LV_FAULT_RESPONSE = 0x49, Byte, ReadWrite;
+    IOUT_OC_WARN_LIMIT = 0x4a, Word, ReadWrite;
+    IOUT_UC_FAULT_LIMIT = 0x4b, Word, ReadWrite;
+    IOUT_UC_FAULT_RESPONSE = 0x4c, Byte, ReadWrite;
+    OT_FAULT_LIMIT = 0x4f, Word, ReadWrite;
+    OT_FAULT_RESPONSE = 0x50, Byte, ReadWrite;
+    OT_WARN_LIMIT = 0x51, Word, ReadWrite;
+    UT_WARN_LIMIT = 0x52, Word, ReadWrite;
+    UT_FAULT_LIMIT = 0x53, Word, ReadWrite;
+    UT_FAULT_RESPONSE = 0x54, Byte, ReadWrite;
+    VIN_OV_FAULT_LIMIT = 0x55, Word, ReadWrite;
+    VIN_OV_FAULT_RESPONSE = 0x56, Byte, ReadWrite;
+    VIN_OV_WARN_LIMIT = 0x57, Word, ReadWrite;
+    VIN_UV_WARN_LIMIT = 0x58, Word, ReadWrite;
+    VIN_UV_FAULT_LIMIT = 0x59, Word, ReadWrite;
+    VIN_UV_FAULT_RESPONSE = 0x5a, Byte, ReadWrite;
+    IIN_OC_FAULT_LIMIT = 0x5b, Word, ReadWrite;
+    IIN_OC_FAULT_RESPONSE = 0x5c, Byte, ReadWrite;
+    IIN_OC_WARN_LIMIT = 0x5d, Word, ReadWrite;
+    POWER_GOOD_ON = 0x5e, Word, ReadWrite;
+    POWER_GOOD_OFF = 0x5f, Word, ReadWrite;
+    TON_DELAY = 0x60, Word, ReadWrite;
+    TON_RISE = 0x61, Word, ReadWrite;
+    TON_MAX_FAULT_LIMIT = 0x62, Word, ReadWrite;
+    TON_MAX_FAULT_RESPONSE = 0x63, Byte, ReadWrite;
+    TOFF_DELAY = 0x64, Word, ReadWrite;
+    TOFF_FALL = 0x65, Word, ReadWrite;
+    TOFF_MAX_WARN_LIMIT = 0x66, Word, ReadWrite;
+    POUT_OP_FAULT_LIMIT = 0x68, Word, ReadWrite;
+    POUT_OP_FAULT_RESPONSE = 0x69, Byte, ReadWrite;
+    POUT_OP_WARN_LIMIT = 0x6a, Word, ReadWrite;
+    PIN_OP_WARN_LIMIT = 0x6b, Word, ReadWrite;
+    STATUS_BYTE = 0x78, Byte, WriteOneToClear;
+    STATUS_WORD = 0x79, Word, WriteOneToClear;
+    STATUS_VOUT = 0x7a, Byte, WriteOneToClear;
+    STATUS_IOUT = 0x7b, Byte, WriteOneToClear;
+    STATUS_INPUT = 0x7c, Byte, WriteOneToClear;
+    STATUS_TEMPERATURE = 0x7d, Byte, WriteOneToClear;
+    STATUS_CML = 0x7e, Byte, WriteOneToClear;
+    STATUS_OTHER = 0x7f, Byte, WriteOneToClear;
+    STATUS_MFR_SPECIFIC = 0x80, Byte, WriteOneToClear;
+    STATUS_FANS_1_2 = 0x81, Byte, WriteOneToClear;
+    STATUS_FANS_3_4 = 0x82, Byte, WriteOneToClear;
+    READ_EIN = 0x86, Block, ReadOnly;
+    READ_EOUT = 0x87, Block, ReadOnly;
+    READ_VIN = 0x88, Word, ReadOnly;
+    READ_IIN = 0x89, Word, ReadOnly;
+    READ_VCAP = 0x8a, Word, ReadOnly;
+    READ_VOUT = 0x8b, Word, ReadOnly;
+    READ_IOUT = 0x8c, Word, ReadOnly;
+    READ_TEMPERATURE_1 = 0x8d, Word, ReadOnly;
+    READ_TEMPERATURE_2 = 0x8e, Word, ReadOnly;
+    READ_TEMPERATURE_3 = 0x8f, Word, ReadOnly;
+    READ_FAN_SPEED_1 = 0x90, Word, ReadOnly;
+    READ_FAN_SPEED_2 = 0x91, Word, ReadOnly;
+    READ_DUTY_CYCLE = 0x94, Word, ReadOnly;
+    READ_FREQUENCY = 0x95, Word, ReadOnly;
+    READ_POUT = 0x96, Word, ReadOnly;
+    READ_PIN = 0x97, Word, ReadOnly;
+    PMBUS_REVISION = 0x98, Byte, ReadOnly;
+    MFR_ID = 0x99, Block, ReadWrite;
+    MFR_MODEL = 0x9a, Block, ReadWrite;
+    MFR_REVISION = 0x9b, Block, ReadWrite;
+    MFR_LOCATION = 0x9c, Block, ReadWrite;
+    MFR_DATE = 0x9d, Block, ReadWrite;
+    MFR_SERIAL = 0x9e, Block, ReadWrite;
+    MFR_VIN_MIN = 0xa0, Word, ReadOnly;
+    MFR_VIN_MAX = 0xa1, Word, ReadOnly;
+    MFR_IIN_MAX = 0xa2, Word, ReadOnly;
+    MFR_PIN_MAX = 0xa3, Word, ReadOnly;
+    MFR_VOUT_MIN = 0xa4, Word, ReadOnly;
+    MFR_VOUT_MAX = 0xa5, Word, ReadOnly;
+    MFR_IOUT_MAX = 0xa6, Word, ReadOnly;
+    MFR_POUT_MAX = 0xa7, Word, ReadOnly;
+    MFR_TAMBIENT_MAX = 0xa8, Word, ReadOnly;
+    MFR_TAMBIENT_MIN = 0xa9, Word, ReadOnly;
+}
+
+/// Look up a standard command. Manufacturer specific codes return `None`.
+pub fn info(code: u8) -> Option<&'static CommandInfo> {
+    COMMANDS
+        .binary_search_by_key(&code, |command| command.code)
+        .ok()
+        .map(|i| &COMMANDS[i])
+}
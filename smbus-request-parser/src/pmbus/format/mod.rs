@@ -0,0 +1,245 @@
+//! PMBus numeric formats without floating point. Values are integers in
+//! millionths of the unit (µV, µA, µW, µ°C, ...), which resolves every LINEAR11
+//! and ULINEAR16 step exactly. Encoding rounds to the nearest step and
+//! saturates at the limits of the format.
+
+#[cfg(test)]
+mod test;
+
+const MICRO: i128 = 1_000_000;
+
+/// Divide rounding half away from zero; `den` is positive.
+fn div_round(num: i128, den: i128) -> i128 {
+    if num >= 0 {
+        (num + den / 2) / den
+    } else {
+        (num - den / 2) / den
+    }
+}
+
+/// Range of the 5 bit two's complement exponents of LINEAR11 and VOUT_MODE.
+const EXPONENT: core::ops::RangeInclusive<i8> = -16..=15;
+
+/// `value * 2^exponent` in micro-units, `None` if it overflows.
+fn scale_to_micro(value: i128, exponent: i8) -> Option<i64> {
+    let power = 2i128.checked_pow(exponent.unsigned_abs() as u32)?;
+    let micro = if exponent >= 0 {
+        (value * MICRO).checked_mul(power)?
+    } else {
+        div_round(value * MICRO, power)
+    };
+    i64::try_from(micro).ok()
+}
+
+/// Micro-units as a multiple of `2^exponent`, rounded; `None` if it overflows.
+fn scale_from_micro(micro: i64, exponent: i8) -> Option<i128> {
+    let micro = micro as i128;
+    let power = 2i128.checked_pow(exponent.unsigned_abs() as u32)?;
+    Some(if exponent >= 0 {
+        div_round(micro, MICRO.checked_mul(power)?)
+    } else {
+        div_round(micro.checked_mul(power)?, MICRO)
+    })
+}
+
+/// LINEAR11 word: a 5 bit exponent and an 11 bit mantissa, both two's complement.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Linear11(pub u16);
+
+impl Linear11 {
+    const MANTISSA: core::ops::RangeInclusive<i128> = -1024..=1023;
+
+    /// Encode with the smallest exponent that fits, i.e. the best resolution.
+    pub fn from_micro(value: i64) -> Self {
+        for exponent in EXPONENT {
+            match scale_from_micro(value, exponent) {
+                Some(mantissa) if Self::MANTISSA.contains(&mantissa) => {
+                    return Self::new(exponent, mantissa as i16)
+                }
+                _ => {}
+            }
+        }
+        let mantissa = if value < 0 { -1024 } else { 1023 };
+        Self::new(15, mantissa)
+    }
+
+    /// `exponent` is taken modulo 32 and `mantissa` modulo 2048.
+    pub const fn new(exponent: i8, mantissa: i16) -> Self {
+        Self(((exponent as u16 & 0x1f) << 11) | (mantissa as u16 & 0x7ff))
+    }
+
+    pub const fn exponent(self) -> i8 {
+        (self.0 as i16 >> 11) as i8
+    }
+
+    pub const fn mantissa(self) -> i16 {
+        ((self.0 << 5) as i16) >> 5
+    }
+
+    pub fn to_micro(self) -> i64 {
+        // Every 11 bit mantissa scaled by a 5 bit exponent fits.
+        scale_to_micro(self.mantissa() as i128, self.exponent()).unwrap_or_default()
+    }
+}
+
+impl From<u16> for Linear11 {
+    fn from(raw: u16) -> Self {
+        Self(raw)
+    }
+}
+
+impl From<Linear11> for u16 {
+    fn from(value: Linear11) -> Self {
+        value.0
+    }
+}
+
+/// Contents of VOUT_MODE, which selects the format of the output voltage commands.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum VoutMode {
+    /// ULINEAR16 with a 5 bit two's complement exponent.
+    Linear {
+        exponent: i8,
+    },
+    Vid {
+        code: u8,
+    },
+    Direct,
+}
+
+impl VoutMode {
+    pub const fn from_byte(byte: u8) -> Option<Self> {
+        let parameter = byte & 0x1f;
+        match byte >> 5 {
+            0b000 => Some(VoutMode::Linear {
+                exponent: ((parameter << 3) as i8) >> 3,
+            }),
+            0b001 => Some(VoutMode::Vid { code: parameter }),
+            0b010 => Some(VoutMode::Direct),
+            _ => None,
+        }
+    }
+
+    pub const fn byte(self) -> u8 {
+        match self {
+            VoutMode::Linear { exponent } => exponent as u8 & 0x1f,
+            VoutMode::Vid { code } => 0b001 << 5 | (code & 0x1f),
+            VoutMode::Direct => 0b010 << 5,
+        }
+    }
+}
+
+/// ULINEAR16 word: an unsigned mantissa, with the exponent from VOUT_MODE.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ULinear16(pub u16);
+
+impl ULinear16 {
+    /// `None` unless `exponent` is a VOUT_MODE exponent, i.e. in -16..=15.
+    pub fn from_micro(value: i64, exponent: i8) -> Option<Self> {
+        if !EXPONENT.contains(&exponent) {
+            return None;
+        }
+        let mantissa = scale_from_micro(value, exponent)?;
+        Some(Self(mantissa.clamp(0, u16::MAX as i128) as u16))
+    }
+
+    /// `None` unless `exponent` is a VOUT_MODE exponent, i.e. in -16..=15.
+    pub fn to_micro(self, exponent: i8) -> Option<i64> {
+        if !EXPONENT.contains(&exponent) {
+            return None;
+        }
+        scale_to_micro(self.0 as i128, exponent)
+    }
+}
+
+impl From<u16> for ULinear16 {
+    fn from(raw: u16) -> Self {
+        Self(raw)
+    }
+}
+
+impl From<ULinear16> for u16 {
+    fn from(value: ULinear16) -> Self {
+        value.0
+    }
+}
+
+/// DIRECT format coefficients: `Y = (m * X + b) * 10^R`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Coefficients {
+    pub m: i16,
+    pub b: i16,
+    pub r: i8,
+}
+
+impl Coefficients {
+    /// Response of the COEFFICIENTS command, without the byte count.
+    pub const fn to_bytes(self) -> [u8; 5] {
+        let [m_low, m_high] = self.m.to_le_bytes();
+        let [b_low, b_high] = self.b.to_le_bytes();
+        [m_low, m_high, b_low, b_high, self.r as u8]
+    }
+
+    pub const fn from_bytes(bytes: [u8; 5]) -> Self {
+        Self {
+            m: i16::from_le_bytes([bytes[0], bytes[1]]),
+            b: i16::from_le_bytes([bytes[2], bytes[3]]),
+            r: bytes[4] as i8,
+        }
+    }
+}
+
+/// DIRECT word: a two's complement value scaled by [`Coefficients`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Direct(pub u16);
+
+impl Direct {
+    /// `None` if the coefficients overflow the arithmetic, e.g. for `|R| > 38`.
+    pub fn from_micro(value: i64, coefficients: Coefficients) -> Option<Self> {
+        let Coefficients { m, b, r } = coefficients;
+        let scaled = m as i128 * value as i128 + b as i128 * MICRO;
+        let power = 10i128.checked_pow(r.unsigned_abs() as u32)?;
+        let y = if r >= 0 {
+            div_round(scaled.checked_mul(power)?, MICRO)
+        } else {
+            div_round(scaled, MICRO.checked_mul(power)?)
+        };
+        Some(Self(
+            y.clamp(i16::MIN as i128, i16::MAX as i128) as i16 as u16
+        ))
+    }
+
+    /// `None` if `m` is zero or the coefficients overflow the arithmetic.
+    pub fn to_micro(self, coefficients: Coefficients) -> Option<i64> {
+        let Coefficients { m, b, r } = coefficients;
+        if m == 0 {
+            return None;
+        }
+        let y = self.0 as i16 as i128;
+        let power = 10i128.checked_pow(r.unsigned_abs() as u32)?;
+        let (num, den) = if r >= 0 {
+            let offset = (b as i128 * MICRO).checked_mul(power)?;
+            (
+                (y * MICRO).checked_sub(offset)?,
+                (m as i128).checked_mul(power)?,
+            )
+        } else {
+            let value = (y * MICRO).checked_mul(power)?;
+            (value.checked_sub(b as i128 * MICRO)?, m as i128)
+        };
+        let (num, den) = if den < 0 { (-num, -den) } else { (num, den) };
+        i64::try_from(div_round(num, den)).ok()
+    }
+}
+
+impl From<u16> for Direct {
+    fn from(raw: u16) -> Self {
+        Self(raw)
+    }
+}
+
+impl From<Direct> for u16 {
+    fn from(value: Direct) -> Self {
+        value.0
+    }
+}
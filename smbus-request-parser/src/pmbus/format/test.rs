@@ -0,0 +1,164 @@
+use crate::pmbus::format::*;
+
+#[test]
+fn test_linear11_examples() {
+    assert_eq!(8_156_250, Linear11(0xd20a).to_micro());
+    assert_eq!(-1_000_000, Linear11(0x07ff).to_micro());
+    assert_eq!(97_656, Linear11(0x9b20).to_micro());
+    assert_eq!(0, Linear11(0xf800).to_micro());
+
+    assert_eq!(Linear11(0xd300), Linear11::from_micro(12_000_000));
+    assert_eq!(Linear11::new(-6, 768), Linear11(0xd300));
+    assert_eq!(Linear11::new(-16, -1), Linear11::from_micro(-15));
+    assert_eq!(Linear11::new(15, 1023), Linear11::from_micro(i64::MAX));
+    assert_eq!(Linear11::new(15, -1024), Linear11::from_micro(i64::MIN));
+}
+
+#[test]
+fn test_linear11_round_trip() {
+    for raw in 0..=u16::MAX {
+        let value = Linear11(raw);
+        assert_eq!(value, Linear11::new(value.exponent(), value.mantissa()));
+        let micro = value.to_micro();
+        assert_eq!(
+            micro,
+            Linear11::from_micro(micro).to_micro(),
+            "{:#06x}",
+            raw
+        );
+    }
+}
+
+#[test]
+fn test_vout_mode() {
+    assert_eq!(
+        Some(VoutMode::Linear { exponent: -9 }),
+        VoutMode::from_byte(0x17)
+    );
+    assert_eq!(
+        Some(VoutMode::Linear { exponent: -12 }),
+        VoutMode::from_byte(0x14)
+    );
+    assert_eq!(
+        Some(VoutMode::Vid { code: 0x01 }),
+        VoutMode::from_byte(0x21)
+    );
+    assert_eq!(Some(VoutMode::Direct), VoutMode::from_byte(0x40));
+    assert_eq!(None, VoutMode::from_byte(0x60));
+    for byte in 0..=0x40 {
+        assert_eq!(byte, VoutMode::from_byte(byte).unwrap().byte());
+    }
+}
+
+#[test]
+fn test_ulinear16_examples() {
+    assert_eq!(
+        Some(ULinear16(0x1800)),
+        ULinear16::from_micro(12_000_000, -9)
+    );
+    assert_eq!(
+        Some(ULinear16(0x1000)),
+        ULinear16::from_micro(1_000_000, -12)
+    );
+    assert_eq!(Some(3_300_049), ULinear16(0x34cd).to_micro(-12));
+    assert_eq!(Some(ULinear16(0)), ULinear16::from_micro(-1_000_000, -12));
+    assert_eq!(
+        Some(ULinear16(u16::MAX)),
+        ULinear16::from_micro(20_000_000, -12)
+    );
+}
+
+#[test]
+fn test_ulinear16_invalid_exponent() {
+    assert_eq!(None, ULinear16(1).to_micro(-128));
+    assert_eq!(None, ULinear16(1).to_micro(16));
+    assert_eq!(None, ULinear16::from_micro(1, 122));
+    assert_eq!(None, ULinear16::from_micro(1_000_000, 40));
+    assert_eq!(None, ULinear16::from_micro(1_000_000, -17));
+    assert_eq!(
+        Some(ULinear16(u16::MAX)),
+        ULinear16::from_micro(i64::MAX, -16)
+    );
+    assert_eq!(Some(65_535_000_000 << 15), ULinear16(u16::MAX).to_micro(15));
+}
+
+#[test]
+fn test_ulinear16_round_trip() {
+    for exponent in -16..=15 {
+        for raw in 0..=u16::MAX {
+            let micro = ULinear16(raw).to_micro(exponent).unwrap();
+            assert_eq!(Some(ULinear16(raw)), ULinear16::from_micro(micro, exponent));
+        }
+    }
+}
+
+const VOLTAGE: Coefficients = Coefficients {
+    m: 19995,
+    b: 0,
+    r: -1,
+};
+
+const KELVIN: Coefficients = Coefficients { m: 1, b: 273, r: 0 };
+
+const CURRENT: Coefficients = Coefficients {
+    m: 200,
+    b: -100,
+    r: -2,
+};
+
+#[test]
+fn test_direct_examples() {
+    assert_eq!(Some(Direct(23994)), Direct::from_micro(12_000_000, VOLTAGE));
+    assert_eq!(Some(12_000_000), Direct(23994).to_micro(VOLTAGE));
+    assert_eq!(Some(Direct(298)), Direct::from_micro(25_000_000, KELVIN));
+    assert_eq!(Some(-40_000_000), Direct(233).to_micro(KELVIN));
+    assert_eq!(Some(Direct(0x7fff)), Direct::from_micro(i64::MAX, KELVIN));
+    assert_eq!(Some(Direct(1)), Direct::from_micro(1_000_000, CURRENT));
+    assert_eq!(Some(-500_000), Direct(-2i16 as u16).to_micro(CURRENT));
+}
+
+#[test]
+fn test_direct_invalid_coefficients() {
+    let flat = Coefficients { m: 0, b: 5, r: 0 };
+    assert_eq!(Some(Direct(5)), Direct::from_micro(1_000_000, flat));
+    assert_eq!(None, Direct(5).to_micro(flat));
+
+    let huge = Coefficients { m: 1, b: 0, r: 39 };
+    assert_eq!(None, Direct::from_micro(1, huge));
+    assert_eq!(None, Direct(1).to_micro(huge));
+    let tiny = Coefficients { m: 1, b: 0, r: -39 };
+    assert_eq!(None, Direct::from_micro(1, tiny));
+    assert_eq!(None, Direct(1).to_micro(tiny));
+
+    let large = Coefficients { m: 1, b: 0, r: 38 };
+    assert_eq!(Some(Direct(0)), Direct::from_micro(0, large));
+    assert_eq!(None, Direct::from_micro(1_000_000, large));
+    assert_eq!(Some(0), Direct(1).to_micro(large));
+    let scaled = Coefficients {
+        m: 100,
+        b: 0,
+        r: 37,
+    };
+    assert_eq!(None, Direct(0).to_micro(scaled));
+    assert_eq!(None, Direct::from_micro(1, scaled));
+    let small = Coefficients { m: 1, b: 0, r: -38 };
+    assert_eq!(None, Direct::from_micro(i64::MAX, small));
+    assert_eq!(Some(0), Direct(0).to_micro(small));
+    assert_eq!(None, Direct(1).to_micro(small));
+}
+
+#[test]
+fn test_direct_round_trip() {
+    for coefficients in [VOLTAGE, KELVIN, CURRENT] {
+        for raw in 0..=u16::MAX {
+            let micro = Direct(raw).to_micro(coefficients).unwrap();
+            assert_eq!(Some(Direct(raw)), Direct::from_micro(micro, coefficients));
+        }
+    }
+}
+
+#[test]
+fn test_coefficients_bytes() {
+    assert_eq!([0x1b, 0x4e, 0x00, 0x00, 0xff], VOLTAGE.to_bytes());
+    assert_eq!(CURRENT, Coefficients::from_bytes(CURRENT.to_bytes()));
+}
@@ -0,0 +1,493 @@
+use crate::{
+    paged::ALL_PAGES,
+    register_map::{Access, RegisterPolicy},
+    CommandHandler, Unsupported,
+};
+
+use command::{CommandInfo, Protocol};
+use status::{cml, Status};
+
+pub mod command;
+pub mod format;
+pub mod status;
+
+#[cfg(test)]
+mod test;
+
+/// PHASE value addressing every phase of the selected page.
+pub const ALL_PHASES: u8 = 0xff;
+
+/// Bits of the QUERY response.
+pub mod query {
+    pub const SUPPORTED: u8 = 1 << 7;
+    pub const WRITE: u8 = 1 << 6;
+    pub const READ: u8 = 1 << 5;
+}
+
+/// Page and phase a command applies to.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Target {
+    pub page: u8,
+    pub phase: u8,
+}
+
+/// Numeric format of a command, as reported by QUERY.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum DataFormat {
+    Linear11,
+    ULinear16,
+    /// 16 bit two's complement.
+    Signed16,
+    Direct,
+    Unsigned8,
+    Vid,
+    Manufacturer,
+    #[default]
+    NotNumeric,
+}
+
+impl DataFormat {
+    const fn query_bits(self) -> u8 {
+        let format = match self {
+            DataFormat::Linear11 | DataFormat::ULinear16 => 0b000,
+            DataFormat::Signed16 => 0b001,
+            DataFormat::Direct => 0b011,
+            DataFormat::Unsigned8 => 0b100,
+            DataFormat::Vid => 0b101,
+            DataFormat::Manufacturer => 0b110,
+            DataFormat::NotNumeric => 0b111,
+        };
+        format << 2
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum BusSpeed {
+    #[default]
+    Standard,
+    Fast,
+    FastPlus,
+}
+
+/// Contents of the CAPABILITY command.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct Capability {
+    /// Reported by [`Pmbus`] from [`PmbusDevice::pec_address`].
+    pub pec: bool,
+    pub max_bus_speed: BusSpeed,
+    pub smbalert: bool,
+}
+
+impl Capability {
+    pub const fn byte(self) -> u8 {
+        let speed = match self.max_bus_speed {
+            BusSpeed::Standard => 0b00,
+            BusSpeed::Fast => 0b01,
+            BusSpeed::FastPlus => 0b10,
+        };
+        (self.pec as u8) << 7 | speed << 5 | (self.smbalert as u8) << 4
+    }
+}
+
+/// Application side of a PMBus device, used through [`Pmbus`].
+///
+/// PAGE, PHASE, CLEAR_FAULTS, CAPABILITY, QUERY and the STATUS_* commands are
+/// handled by [`Pmbus`]; the device only keeps a [`Status`] per page. Every
+/// other command reaches the typed hooks if [`PmbusDevice::supports`] it, with
+/// the protocol given by [`PmbusDevice::command_info`].
+pub trait PmbusDevice {
+    type Error: From<Unsupported>;
+
+    /// Number of pages; valid pages are `0..pages()`.
+    fn pages(&self) -> u8 {
+        1
+    }
+
+    fn phases(&self, _page: u8) -> u8 {
+        1
+    }
+
+    fn supports(&self, command: u8) -> bool;
+
+    /// Protocol and access of `command`, the standard table by default.
+    /// Manufacturer specific commands need to be described here.
+    fn command_info(&self, command: u8) -> Option<CommandInfo> {
+        command::info(command).copied()
+    }
+
+    fn data_format(&self, _command: u8) -> DataFormat {
+        DataFormat::NotNumeric
+    }
+
+    fn capability(&self) -> Capability {
+        Capability::default()
+    }
+
+    /// 7-bit address of the device if it supports Packet Error Checking.
+    /// Transfers failing the check set STATUS_CML PEC failed.
+    fn pec_address(&self) -> Option<u8> {
+        None
+    }
+
+    fn status(&mut self, page: u8) -> &mut Status;
+
+    /// Called after CLEAR_FAULTS cleared the status of `page`.
+    fn clear_faults(&mut self, _page: u8) {}
+
+    fn send(&mut self, _target: Target, _command: u8) -> Result<(), Self::Error> {
+        Err(Unsupported.into())
+    }
+
+    fn read_byte(&mut self, _target: Target, _command: u8) -> Option<u8> {
+        None
+    }
+
+    fn read_word(&mut self, _target: Target, _command: u8) -> Option<u16> {
+        None
+    }
+
+    fn read_block(&mut self, _target: Target, _command: u8, _index: u8) -> Option<u8> {
+        None
+    }
+
+    fn process_call(
+        &mut self,
+        _target: Target,
+        _command: u8,
+        _request: &[u8],
+        _index: u8,
+    ) -> Option<u8> {
+        None
+    }
+
+    fn write_byte(&mut self, _target: Target, _command: u8, _data: u8) -> Result<(), Self::Error> {
+        Err(Unsupported.into())
+    }
+
+    fn write_word(&mut self, _target: Target, _command: u8, _data: u16) -> Result<(), Self::Error> {
+        Err(Unsupported.into())
+    }
+
+    fn write_block(
+        &mut self,
+        _target: Target,
+        _command: u8,
+        _data: &[u8],
+    ) -> Result<(), Self::Error> {
+        Err(Unsupported.into())
+    }
+}
+
+/// [`CommandHandler`] implementing the PMBus command layer on top of a
+/// [`PmbusDevice`]. Rejected commands set the matching STATUS_CML bits: invalid
+/// command for unsupported commands and access, invalid data for rejected
+/// values and for transfers the parser refused.
+#[derive(Debug, Clone)]
+pub struct Pmbus<D> {
+    device: D,
+    page: u8,
+    phase: u8,
+    /// A STATUS_CML bit was set for the current transaction.
+    flagged: bool,
+}
+
+impl<D: PmbusDevice> Pmbus<D> {
+    pub fn new(device: D) -> Self {
+        Self {
+            device,
+            page: 0,
+            phase: 0,
+            flagged: false,
+        }
+    }
+
+    pub fn page(&self) -> u8 {
+        self.page
+    }
+
+    pub fn phase(&self) -> u8 {
+        self.phase
+    }
+
+    pub fn device(&self) -> &D {
+        &self.device
+    }
+
+    pub fn device_mut(&mut self) -> &mut D {
+        &mut self.device
+    }
+
+    pub fn into_inner(self) -> D {
+        self.device
+    }
+
+    /// Commands implemented by this layer rather than the device.
+    fn is_builtin(command: u8) -> bool {
+        matches!(
+            command,
+            command::PAGE
+                | command::PHASE
+                | command::CLEAR_FAULTS
+                | command::CAPABILITY
+                | command::QUERY
+                | command::STATUS_BYTE..=command::STATUS_FANS_3_4
+        )
+    }
+
+    fn info(&self, command: u8) -> Option<CommandInfo> {
+        if Self::is_builtin(command) {
+            return command::info(command).copied();
+        }
+        if !self.device.supports(command) {
+            return None;
+        }
+        self.device.command_info(command)
+    }
+
+    fn query(&self, command: u8) -> u8 {
+        let Some(info) = self.info(command) else {
+            return 0;
+        };
+        let mut response = query::SUPPORTED | self.device.data_format(command).query_bits();
+        if info.access.is_writable() {
+            response |= query::WRITE;
+        }
+        if info.access.is_readable() && info.protocol != Protocol::SendByte {
+            response |= query::READ;
+        }
+        response
+    }
+
+    /// Target of a read of `command` if it is supported with `protocol` and
+    /// readable. Paged commands need a single page to be selected.
+    fn readable(&mut self, command: u8, protocol: Protocol) -> Option<Target> {
+        let paged = !matches!(
+            command,
+            command::PAGE | command::PHASE | command::CAPABILITY | command::QUERY
+        );
+        let target = self.info(command).and_then(|info| {
+            let single_page = !paged || self.page != ALL_PAGES;
+            (info.protocol == protocol && info.access.is_readable() && single_page)
+                .then_some(self.target())
+        });
+        if target.is_none() {
+            self.flag(cml::INVALID_COMMAND);
+        }
+        target
+    }
+
+    fn writable(&mut self, command: u8, protocol: Protocol) -> Result<(), D::Error> {
+        match self.info(command) {
+            Some(info) if info.protocol == protocol && info.access.is_writable() => Ok(()),
+            _ => {
+                self.flag(cml::INVALID_COMMAND);
+                Err(Unsupported.into())
+            }
+        }
+    }
+
+    fn target(&self) -> Target {
+        Target {
+            page: self.page,
+            phase: self.phase,
+        }
+    }
+
+    fn pages(&self) -> core::ops::Range<u8> {
+        match self.page {
+            ALL_PAGES => 0..self.device.pages(),
+            page => page..page + 1,
+        }
+    }
+
+    fn flag(&mut self, bits: u8) {
+        self.flagged = true;
+        for page in self.pages() {
+            self.device.status(page).cml |= bits;
+        }
+    }
+
+    /// Apply `write` to the selected page, or to every page for [`ALL_PAGES`].
+    /// A rejected value sets STATUS_CML invalid data.
+    fn write_pages(
+        &mut self,
+        mut write: impl FnMut(&mut D, Target) -> Result<(), D::Error>,
+    ) -> Result<(), D::Error> {
+        let mut result = Ok(());
+        for page in self.pages() {
+            let target = Target {
+                page,
+                phase: self.phase,
+            };
+            let written = write(&mut self.device, target);
+            if result.is_ok() {
+                result = written;
+            }
+        }
+        if result.is_err() {
+            self.flag(cml::INVALID_DATA);
+        }
+        result
+    }
+
+    fn status_register(status: &mut Status, command: u8) -> Option<&mut u8> {
+        match command {
+            command::STATUS_VOUT => Some(&mut status.vout),
+            command::STATUS_IOUT => Some(&mut status.iout),
+            command::STATUS_INPUT => Some(&mut status.input),
+            command::STATUS_TEMPERATURE => Some(&mut status.temperature),
+            command::STATUS_CML => Some(&mut status.cml),
+            command::STATUS_OTHER => Some(&mut status.other),
+            command::STATUS_MFR_SPECIFIC => Some(&mut status.mfr_specific),
+            command::STATUS_FANS_1_2 => Some(&mut status.fans_1_2),
+            command::STATUS_FANS_3_4 => Some(&mut status.fans_3_4),
+            _ => None,
+        }
+    }
+
+    fn select(&mut self, command: u8, value: u8) -> Result<(), D::Error> {
+        let count = match command {
+            command::PAGE => self.device.pages(),
+            _ if self.page == ALL_PAGES => self.device.phases(0),
+            _ => self.device.phases(self.page),
+        };
+        if value != 0xff && value >= count {
+            self.flag(cml::INVALID_DATA);
+            return Err(Unsupported.into());
+        }
+        match command {
+            command::PAGE => self.page = value,
+            _ => self.phase = value,
+        }
+        Ok(())
+    }
+}
+
+impl<D: PmbusDevice> CommandHandler for Pmbus<D> {
+    type Error = D::Error;
+
+    fn handle_read_byte_data(&mut self, reg: u8) -> Option<u8> {
+        let target = self.readable(reg, Protocol::Byte)?;
+        match reg {
+            command::PAGE => Some(self.page),
+            command::PHASE => Some(self.phase),
+            command::CAPABILITY => {
+                let capability = Capability {
+                    pec: self.device.pec_address().is_some(),
+                    ..self.device.capability()
+                };
+                Some(capability.byte())
+            }
+            command::STATUS_BYTE => Some(self.device.status(target.page).word() as u8),
+            command::STATUS_VOUT..=command::STATUS_FANS_3_4 => {
+                Self::status_register(self.device.status(target.page), reg)
+                    .map(|register| *register)
+            }
+            _ => self.device.read_byte(target, reg),
+        }
+    }
+
+    fn handle_read_word_data(&mut self, reg: u8) -> Option<u16> {
+        let target = self.readable(reg, Protocol::Word)?;
+        match reg {
+            command::STATUS_WORD => Some(self.device.status(target.page).word()),
+            _ => self.device.read_word(target, reg),
+        }
+    }
+
+    fn handle_read_block_data(&mut self, reg: u8, index: u8) -> Option<u8> {
+        let target = self.readable(reg, Protocol::Block)?;
+        self.device.read_block(target, reg, index)
+    }
+
+    fn handle_block_process_call(&mut self, reg: u8, request: &[u8], index: u8) -> Option<u8> {
+        let target = self.readable(reg, Protocol::BlockProcessCall)?;
+        match (reg, request, index) {
+            (command::QUERY, [_], 0) => Some(1),
+            (command::QUERY, [command], 1) => Some(self.query(*command)),
+            (command::QUERY, _, _) => None,
+            _ => self.device.process_call(target, reg, request, index),
+        }
+    }
+
+    fn handle_write_byte(&mut self, data: u8) -> Result<(), Self::Error> {
+        self.writable(data, Protocol::SendByte)?;
+        match data {
+            command::CLEAR_FAULTS => self.write_pages(|device, target| {
+                device.status(target.page).clear();
+                device.clear_faults(target.page);
+                Ok(())
+            }),
+            _ => self.write_pages(|device, target| device.send(target, data)),
+        }
+    }
+
+    fn handle_write_byte_data(&mut self, reg: u8, data: u8) -> Result<(), Self::Error> {
+        self.writable(reg, Protocol::Byte)?;
+        match reg {
+            command::PAGE | command::PHASE => self.select(reg, data),
+            command::STATUS_BYTE => self.write_pages(|device, target| {
+                device.status(target.page).clear_word(data as u16);
+                Ok(())
+            }),
+            command::STATUS_VOUT..=command::STATUS_FANS_3_4 => {
+                self.write_pages(|device, target| {
+                    if let Some(register) = Self::status_register(device.status(target.page), reg) {
+                        *register &= !data;
+                    }
+                    Ok(())
+                })
+            }
+            _ => self.write_pages(|device, target| device.write_byte(target, reg, data)),
+        }
+    }
+
+    fn handle_write_word_data(&mut self, reg: u8, data: u16) -> Result<(), Self::Error> {
+        self.writable(reg, Protocol::Word)?;
+        match reg {
+            command::STATUS_WORD => self.write_pages(|device, target| {
+                device.status(target.page).clear_word(data);
+                Ok(())
+            }),
+            _ => self.write_pages(|device, target| device.write_word(target, reg, data)),
+        }
+    }
+
+    fn handle_write_block_data(
+        &mut self,
+        reg: u8,
+        count: u8,
+        block: &[u8],
+    ) -> Result<(), Self::Error> {
+        self.writable(reg, Protocol::Block)?;
+        let data = &block[..count as usize];
+        self.write_pages(|device, target| device.write_block(target, reg, data))
+    }
+
+    /// Widths only; access is checked by the hooks so that it is reported in STATUS_CML.
+    fn register_policy(&self, reg: u8) -> Option<RegisterPolicy> {
+        let info = self.info(reg)?;
+        Some(RegisterPolicy {
+            widths: info.protocol.widths(),
+            ..RegisterPolicy::new(Access::ReadWrite)
+        })
+    }
+
+    fn pec_address(&self) -> Option<u8> {
+        self.device.pec_address()
+    }
+
+    fn on_pec_error(&mut self) {
+        self.flag(cml::PEC_FAILED);
+    }
+
+    fn on_start(&mut self) {
+        self.flagged = false;
+    }
+
+    fn on_abort(&mut self) {
+        if !self.flagged {
+            self.flag(cml::INVALID_DATA);
+        }
+    }
+}
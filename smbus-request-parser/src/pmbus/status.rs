@@ -0,0 +1,165 @@
+//! STATUS_* registers and the summary reported through STATUS_BYTE and STATUS_WORD.
+
+/// Bits of STATUS_WORD; the low byte is STATUS_BYTE.
+pub mod word {
+    pub const VOUT: u16 = 1 << 15;
+    pub const IOUT_POUT: u16 = 1 << 14;
+    pub const INPUT: u16 = 1 << 13;
+    pub const MFR_SPECIFIC: u16 = 1 << 12;
+    pub const POWER_GOOD_NEGATED: u16 = 1 << 11;
+    pub const FANS: u16 = 1 << 10;
+    pub const OTHER: u16 = 1 << 9;
+    pub const UNKNOWN: u16 = 1 << 8;
+    pub const BUSY: u16 = 1 << 7;
+    pub const OFF: u16 = 1 << 6;
+    pub const VOUT_OV_FAULT: u16 = 1 << 5;
+    pub const IOUT_OC_FAULT: u16 = 1 << 4;
+    pub const VIN_UV_FAULT: u16 = 1 << 3;
+    pub const TEMPERATURE: u16 = 1 << 2;
+    pub const CML: u16 = 1 << 1;
+    pub const NONE_OF_THE_ABOVE: u16 = 1 << 0;
+}
+
+pub mod vout {
+    pub const OV_FAULT: u8 = 1 << 7;
+    pub const OV_WARNING: u8 = 1 << 6;
+    pub const UV_WARNING: u8 = 1 << 5;
+    pub const UV_FAULT: u8 = 1 << 4;
+    pub const MAX_MIN_WARNING: u8 = 1 << 3;
+    pub const TON_MAX_FAULT: u8 = 1 << 2;
+    pub const TOFF_MAX_WARNING: u8 = 1 << 1;
+    pub const POWER_ON_TRACKING_ERROR: u8 = 1 << 0;
+}
+
+pub mod iout {
+    pub const OC_FAULT: u8 = 1 << 7;
+    pub const OC_LV_FAULT: u8 = 1 << 6;
+    pub const OC_WARNING: u8 = 1 << 5;
+    pub const UC_FAULT: u8 = 1 << 4;
+    pub const CURRENT_SHARE_FAULT: u8 = 1 << 3;
+    pub const POWER_LIMITING: u8 = 1 << 2;
+    pub const POUT_OP_FAULT: u8 = 1 << 1;
+    pub const POUT_OP_WARNING: u8 = 1 << 0;
+}
+
+pub mod input {
+    pub const VIN_OV_FAULT: u8 = 1 << 7;
+    pub const VIN_OV_WARNING: u8 = 1 << 6;
+    pub const VIN_UV_WARNING: u8 = 1 << 5;
+    pub const VIN_UV_FAULT: u8 = 1 << 4;
+    pub const UNIT_OFF_LOW_VIN: u8 = 1 << 3;
+    pub const IIN_OC_FAULT: u8 = 1 << 2;
+    pub const IIN_OC_WARNING: u8 = 1 << 1;
+    pub const PIN_OP_WARNING: u8 = 1 << 0;
+}
+
+pub mod temperature {
+    pub const OT_FAULT: u8 = 1 << 7;
+    pub const OT_WARNING: u8 = 1 << 6;
+    pub const UT_WARNING: u8 = 1 << 5;
+    pub const UT_FAULT: u8 = 1 << 4;
+}
+
+pub mod cml {
+    pub const INVALID_COMMAND: u8 = 1 << 7;
+    pub const INVALID_DATA: u8 = 1 << 6;
+    pub const PEC_FAILED: u8 = 1 << 5;
+    pub const MEMORY_FAULT: u8 = 1 << 4;
+    pub const PROCESSOR_FAULT: u8 = 1 << 3;
+    pub const OTHER_COMMUNICATION_FAULT: u8 = 1 << 1;
+    pub const OTHER_MEMORY_LOGIC_FAULT: u8 = 1 << 0;
+}
+
+/// Fault and warning state of one page. The detail registers are latched
+/// until cleared by CLEAR_FAULTS or by writing 1 to the bits; `busy`, `off`
+/// and `power_good` reflect the live state of the device.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct Status {
+    pub vout: u8,
+    pub iout: u8,
+    pub input: u8,
+    pub temperature: u8,
+    pub cml: u8,
+    pub other: u8,
+    pub mfr_specific: u8,
+    pub fans_1_2: u8,
+    pub fans_3_4: u8,
+    pub unknown: bool,
+    pub busy: bool,
+    pub off: bool,
+    pub power_good: bool,
+}
+
+impl Status {
+    /// STATUS_WORD as derived from the detail registers.
+    pub fn word(&self) -> u16 {
+        let flags = [
+            (self.vout != 0, word::VOUT),
+            (self.iout != 0, word::IOUT_POUT),
+            (self.input != 0, word::INPUT),
+            (self.mfr_specific != 0, word::MFR_SPECIFIC),
+            (!self.power_good, word::POWER_GOOD_NEGATED),
+            (self.fans_1_2 != 0 || self.fans_3_4 != 0, word::FANS),
+            (self.other != 0, word::OTHER),
+            (self.unknown, word::UNKNOWN),
+            (self.busy, word::BUSY),
+            (self.off, word::OFF),
+            (self.vout & vout::OV_FAULT != 0, word::VOUT_OV_FAULT),
+            (self.iout & iout::OC_FAULT != 0, word::IOUT_OC_FAULT),
+            (self.input & input::VIN_UV_FAULT != 0, word::VIN_UV_FAULT),
+            (self.temperature != 0, word::TEMPERATURE),
+            (self.cml != 0, word::CML),
+        ];
+        let mut status = flags
+            .iter()
+            .filter(|(set, _)| *set)
+            .fold(0, |status, (_, bit)| status | bit);
+        let unreported = self.vout & !vout::OV_FAULT != 0
+            || self.iout & !iout::OC_FAULT != 0
+            || self.input & !input::VIN_UV_FAULT != 0
+            || self.mfr_specific != 0
+            || self.fans_1_2 != 0
+            || self.fans_3_4 != 0
+            || self.other != 0
+            || self.unknown;
+        if unreported {
+            status |= word::NONE_OF_THE_ABOVE;
+        }
+        status
+    }
+
+    /// Clear the latched conditions behind the given STATUS_WORD bits.
+    pub fn clear_word(&mut self, bits: u16) {
+        let clear = |bit: u16, register: &mut u8| {
+            if bits & bit != 0 {
+                *register = 0;
+            }
+        };
+        clear(word::VOUT, &mut self.vout);
+        clear(word::IOUT_POUT, &mut self.iout);
+        clear(word::INPUT, &mut self.input);
+        clear(word::MFR_SPECIFIC, &mut self.mfr_specific);
+        clear(word::FANS, &mut self.fans_1_2);
+        clear(word::FANS, &mut self.fans_3_4);
+        clear(word::OTHER, &mut self.other);
+        clear(word::TEMPERATURE, &mut self.temperature);
+        clear(word::CML, &mut self.cml);
+        if bits & word::UNKNOWN != 0 {
+            self.unknown = false;
+        }
+        if bits & word::VOUT_OV_FAULT != 0 {
+            self.vout &= !vout::OV_FAULT;
+        }
+        if bits & word::IOUT_OC_FAULT != 0 {
+            self.iout &= !iout::OC_FAULT;
+        }
+        if bits & word::VIN_UV_FAULT != 0 {
+            self.input &= !input::VIN_UV_FAULT;
+        }
+    }
+
+    /// CLEAR_FAULTS: clear every latched condition.
+    pub fn clear(&mut self) {
+        self.clear_word(u16::MAX);
+    }
+}
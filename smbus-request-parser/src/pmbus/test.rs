@@ -0,0 +1,303 @@
+use crate::pmbus::command::*;
+use crate::pmbus::format::{ULinear16, VoutMode};
+use crate::pmbus::status::{self, Status};
+use crate::pmbus::*;
+use crate::test::{read, write};
+use crate::*;
+
+const VOUT_EXPONENT: i8 = -9;
+const VOUT_MODE_LINEAR: VoutMode = VoutMode::Linear {
+    exponent: VOUT_EXPONENT,
+};
+
+#[derive(Default)]
+struct Rail {
+    vout_command: u16,
+    /// Output voltage in µV.
+    read_vout: i64,
+    status: Status,
+}
+
+#[derive(Default)]
+struct PowerStage {
+    rails: [Rail; 2],
+    cleared: u8,
+    pec: bool,
+}
+
+impl PmbusDevice for PowerStage {
+    type Error = ();
+
+    fn pages(&self) -> u8 {
+        2
+    }
+
+    fn phases(&self, page: u8) -> u8 {
+        [4, 1][page as usize]
+    }
+
+    fn supports(&self, command: u8) -> bool {
+        matches!(command, VOUT_MODE | VOUT_COMMAND | READ_VOUT | MFR_ID)
+    }
+
+    fn data_format(&self, command: u8) -> DataFormat {
+        match command {
+            VOUT_COMMAND | READ_VOUT => DataFormat::ULinear16,
+            _ => DataFormat::NotNumeric,
+        }
+    }
+
+    fn capability(&self) -> Capability {
+        Capability {
+            max_bus_speed: BusSpeed::Fast,
+            smbalert: true,
+            ..Default::default()
+        }
+    }
+
+    fn pec_address(&self) -> Option<u8> {
+        self.pec.then_some(0x40)
+    }
+
+    fn status(&mut self, page: u8) -> &mut Status {
+        &mut self.rails[page as usize].status
+    }
+
+    fn clear_faults(&mut self, _page: u8) {
+        self.cleared += 1;
+    }
+
+    fn read_byte(&mut self, _target: Target, command: u8) -> Option<u8> {
+        (command == VOUT_MODE).then_some(VOUT_MODE_LINEAR.byte())
+    }
+
+    fn read_word(&mut self, target: Target, command: u8) -> Option<u16> {
+        let rail = &self.rails[target.page as usize];
+        match command {
+            VOUT_COMMAND => Some(rail.vout_command),
+            READ_VOUT => ULinear16::from_micro(rail.read_vout, VOUT_EXPONENT).map(u16::from),
+            _ => None,
+        }
+    }
+
+    fn read_block(&mut self, _target: Target, command: u8, index: u8) -> Option<u8> {
+        let id = b"ACME";
+        match (command, index) {
+            (MFR_ID, 0) => Some(id.len() as u8),
+            (MFR_ID, n) => id.get(n as usize - 1).copied(),
+            _ => None,
+        }
+    }
+
+    fn write_word(&mut self, target: Target, command: u8, data: u16) -> Result<(), ()> {
+        if command != VOUT_COMMAND || data > 0x2000 {
+            return Err(());
+        }
+        self.rails[target.page as usize].vout_command = data;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_command_table() {
+    assert!(COMMANDS.windows(2).all(|pair| pair[0].code < pair[1].code));
+    let info = command::info(READ_VOUT).unwrap();
+    assert_eq!("READ_VOUT", info.name);
+    assert_eq!(Protocol::Word, info.protocol);
+    assert_eq!(None, command::info(0xd0));
+}
+
+#[test]
+fn test_paged_commands() {
+    let mut stage = Pmbus::new(PowerStage::default());
+    let mut bus_state = SMBusState::default();
+    stage.device_mut().rails[1].read_vout = 6_400_000;
+
+    write(&mut stage, &mut bus_state, &[PAGE, 1]).unwrap();
+    let mut word = [0; 2];
+    read(&mut stage, &mut bus_state, &[READ_VOUT], &mut word).unwrap();
+    assert_eq!([0xcd, 0x0c], word);
+
+    write(&mut stage, &mut bus_state, &[PAGE, 0xff]).unwrap();
+    write(&mut stage, &mut bus_state, &[VOUT_COMMAND, 0x00, 0x10]).unwrap();
+    assert_eq!(0x1000, stage.device().rails[0].vout_command);
+    assert_eq!(0x1000, stage.device().rails[1].vout_command);
+
+    let mut page = [0];
+    read(&mut stage, &mut bus_state, &[PAGE], &mut page).unwrap();
+    assert_eq!([0xff], page);
+    let result = read(&mut stage, &mut bus_state, &[READ_VOUT], &mut word);
+    assert_eq!(
+        Err(SMBusProtocolError::InvalidReadRegister(READ_VOUT)),
+        result
+    );
+
+    write(&mut stage, &mut bus_state, &[PAGE, 0]).unwrap();
+    write(&mut stage, &mut bus_state, &[PHASE, 3]).unwrap();
+    assert_eq!(3, stage.phase());
+    let result = write(&mut stage, &mut bus_state, &[PAGE, 2]);
+    assert_eq!(Err(SMBusProtocolError::InvalidWriteRegister(PAGE)), result);
+    assert_eq!(0, stage.page());
+}
+
+#[test]
+fn test_query_and_capability() {
+    let mut stage = Pmbus::new(PowerStage::default());
+    let mut bus_state = SMBusState::default();
+
+    let mut response = [0; 2];
+    read(
+        &mut stage,
+        &mut bus_state,
+        &[QUERY, 1, VOUT_COMMAND],
+        &mut response,
+    )
+    .unwrap();
+    assert_eq!([1, 0b1110_0000], response);
+    read(
+        &mut stage,
+        &mut bus_state,
+        &[QUERY, 1, READ_VOUT],
+        &mut response,
+    )
+    .unwrap();
+    assert_eq!([1, 0b1010_0000], response);
+    read(
+        &mut stage,
+        &mut bus_state,
+        &[QUERY, 1, CLEAR_FAULTS],
+        &mut response,
+    )
+    .unwrap();
+    assert_eq!([1, 0b1101_1100], response);
+    read(
+        &mut stage,
+        &mut bus_state,
+        &[QUERY, 1, READ_IOUT],
+        &mut response,
+    )
+    .unwrap();
+    assert_eq!([1, 0], response);
+
+    let mut capability = [0];
+    read(&mut stage, &mut bus_state, &[CAPABILITY], &mut capability).unwrap();
+    assert_eq!([0b0011_0000], capability);
+    stage.device_mut().pec = true;
+    read(&mut stage, &mut bus_state, &[CAPABILITY], &mut capability).unwrap();
+    assert_eq!([0b1011_0000], capability);
+
+    let mut id = [0; 5];
+    read(&mut stage, &mut bus_state, &[MFR_ID], &mut id).unwrap();
+    assert_eq!(*b"\x04ACME", id);
+}
+
+#[test]
+fn test_status_registers() {
+    let mut stage = Pmbus::new(PowerStage::default());
+    let mut bus_state = SMBusState::default();
+    let rail = &mut stage.device_mut().rails[0].status;
+    rail.power_good = true;
+    rail.vout = status::vout::OV_FAULT | status::vout::UV_WARNING;
+    rail.temperature = status::temperature::OT_WARNING;
+
+    let mut word = [0; 2];
+    read(&mut stage, &mut bus_state, &[STATUS_WORD], &mut word).unwrap();
+    let expected = status::word::VOUT
+        | status::word::VOUT_OV_FAULT
+        | status::word::TEMPERATURE
+        | status::word::NONE_OF_THE_ABOVE;
+    assert_eq!(expected.to_le_bytes(), word);
+
+    write(
+        &mut stage,
+        &mut bus_state,
+        &[STATUS_VOUT, status::vout::OV_FAULT],
+    )
+    .unwrap();
+    let mut byte = [0];
+    read(&mut stage, &mut bus_state, &[STATUS_VOUT], &mut byte).unwrap();
+    assert_eq!([status::vout::UV_WARNING], byte);
+
+    write(&mut stage, &mut bus_state, &[CLEAR_FAULTS]).unwrap();
+    read(&mut stage, &mut bus_state, &[STATUS_BYTE], &mut byte).unwrap();
+    assert_eq!([0], byte);
+    assert_eq!(1, stage.device().cleared);
+}
+
+#[test]
+fn test_communication_faults() {
+    let mut stage = Pmbus::new(PowerStage::default());
+    let mut bus_state = SMBusState::default();
+    let mut byte = [0];
+
+    let result = write(&mut stage, &mut bus_state, &[READ_VOUT, 0x00, 0x10]);
+    assert_eq!(
+        Err(SMBusProtocolError::InvalidWriteRegister(READ_VOUT)),
+        result
+    );
+    read(&mut stage, &mut bus_state, &[STATUS_CML], &mut byte).unwrap();
+    assert_eq!([status::cml::INVALID_COMMAND], byte);
+    write(&mut stage, &mut bus_state, &[STATUS_CML, 0xff]).unwrap();
+
+    let result = write(&mut stage, &mut bus_state, &[VOUT_COMMAND, 0x00, 0x30]);
+    assert_eq!(
+        Err(SMBusProtocolError::InvalidWriteRegister(VOUT_COMMAND)),
+        result
+    );
+    read(&mut stage, &mut bus_state, &[STATUS_CML], &mut byte).unwrap();
+    assert_eq!([status::cml::INVALID_DATA], byte);
+    write(&mut stage, &mut bus_state, &[CLEAR_FAULTS]).unwrap();
+
+    let result = write(&mut stage, &mut bus_state, &[VOUT_MODE, 0x17, 0x00]);
+    assert_eq!(
+        Err(SMBusProtocolError::InvalidAccessWidth(VOUT_MODE)),
+        result
+    );
+    read(&mut stage, &mut bus_state, &[STATUS_BYTE], &mut byte).unwrap();
+    assert_eq!([status::word::CML as u8], byte);
+}
+
+#[test]
+fn test_read_pec() {
+    let mut stage = Pmbus::new(PowerStage {
+        pec: true,
+        ..Default::default()
+    });
+    let mut bus_state = SMBusState::default();
+    stage.device_mut().rails[0].read_vout = 6_400_000;
+    let crc = |bytes: &[u8]| {
+        let crc = pec::update(pec::compute(&[pec::write_address(0x40)]), &bytes[..1]);
+        pec::update(crc, &[&[pec::read_address(0x40)], &bytes[1..]].concat())
+    };
+
+    let mut word = [0; 3];
+    read(&mut stage, &mut bus_state, &[READ_VOUT], &mut word).unwrap();
+    assert_eq!([0xcd, 0x0c, crc(&[READ_VOUT, 0xcd, 0x0c])], word);
+
+    let mut byte = [0; 2];
+    read(&mut stage, &mut bus_state, &[STATUS_CML], &mut byte).unwrap();
+    assert_eq!([0, crc(&[STATUS_CML, 0])], byte);
+}
+
+#[test]
+fn test_pec_failure() {
+    let mut stage = Pmbus::new(PowerStage {
+        pec: true,
+        ..Default::default()
+    });
+    let mut bus_state = SMBusState::default();
+
+    let frame = [MFR_ID, 1, b'X'];
+    let pec = pec::update(pec::compute(&[pec::write_address(0x40)]), &frame);
+    let result = write(&mut stage, &mut bus_state, &[MFR_ID, 1, b'X', !pec]);
+    assert_eq!(Err(SMBusProtocolError::InvalidPec), result);
+
+    let mut byte = [0];
+    read(&mut stage, &mut bus_state, &[STATUS_CML], &mut byte).unwrap();
+    assert_eq!([status::cml::PEC_FAILED], byte);
+
+    let pec = pec::update(pec::compute(&[pec::write_address(0x40)]), &[CLEAR_FAULTS]);
+    write(&mut stage, &mut bus_state, &[CLEAR_FAULTS, pec]).unwrap();
+    read(&mut stage, &mut bus_state, &[STATUS_CML], &mut byte).unwrap();
+    assert_eq!([0], byte);
+}
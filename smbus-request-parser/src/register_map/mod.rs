@@ -0,0 +1,450 @@
+use crate::{CommandHandler, Unsupported};
+
+#[cfg(test)]
+mod test;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Width {
+    Byte,
+    Word,
+    /// Block register holding up to the given number of bytes.
+    Block(u8),
+}
+
+impl Width {
+    /// Number of bytes the register occupies in the backing storage.
+    /// Blocks store their current length in front of the data.
+    pub const fn storage_size(self) -> usize {
+        match self {
+            Width::Byte => 1,
+            Width::Word => 2,
+            Width::Block(capacity) => capacity as usize + 1,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Access {
+    ReadOnly,
+    WriteOnly,
+    ReadWrite,
+    /// Writing a 1 clears the corresponding bit, writing a 0 leaves it unchanged.
+    /// The parser only knows the written value, so the handler applies the
+    /// clearing, as [`RegisterMap`] and `#[derive(SmbusDevice)]` do.
+    WriteOneToClear,
+    /// Read-only, cleared after the master has read the whole value. The
+    /// handler clears it in [`CommandHandler::handle_read_complete`].
+    ClearOnRead,
+    /// Writable once after reset, read-only afterwards. The parser rejects
+    /// writes while [`RegisterPolicy::locked`] is set.
+    WriteOnce,
+}
+
+impl Access {
+    pub const fn is_readable(self) -> bool {
+        !matches!(self, Access::WriteOnly)
+    }
+
+    pub const fn is_writable(self) -> bool {
+        !matches!(self, Access::ReadOnly | Access::ClearOnRead)
+    }
+}
+
+/// Set of SMBus transfer widths a register accepts.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct AccessWidths(u8);
+
+impl AccessWidths {
+    /// No data after the command code, i.e. a send byte command.
+    pub const NONE: Self = Self(0);
+    pub const BYTE: Self = Self(1);
+    pub const WORD: Self = Self(2);
+    pub const BLOCK: Self = Self(4);
+    pub const ANY: Self = Self(7);
+
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub const fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+impl From<Width> for AccessWidths {
+    fn from(width: Width) -> Self {
+        match width {
+            Width::Byte => AccessWidths::BYTE,
+            Width::Word => AccessWidths::WORD,
+            Width::Block(_) => AccessWidths::BLOCK,
+        }
+    }
+}
+
+/// Access rules of a register, enforced by the parser before the handler sees a request.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct RegisterPolicy {
+    pub access: Access,
+    /// Transfer widths the register may be read or written with.
+    pub widths: AccessWidths,
+    /// Bits which must be written as zero.
+    pub reserved: u16,
+    /// Set once a [`Access::WriteOnce`] register has been written.
+    pub locked: bool,
+}
+
+impl RegisterPolicy {
+    pub const fn new(access: Access) -> Self {
+        Self {
+            access,
+            widths: AccessWidths::ANY,
+            reserved: 0,
+            locked: false,
+        }
+    }
+}
+
+/// Static description of one register.
+///
+/// `offset` locates the register in the backing storage of the [`RegisterMap`].
+/// `reset` is little-endian for words and the initial contents for blocks.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct RegisterDescriptor {
+    pub code: u8,
+    pub width: Width,
+    pub access: Access,
+    pub reset: &'static [u8],
+    /// Bits of a byte or word register which must be written as zero.
+    pub reserved: u16,
+    pub offset: usize,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum RegisterMapError {
+    DuplicateCode(u8),
+    OutOfBounds(u8),
+    Overlap(u8),
+    InvalidReset(u8),
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum RegisterError {
+    Unsupported,
+    UnknownRegister(u8),
+    NotWritable(u8),
+    BlockTooLong(u8),
+}
+
+impl From<Unsupported> for RegisterError {
+    fn from(_: Unsupported) -> Self {
+        RegisterError::Unsupported
+    }
+}
+
+/// Backing storage of a block register, as used by `#[derive(SmbusDevice)]`.
+pub trait BlockValue {
+    /// Byte `index` of the block read, where index 0 is the byte count.
+    fn block_byte(&self, index: u8) -> Option<u8>;
+
+    /// Store a written block. Returns `false` if `data` does not fit.
+    fn write_block(&mut self, data: &[u8]) -> bool;
+}
+
+/// Fixed-length blocks: reads always return `N` bytes, writes must provide exactly `N`.
+impl<const N: usize> BlockValue for [u8; N] {
+    fn block_byte(&self, index: u8) -> Option<u8> {
+        match index {
+            0 => u8::try_from(N).ok(),
+            n => self.get(n as usize - 1).copied(),
+        }
+    }
+
+    fn write_block(&mut self, data: &[u8]) -> bool {
+        if data.len() != N {
+            return false;
+        }
+        self.copy_from_slice(data);
+        true
+    }
+}
+
+/// Variable-length block of up to `N` bytes.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Block<const N: usize> {
+    len: u8,
+    data: [u8; N],
+}
+
+impl<const N: usize> Default for Block<N> {
+    fn default() -> Self {
+        Self {
+            len: 0,
+            data: [0; N],
+        }
+    }
+}
+
+impl<const N: usize> Block<N> {
+    /// Returns `None` if `data` is longer than `N` bytes.
+    pub fn new(data: &[u8]) -> Option<Self> {
+        let mut block = Self::default();
+        block.write_block(data).then_some(block)
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.data[..self.len as usize]
+    }
+}
+
+impl<const N: usize> BlockValue for Block<N> {
+    fn block_byte(&self, index: u8) -> Option<u8> {
+        match index {
+            0 => Some(self.len),
+            n => self.as_slice().get(n as usize - 1).copied(),
+        }
+    }
+
+    fn write_block(&mut self, data: &[u8]) -> bool {
+        if data.len() > N || data.len() > u8::MAX as usize {
+            return false;
+        }
+        self.len = data.len() as u8;
+        self.data[..data.len()].copy_from_slice(data);
+        true
+    }
+}
+
+/// [`CommandHandler`] serving a table of [`RegisterDescriptor`]s from `N` bytes of storage.
+#[derive(Debug, Clone)]
+pub struct RegisterMap<'a, const N: usize> {
+    registers: &'a [RegisterDescriptor],
+    storage: [u8; N],
+    written: [u32; 8],
+}
+
+impl<'a, const N: usize> RegisterMap<'a, N> {
+    pub fn new(registers: &'a [RegisterDescriptor]) -> Result<Self, RegisterMapError> {
+        for (i, register) in registers.iter().enumerate() {
+            let end = register.offset + register.width.storage_size();
+            if end > N {
+                return Err(RegisterMapError::OutOfBounds(register.code));
+            }
+            let reset_size = match register.width {
+                Width::Block(capacity) => capacity as usize,
+                width => width.storage_size(),
+            };
+            if register.reset.len() > reset_size {
+                return Err(RegisterMapError::InvalidReset(register.code));
+            }
+            for other in &registers[..i] {
+                if other.code == register.code {
+                    return Err(RegisterMapError::DuplicateCode(register.code));
+                }
+                let other_end = other.offset + other.width.storage_size();
+                if register.offset < other_end && other.offset < end {
+                    return Err(RegisterMapError::Overlap(register.code));
+                }
+            }
+        }
+        let mut map = Self {
+            registers,
+            storage: [0; N],
+            written: [0; 8],
+        };
+        map.reset();
+        Ok(map)
+    }
+
+    /// Restore every register to its reset value.
+    pub fn reset(&mut self) {
+        self.written = [0; 8];
+        for register in self.registers {
+            let storage = &mut self.storage[register.offset..][..register.width.storage_size()];
+            storage.iter_mut().for_each(|x| *x = 0);
+            match register.width {
+                Width::Block(_) => {
+                    storage[0] = register.reset.len() as u8;
+                    storage[1..=register.reset.len()].copy_from_slice(register.reset);
+                }
+                _ => storage[..register.reset.len()].copy_from_slice(register.reset),
+            }
+        }
+    }
+
+    pub fn descriptor(&self, code: u8) -> Option<&'a RegisterDescriptor> {
+        self.registers.iter().find(|register| register.code == code)
+    }
+
+    pub fn byte(&self, code: u8) -> Option<u8> {
+        let register = self.register(code, Width::Byte)?;
+        Some(self.storage[register.offset])
+    }
+
+    pub fn word(&self, code: u8) -> Option<u16> {
+        let register = self.register(code, Width::Word)?;
+        let offset = register.offset;
+        Some(u16::from_le_bytes([
+            self.storage[offset],
+            self.storage[offset + 1],
+        ]))
+    }
+
+    pub fn block(&self, code: u8) -> Option<&[u8]> {
+        let register = self.block_register(code)?;
+        let len = self.storage[register.offset] as usize;
+        Some(&self.storage[register.offset + 1..][..len])
+    }
+
+    /// Set a byte register from the application side, regardless of its access.
+    pub fn set_byte(&mut self, code: u8, value: u8) -> Result<(), RegisterError> {
+        let register = self
+            .register(code, Width::Byte)
+            .ok_or(RegisterError::UnknownRegister(code))?;
+        self.storage[register.offset] = value;
+        Ok(())
+    }
+
+    /// Set a word register from the application side, regardless of its access.
+    pub fn set_word(&mut self, code: u8, value: u16) -> Result<(), RegisterError> {
+        let register = self
+            .register(code, Width::Word)
+            .ok_or(RegisterError::UnknownRegister(code))?;
+        self.storage[register.offset..][..2].copy_from_slice(&value.to_le_bytes());
+        Ok(())
+    }
+
+    /// Set a block register from the application side, regardless of its access.
+    pub fn set_block(&mut self, code: u8, data: &[u8]) -> Result<(), RegisterError> {
+        let register = self
+            .block_register(code)
+            .ok_or(RegisterError::UnknownRegister(code))?;
+        let storage = &mut self.storage[register.offset..][..register.width.storage_size()];
+        if data.len() >= storage.len() {
+            return Err(RegisterError::BlockTooLong(code));
+        }
+        storage[0] = data.len() as u8;
+        storage[1..=data.len()].copy_from_slice(data);
+        Ok(())
+    }
+
+    fn is_written(&self, code: u8) -> bool {
+        self.written[code as usize / 32] & (1 << (code % 32)) != 0
+    }
+
+    fn mark_written(&mut self, code: u8) {
+        self.written[code as usize / 32] |= 1 << (code % 32);
+    }
+
+    fn register(&self, code: u8, width: Width) -> Option<&'a RegisterDescriptor> {
+        self.descriptor(code)
+            .filter(|register| register.width == width)
+    }
+
+    fn block_register(&self, code: u8) -> Option<&'a RegisterDescriptor> {
+        self.descriptor(code)
+            .filter(|register| matches!(register.width, Width::Block(_)))
+    }
+
+    fn writable(&self, code: u8, width: Width) -> Result<&'a RegisterDescriptor, RegisterError> {
+        let register = self
+            .descriptor(code)
+            .filter(|register| {
+                register.width == width
+                    || matches!((register.width, width), (Width::Block(_), Width::Block(_)))
+            })
+            .ok_or(RegisterError::UnknownRegister(code))?;
+        if !register.access.is_writable()
+            || (register.access == Access::WriteOnce && self.is_written(code))
+        {
+            return Err(RegisterError::NotWritable(code));
+        }
+        Ok(register)
+    }
+}
+
+impl<'a, const N: usize> CommandHandler for RegisterMap<'a, N> {
+    type Error = RegisterError;
+
+    fn handle_read_byte_data(&mut self, reg: u8) -> Option<u8> {
+        self.register(reg, Width::Byte)
+            .filter(|register| register.access.is_readable())?;
+        self.byte(reg)
+    }
+
+    fn handle_read_word_data(&mut self, reg: u8) -> Option<u16> {
+        self.register(reg, Width::Word)
+            .filter(|register| register.access.is_readable())?;
+        self.word(reg)
+    }
+
+    fn handle_read_block_data(&mut self, reg: u8, index: u8) -> Option<u8> {
+        let register = self
+            .block_register(reg)
+            .filter(|register| register.access.is_readable())?;
+        let len = self.storage[register.offset];
+        if index > len {
+            return None;
+        }
+        Some(self.storage[register.offset + index as usize])
+    }
+
+    fn handle_read_complete(&mut self, reg: Option<u8>, count: u8) {
+        let Some(register) = reg.and_then(|reg| self.descriptor(reg)) else {
+            return;
+        };
+        let complete = match register.width {
+            Width::Block(_) => count > self.storage[register.offset],
+            width => count as usize >= width.storage_size(),
+        };
+        if register.access == Access::ClearOnRead && complete {
+            let storage = &mut self.storage[register.offset..][..register.width.storage_size()];
+            storage.iter_mut().for_each(|x| *x = 0);
+        }
+    }
+
+    fn handle_write_byte_data(&mut self, reg: u8, data: u8) -> Result<(), RegisterError> {
+        let register = self.writable(reg, Width::Byte)?;
+        let data = match register.access {
+            Access::WriteOneToClear => self.storage[register.offset] & !data,
+            _ => data,
+        };
+        self.mark_written(reg);
+        self.set_byte(reg, data)
+    }
+
+    fn handle_write_word_data(&mut self, reg: u8, data: u16) -> Result<(), RegisterError> {
+        let register = self.writable(reg, Width::Word)?;
+        let data = match register.access {
+            Access::WriteOneToClear => self.word(reg).unwrap_or_default() & !data,
+            _ => data,
+        };
+        self.mark_written(reg);
+        self.set_word(reg, data)
+    }
+
+    fn handle_write_block_data(
+        &mut self,
+        reg: u8,
+        count: u8,
+        block: &[u8],
+    ) -> Result<(), RegisterError> {
+        self.writable(reg, Width::Block(0))?;
+        self.set_block(reg, &block[..count as usize])?;
+        self.mark_written(reg);
+        Ok(())
+    }
+
+    fn register_policy(&self, reg: u8) -> Option<RegisterPolicy> {
+        let register = self.descriptor(reg)?;
+        Some(RegisterPolicy {
+            access: register.access,
+            widths: register.width.into(),
+            reserved: register.reserved,
+            locked: register.access == Access::WriteOnce && self.is_written(reg),
+        })
+    }
+}
@@ -0,0 +1,260 @@
+use crate::register_map::*;
+use crate::test::{read, write};
+use crate::*;
+
+static REGISTERS: [RegisterDescriptor; 5] = [
+    RegisterDescriptor {
+        code: 0x01,
+        width: Width::Byte,
+        access: Access::ReadWrite,
+        reset: &[0x42],
+        reserved: 0,
+        offset: 0,
+    },
+    RegisterDescriptor {
+        code: 0x02,
+        width: Width::Word,
+        access: Access::ReadOnly,
+        reset: &[0x34, 0x12],
+        reserved: 0,
+        offset: 1,
+    },
+    RegisterDescriptor {
+        code: 0x03,
+        width: Width::Word,
+        access: Access::WriteOnly,
+        reset: &[],
+        reserved: 0,
+        offset: 3,
+    },
+    RegisterDescriptor {
+        code: 0x10,
+        width: Width::Block(8),
+        access: Access::ReadOnly,
+        reset: b"ACME",
+        reserved: 0,
+        offset: 5,
+    },
+    RegisterDescriptor {
+        code: 0x11,
+        width: Width::Block(4),
+        access: Access::ReadWrite,
+        reset: &[],
+        reserved: 0,
+        offset: 14,
+    },
+];
+
+#[test]
+fn test_reset_values() {
+    let map = RegisterMap::<19>::new(&REGISTERS).unwrap();
+    assert_eq!(Some(0x42), map.byte(0x01));
+    assert_eq!(Some(0x1234), map.word(0x02));
+    assert_eq!(Some(0), map.word(0x03));
+    assert_eq!(Some(&b"ACME"[..]), map.block(0x10));
+    assert_eq!(Some(&[][..]), map.block(0x11));
+    assert_eq!(None, map.byte(0x02));
+}
+
+#[test]
+fn test_invalid_tables() {
+    assert_eq!(
+        Err(RegisterMapError::OutOfBounds(0x11)),
+        RegisterMap::<18>::new(&REGISTERS).map(|_| ())
+    );
+
+    let overlapping = [
+        REGISTERS[0],
+        RegisterDescriptor {
+            code: 0x05,
+            offset: 0,
+            ..REGISTERS[1]
+        },
+    ];
+    assert_eq!(
+        Err(RegisterMapError::Overlap(0x05)),
+        RegisterMap::<8>::new(&overlapping).map(|_| ())
+    );
+
+    let duplicate = [
+        REGISTERS[0],
+        RegisterDescriptor {
+            offset: 1,
+            ..REGISTERS[0]
+        },
+    ];
+    assert_eq!(
+        Err(RegisterMapError::DuplicateCode(0x01)),
+        RegisterMap::<8>::new(&duplicate).map(|_| ())
+    );
+}
+
+#[test]
+fn test_read_and_write_over_bus() {
+    let mut map = RegisterMap::<19>::new(&REGISTERS).unwrap();
+    let mut bus_state = SMBusState::default();
+
+    let mut byte = [0];
+    read(&mut map, &mut bus_state, &[0x01], &mut byte).unwrap();
+    assert_eq!([0x42], byte);
+
+    write(&mut map, &mut bus_state, &[0x01, 0x17]).unwrap();
+    assert_eq!(Some(0x17), map.byte(0x01));
+
+    let mut word = [0; 2];
+    read(&mut map, &mut bus_state, &[0x02], &mut word).unwrap();
+    assert_eq!([0x34, 0x12], word);
+
+    write(&mut map, &mut bus_state, &[0x03, 0xcd, 0xab]).unwrap();
+    assert_eq!(Some(0xabcd), map.word(0x03));
+
+    let mut block = [0; 5];
+    read(&mut map, &mut bus_state, &[0x10], &mut block).unwrap();
+    assert_eq!(*b"\x04ACME", block);
+
+    write(&mut map, &mut bus_state, &[0x11, 3, 7, 8, 9]).unwrap();
+    assert_eq!(Some(&[7, 8, 9][..]), map.block(0x11));
+}
+
+#[test]
+fn test_access_is_respected() {
+    let mut map = RegisterMap::<19>::new(&REGISTERS).unwrap();
+    let mut bus_state = SMBusState::default();
+
+    let result = write(&mut map, &mut bus_state, &[0x02, 0x00, 0x00]);
+    assert_eq!(Err(SMBusProtocolError::ReadOnlyRegister(0x02)), result);
+    assert_eq!(Some(0x1234), map.word(0x02));
+
+    let mut word = [0; 2];
+    let result = read(&mut map, &mut bus_state, &[0x03], &mut word);
+    assert_eq!(Err(SMBusProtocolError::WriteOnlyRegister(0x03)), result);
+
+    let result = write(&mut map, &mut bus_state, &[0x11, 5, 1, 2, 3, 4, 5]);
+    assert_eq!(Err(SMBusProtocolError::InvalidWriteBound(5)), result);
+    assert_eq!(Some(&[][..]), map.block(0x11));
+}
+
+#[test]
+fn test_access_widths() {
+    let mut map = RegisterMap::<19>::new(&REGISTERS).unwrap();
+    let mut bus_state = SMBusState::default();
+
+    let result = write(&mut map, &mut bus_state, &[0x01, 0x17, 0x00]);
+    assert_eq!(Err(SMBusProtocolError::InvalidAccessWidth(0x01)), result);
+    assert_eq!(Some(0x42), map.byte(0x01));
+
+    let result = write(&mut map, &mut bus_state, &[0x03, 0x01]);
+    assert_eq!(Err(SMBusProtocolError::InvalidAccessWidth(0x03)), result);
+    let result = write(&mut map, &mut bus_state, &[0x03, 2, 0xcd, 0xab]);
+    assert_eq!(Err(SMBusProtocolError::InvalidAccessWidth(0x03)), result);
+    assert_eq!(Some(0), map.word(0x03));
+
+    let mut bytes = [0; 2];
+    let result = read(&mut map, &mut bus_state, &[0x01], &mut bytes);
+    assert_eq!(Err(SMBusProtocolError::InvalidAccessWidth(0x01)), result);
+
+    let mut block = [0; 3];
+    let result = read(&mut map, &mut bus_state, &[0x02], &mut block);
+    assert_eq!(Err(SMBusProtocolError::InvalidAccessWidth(0x02)), result);
+    let result = read(&mut map, &mut bus_state, &[0x02], &mut block[..1]);
+    assert_eq!(Err(SMBusProtocolError::InvalidAccessWidth(0x02)), result);
+
+    write(&mut map, &mut bus_state, &[0x11, 1, 7]).unwrap();
+    assert_eq!(Some(&[7][..]), map.block(0x11));
+}
+
+static POLICIES: [RegisterDescriptor; 4] = [
+    RegisterDescriptor {
+        code: 0x20,
+        width: Width::Byte,
+        access: Access::WriteOneToClear,
+        reset: &[0xff],
+        reserved: 0,
+        offset: 0,
+    },
+    RegisterDescriptor {
+        code: 0x21,
+        width: Width::Word,
+        access: Access::ClearOnRead,
+        reset: &[0x01, 0x80],
+        reserved: 0,
+        offset: 1,
+    },
+    RegisterDescriptor {
+        code: 0x22,
+        width: Width::Byte,
+        access: Access::WriteOnce,
+        reset: &[],
+        reserved: 0,
+        offset: 3,
+    },
+    RegisterDescriptor {
+        code: 0x23,
+        width: Width::Word,
+        access: Access::ReadWrite,
+        reset: &[],
+        reserved: 0xf000,
+        offset: 4,
+    },
+];
+
+#[test]
+fn test_write_one_to_clear() {
+    let mut map = RegisterMap::<6>::new(&POLICIES).unwrap();
+    let mut bus_state = SMBusState::default();
+
+    write(&mut map, &mut bus_state, &[0x20, 0b1000_0001]).unwrap();
+    assert_eq!(Some(0b0111_1110), map.byte(0x20));
+
+    write(&mut map, &mut bus_state, &[0x20, 0]).unwrap();
+    assert_eq!(Some(0b0111_1110), map.byte(0x20));
+}
+
+#[test]
+fn test_clear_on_read() {
+    let mut map = RegisterMap::<6>::new(&POLICIES).unwrap();
+    let mut bus_state = SMBusState::default();
+
+    let mut byte = [0; 1];
+    let result = read(&mut map, &mut bus_state, &[0x21], &mut byte);
+    assert_eq!(Err(SMBusProtocolError::InvalidAccessWidth(0x21)), result);
+    assert_eq!(Some(0x8001), map.word(0x21));
+    map.handle_read_complete(Some(0x21), 1);
+    assert_eq!(Some(0x8001), map.word(0x21));
+
+    let mut word = [0; 2];
+    read(&mut map, &mut bus_state, &[0x21], &mut word).unwrap();
+    assert_eq!([0x01, 0x80], word);
+    assert_eq!(Some(0), map.word(0x21));
+
+    let result = write(&mut map, &mut bus_state, &[0x21, 0x01, 0x00]);
+    assert_eq!(Err(SMBusProtocolError::ReadOnlyRegister(0x21)), result);
+}
+
+#[test]
+fn test_write_once() {
+    let mut map = RegisterMap::<6>::new(&POLICIES).unwrap();
+    let mut bus_state = SMBusState::default();
+
+    write(&mut map, &mut bus_state, &[0x22, 0x5a]).unwrap();
+    let result = write(&mut map, &mut bus_state, &[0x22, 0xa5]);
+    assert_eq!(Err(SMBusProtocolError::RegisterLocked(0x22)), result);
+    assert_eq!(Some(0x5a), map.byte(0x22));
+
+    map.reset();
+    write(&mut map, &mut bus_state, &[0x22, 0xa5]).unwrap();
+    assert_eq!(Some(0xa5), map.byte(0x22));
+}
+
+#[test]
+fn test_reserved_bits() {
+    let mut map = RegisterMap::<6>::new(&POLICIES).unwrap();
+    let mut bus_state = SMBusState::default();
+
+    let result = write(&mut map, &mut bus_state, &[0x23, 0x00, 0x10]);
+    assert_eq!(Err(SMBusProtocolError::ReservedBitsSet(0x23)), result);
+    assert_eq!(Some(0), map.word(0x23));
+
+    write(&mut map, &mut bus_state, &[0x23, 0xff, 0x0f]).unwrap();
+    assert_eq!(Some(0x0fff), map.word(0x23));
+}
@@ -0,0 +1,288 @@
+use core::ops::RangeInclusive;
+
+use crate::{register_map::RegisterPolicy, AddressingMode, ByteOrder, CommandHandler, Unsupported};
+
+#[cfg(test)]
+mod test;
+
+/// Sub-handler serving an inclusive range of command codes.
+pub struct Route<'a, E> {
+    first: u8,
+    last: u8,
+    handler: &'a mut dyn CommandHandler<Error = E>,
+}
+
+impl<'a, E> Route<'a, E> {
+    pub fn new(codes: RangeInclusive<u8>, handler: &'a mut dyn CommandHandler<Error = E>) -> Self {
+        Self {
+            first: *codes.start(),
+            last: *codes.end(),
+            handler,
+        }
+    }
+
+    fn contains(&self, code: u8) -> bool {
+        (self.first..=self.last).contains(&code)
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum RouterError {
+    /// The command code is served by more than one route.
+    Overlap(u8),
+    EmptyRange(u8),
+    /// The route starting at the code, or the fallback for `None`, disagrees
+    /// with the first route on PEC or addressing mode.
+    Mismatch(Option<u8>),
+    /// The route starting at the code, or the fallback for `None`, uses an
+    /// addressing mode without command codes to route by.
+    Unroutable(Option<u8>),
+}
+
+/// Settings of a handler which apply to the whole bus transaction rather than
+/// to one command code, so all handlers of a router have to share them.
+fn bus_settings<E: From<Unsupported>>(
+    handler: &dyn CommandHandler<Error = E>,
+) -> (Option<u8>, bool, AddressingMode) {
+    (
+        handler.pec_address(),
+        handler.pec_required(),
+        handler.addressing_mode(),
+    )
+}
+
+fn is_routable(mode: AddressingMode) -> bool {
+    matches!(
+        mode,
+        AddressingMode::Command | AddressingMode::Sequential { .. }
+    )
+}
+
+/// [`CommandHandler`] built from reusable blocks: each command code is passed to
+/// the route containing it, codes outside every route and receive byte to the
+/// fallback. PEC and addressing mode are taken from the first route, or the
+/// fallback without routes; the others have to agree.
+pub struct Router<'a, E, const N: usize> {
+    routes: [Route<'a, E>; N],
+    fallback: Option<&'a mut dyn CommandHandler<Error = E>>,
+}
+
+impl<'a, E: From<Unsupported>, const N: usize> Router<'a, E, N> {
+    pub fn new(routes: [Route<'a, E>; N]) -> Result<Self, RouterError> {
+        for (i, route) in routes.iter().enumerate() {
+            if route.first > route.last {
+                return Err(RouterError::EmptyRange(route.first));
+            }
+            for other in &routes[..i] {
+                if route.first <= other.last && other.first <= route.last {
+                    return Err(RouterError::Overlap(route.first.max(other.first)));
+                }
+            }
+            if !is_routable(route.handler.addressing_mode()) {
+                return Err(RouterError::Unroutable(Some(route.first)));
+            }
+            if bus_settings(&*route.handler) != bus_settings(&*routes[0].handler) {
+                return Err(RouterError::Mismatch(Some(route.first)));
+            }
+        }
+        Ok(Self {
+            routes,
+            fallback: None,
+        })
+    }
+
+    pub fn with_fallback(
+        mut self,
+        fallback: &'a mut dyn CommandHandler<Error = E>,
+    ) -> Result<Self, RouterError> {
+        if !is_routable(fallback.addressing_mode()) {
+            return Err(RouterError::Unroutable(None));
+        }
+        if self
+            .routes
+            .first()
+            .is_some_and(|route| bus_settings(&*route.handler) != bus_settings(&*fallback))
+        {
+            return Err(RouterError::Mismatch(None));
+        }
+        self.fallback = Some(fallback);
+        Ok(self)
+    }
+
+    /// Handler whose bus settings the router reports.
+    fn first(&self) -> Option<&dyn CommandHandler<Error = E>> {
+        match self.routes.first() {
+            Some(route) => Some(&*route.handler),
+            None => self.fallback.as_deref(),
+        }
+    }
+
+    fn route(&self, code: u8) -> Option<&dyn CommandHandler<Error = E>> {
+        match self.routes.iter().find(|route| route.contains(code)) {
+            Some(route) => Some(&*route.handler),
+            None => self.fallback.as_deref(),
+        }
+    }
+
+    fn route_mut(&mut self, code: u8) -> Option<&mut (dyn CommandHandler<Error = E> + 'a)> {
+        match self.routes.iter().position(|route| route.contains(code)) {
+            Some(i) => Some(&mut *self.routes[i].handler),
+            None => self.fallback_mut(),
+        }
+    }
+
+    fn fallback_mut(&mut self) -> Option<&mut (dyn CommandHandler<Error = E> + 'a)> {
+        self.fallback.as_deref_mut()
+    }
+
+    fn for_each(&mut self, mut f: impl FnMut(&mut dyn CommandHandler<Error = E>)) {
+        for route in self.routes.iter_mut() {
+            f(&mut *route.handler);
+        }
+        if let Some(fallback) = self.fallback_mut() {
+            f(fallback);
+        }
+    }
+}
+
+impl<'a, E: From<Unsupported>, const N: usize> CommandHandler for Router<'a, E, N> {
+    type Error = E;
+
+    fn handle_read_byte(&mut self) -> Option<u8> {
+        self.fallback_mut()?.handle_read_byte()
+    }
+
+    fn handle_read_byte_data(&mut self, reg: u8) -> Option<u8> {
+        self.route_mut(reg)?.handle_read_byte_data(reg)
+    }
+
+    fn handle_read_word_data(&mut self, reg: u8) -> Option<u16> {
+        self.route_mut(reg)?.handle_read_word_data(reg)
+    }
+
+    fn handle_read_block_data(&mut self, reg: u8, index: u8) -> Option<u8> {
+        self.route_mut(reg)?.handle_read_block_data(reg, index)
+    }
+
+    fn handle_read_complete(&mut self, reg: Option<u8>, count: u8) {
+        let handler = match reg {
+            Some(reg) => self.route_mut(reg),
+            None => self.fallback_mut(),
+        };
+        if let Some(handler) = handler {
+            handler.handle_read_complete(reg, count);
+        }
+    }
+
+    fn handle_block_process_call(&mut self, reg: u8, request: &[u8], index: u8) -> Option<u8> {
+        self.route_mut(reg)?
+            .handle_block_process_call(reg, request, index)
+    }
+
+    fn handle_write_byte(&mut self, data: u8) -> Result<(), Self::Error> {
+        self.route_mut(data)
+            .ok_or(Unsupported)?
+            .handle_write_byte(data)
+    }
+
+    fn handle_write_byte_data(&mut self, reg: u8, data: u8) -> Result<(), Self::Error> {
+        self.route_mut(reg)
+            .ok_or(Unsupported)?
+            .handle_write_byte_data(reg, data)
+    }
+
+    fn handle_write_word_data(&mut self, reg: u8, data: u16) -> Result<(), Self::Error> {
+        self.route_mut(reg)
+            .ok_or(Unsupported)?
+            .handle_write_word_data(reg, data)
+    }
+
+    fn handle_write_block_data(
+        &mut self,
+        reg: u8,
+        count: u8,
+        block: &[u8],
+    ) -> Result<(), Self::Error> {
+        self.route_mut(reg)
+            .ok_or(Unsupported)?
+            .handle_write_block_data(reg, count, block)
+    }
+
+    fn is_streamed_block_write(&self, reg: u8) -> bool {
+        self.route(reg)
+            .is_some_and(|handler| handler.is_streamed_block_write(reg))
+    }
+
+    fn handle_write_block_start(&mut self, reg: u8, count: u8) -> Result<(), Self::Error> {
+        self.route_mut(reg)
+            .ok_or(Unsupported)?
+            .handle_write_block_start(reg, count)
+    }
+
+    fn handle_write_block_chunk(
+        &mut self,
+        reg: u8,
+        offset: u8,
+        chunk: &[u8],
+    ) -> Result<(), Self::Error> {
+        self.route_mut(reg)
+            .ok_or(Unsupported)?
+            .handle_write_block_chunk(reg, offset, chunk)
+    }
+
+    fn handle_write_block_commit(&mut self, reg: u8) -> Result<(), Self::Error> {
+        self.route_mut(reg)
+            .ok_or(Unsupported)?
+            .handle_write_block_commit(reg)
+    }
+
+    fn handle_write_block_abort(&mut self, reg: u8) {
+        if let Some(handler) = self.route_mut(reg) {
+            handler.handle_write_block_abort(reg);
+        }
+    }
+
+    fn on_start(&mut self) {
+        self.for_each(|handler| handler.on_start());
+    }
+
+    fn register_policy(&self, reg: u8) -> Option<RegisterPolicy> {
+        self.route(reg)?.register_policy(reg)
+    }
+
+    fn addressing_mode(&self) -> AddressingMode {
+        self.first()
+            .map_or(AddressingMode::Command, |handler| handler.addressing_mode())
+    }
+
+    fn pec_address(&self) -> Option<u8> {
+        self.first()?.pec_address()
+    }
+
+    fn pec_required(&self) -> bool {
+        self.first().is_some_and(|handler| handler.pec_required())
+    }
+
+    fn byte_order(&self, reg: u8) -> ByteOrder {
+        self.route(reg)
+            .map_or(ByteOrder::LittleEndian, |handler| handler.byte_order(reg))
+    }
+
+    fn on_read_begin(&mut self, reg: u8) {
+        if let Some(handler) = self.route_mut(reg) {
+            handler.on_read_begin(reg);
+        }
+    }
+
+    fn on_stop(&mut self) {
+        self.for_each(|handler| handler.on_stop());
+    }
+
+    fn on_abort(&mut self) {
+        self.for_each(|handler| handler.on_abort());
+    }
+
+    fn on_pec_error(&mut self) {
+        self.for_each(|handler| handler.on_pec_error());
+    }
+}
@@ -0,0 +1,164 @@
+use crate::register_map::*;
+use crate::router::*;
+use crate::test::{read, write};
+use crate::*;
+
+static IDENTIFICATION: [RegisterDescriptor; 1] = [RegisterDescriptor {
+    code: 0x10,
+    width: Width::Block(4),
+    access: Access::ReadOnly,
+    reset: b"ACME",
+    reserved: 0,
+    offset: 0,
+}];
+
+static CALIBRATION: [RegisterDescriptor; 2] = [
+    RegisterDescriptor {
+        code: 0x30,
+        width: Width::Word,
+        access: Access::ReadWrite,
+        reset: &[0x00, 0x40],
+        reserved: 0,
+        offset: 0,
+    },
+    RegisterDescriptor {
+        code: 0x31,
+        width: Width::Word,
+        access: Access::ReadWrite,
+        reset: &[],
+        reserved: 0,
+        offset: 2,
+    },
+];
+
+#[derive(Default)]
+struct Status {
+    faults: u8,
+}
+
+impl CommandHandler for Status {
+    type Error = RegisterError;
+
+    fn handle_read_byte(&mut self) -> Option<u8> {
+        Some(self.faults)
+    }
+
+    /// CLEAR_FAULTS
+    fn handle_write_byte(&mut self, data: u8) -> Result<(), RegisterError> {
+        if data != 0x03 {
+            return Err(RegisterError::UnknownRegister(data));
+        }
+        self.faults = 0;
+        Ok(())
+    }
+}
+
+/// Word register behind a device using PEC.
+#[derive(Default)]
+struct Checked {
+    word: u16,
+    pec_errors: usize,
+}
+
+impl CommandHandler for Checked {
+    type Error = RegisterError;
+
+    fn handle_write_word_data(&mut self, _reg: u8, data: u16) -> Result<(), RegisterError> {
+        self.word = data;
+        Ok(())
+    }
+
+    fn pec_address(&self) -> Option<u8> {
+        Some(0x2a)
+    }
+
+    fn on_pec_error(&mut self) {
+        self.pec_errors += 1;
+    }
+}
+
+#[test]
+fn test_routes_command_codes() {
+    let mut identification = RegisterMap::<5>::new(&IDENTIFICATION).unwrap();
+    let mut calibration = RegisterMap::<4>::new(&CALIBRATION).unwrap();
+    let mut status = Status { faults: 0x81 };
+    let mut router = Router::new([
+        Route::new(0x10..=0x1f, &mut identification),
+        Route::new(0x30..=0x3f, &mut calibration),
+    ])
+    .unwrap()
+    .with_fallback(&mut status)
+    .unwrap();
+    let mut bus_state = SMBusState::default();
+
+    let mut block = [0; 5];
+    read(&mut router, &mut bus_state, &[0x10], &mut block).unwrap();
+    assert_eq!(*b"\x04ACME", block);
+
+    write(&mut router, &mut bus_state, &[0x31, 0x34, 0x12]).unwrap();
+    let result = write(&mut router, &mut bus_state, &[0x10, 1, 0]);
+    assert_eq!(Err(SMBusProtocolError::ReadOnlyRegister(0x10)), result);
+
+    let mut faults = [0];
+    read(&mut router, &mut bus_state, &[], &mut faults).unwrap();
+    assert_eq!([0x81], faults);
+    write(&mut router, &mut bus_state, &[0x03]).unwrap();
+
+    assert_eq!(Some(0x1234), calibration.word(0x31));
+    assert_eq!(0, status.faults);
+}
+
+#[test]
+fn test_without_fallback() {
+    let mut calibration = RegisterMap::<4>::new(&CALIBRATION).unwrap();
+    let mut router = Router::new([Route::new(0x30..=0x3f, &mut calibration)]).unwrap();
+    let mut bus_state = SMBusState::default();
+
+    let result = write(&mut router, &mut bus_state, &[0x20, 0x01]);
+    assert_eq!(Err(SMBusProtocolError::InvalidWriteRegister(0x20)), result);
+    let mut byte = [0];
+    let result = read(&mut router, &mut bus_state, &[], &mut byte);
+    assert_eq!(Err(SMBusProtocolError::ReadByteUnsupported), result);
+}
+
+#[test]
+fn test_overlapping_routes() {
+    let mut identification = RegisterMap::<5>::new(&IDENTIFICATION).unwrap();
+    let mut calibration = RegisterMap::<4>::new(&CALIBRATION).unwrap();
+    let result = Router::new([
+        Route::new(0x10..=0x30, &mut identification),
+        Route::new(0x30..=0x3f, &mut calibration),
+    ]);
+    assert_eq!(Some(RouterError::Overlap(0x30)), result.err());
+
+    #[allow(clippy::reversed_empty_ranges)]
+    let result = Router::new([Route::new(0x3f..=0x30, &mut calibration)]);
+    assert_eq!(Some(RouterError::EmptyRange(0x3f)), result.err());
+}
+
+#[test]
+fn test_routes_share_pec() {
+    let mut checked = Checked::default();
+    let mut router = Router::new([Route::new(0x40..=0x4f, &mut checked)]).unwrap();
+    let mut bus_state = SMBusState::default();
+    let crc = pec::update(pec::compute(&[0x54]), &[0x40, 0x34, 0x12]);
+
+    write(&mut router, &mut bus_state, &[0x40, 0x34, 0x12, crc]).unwrap();
+    let result = write(&mut router, &mut bus_state, &[0x40, 0x00, 0x00, crc]);
+    assert_eq!(Err(SMBusProtocolError::InvalidPec), result);
+    assert_eq!(0x1234, checked.word);
+    assert_eq!(1, checked.pec_errors);
+
+    let mut calibration = RegisterMap::<4>::new(&CALIBRATION).unwrap();
+    let result = Router::new([
+        Route::new(0x30..=0x3f, &mut calibration),
+        Route::new(0x40..=0x4f, &mut checked),
+    ]);
+    assert_eq!(Some(RouterError::Mismatch(Some(0x40))), result.err());
+
+    let mut status = Status::default();
+    let result = Router::new([Route::new(0x40..=0x4f, &mut checked)])
+        .unwrap()
+        .with_fallback(&mut status);
+    assert_eq!(Some(RouterError::Mismatch(None)), result.err());
+}
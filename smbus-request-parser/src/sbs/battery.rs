@@ -0,0 +1,400 @@
+//! Smart Battery Data commands.
+
+use super::{block_byte, commands, lookup, CommandInfo, Unit};
+use crate::{
+    register_map::{Access, AccessWidths, RegisterPolicy},
+    CommandHandler, Unsupported,
+};
+
+commands! {
+    MANUFACTURER_ACCESS = 0x00, WORD, Raw, ReadWrite;
+    REMAINING_CAPACITY_ALARM = 0x01, WORD, Capacity, ReadWrite;
+    REMAINING_TIME_ALARM = 0x02, WORD, Minutes, ReadWrite;
+    BATTERY_MODE = 0x03, WORD, Flags, ReadWrite;
+    AT_RATE = 0x04, WORD, Rate, ReadWrite;
+    AT_RATE_TIME_TO_FULL = 0x05, WORD, Minutes, ReadOnly;
+    AT_RATE_TIME_TO_EMPTY = 0x06, WORD, Minutes, ReadOnly;
+    AT_RATE_OK = 0x07, WORD, Flags, ReadOnly;
+    TEMPERATURE = 0x08, WORD, DeciKelvin, ReadOnly;
+    VOLTAGE = 0x09, WORD, MilliVolts, ReadOnly;
+    CURRENT = 0x0a, WORD, MilliAmps, ReadOnly;
+    AVERAGE_CURRENT = 0x0b, WORD, MilliAmps, ReadOnly;
+    MAX_ERROR = 0x0c, WORD, Percent, ReadOnly;
+    RELATIVE_STATE_OF_CHARGE = 0x0d, WORD, Percent, ReadOnly;
+    ABSOLUTE_STATE_OF_CHARGE = 0x0e, WORD, Percent, ReadOnly;
+    REMAINING_CAPACITY = 0x0f, WORD, Capacity, ReadOnly;
+    FULL_CHARGE_CAPACITY = 0x10, WORD, Capacity, ReadOnly;
+    RUN_TIME_TO_EMPTY = 0x11, WORD, Minutes, ReadOnly;
+    AVERAGE_TIME_TO_EMPTY = 0x12, WORD, Minutes, ReadOnly;
+    AVERAGE_TIME_TO_FULL = 0x13, WORD, Minutes, ReadOnly;
+    CHARGING_CURRENT = 0x14, WORD, MilliAmps, ReadOnly;
+    CHARGING_VOLTAGE = 0x15, WORD, MilliVolts, ReadOnly;
+    BATTERY_STATUS = 0x16, WORD, Flags, ReadOnly;
+    CYCLE_COUNT = 0x17, WORD, Count, ReadOnly;
+    DESIGN_CAPACITY = 0x18, WORD, Capacity, ReadOnly;
+    DESIGN_VOLTAGE = 0x19, WORD, MilliVolts, ReadOnly;
+    SPECIFICATION_INFO = 0x1a, WORD, Raw, ReadOnly;
+    MANUFACTURE_DATE = 0x1b, WORD, Date, ReadOnly;
+    SERIAL_NUMBER = 0x1c, WORD, Raw, ReadOnly;
+    MANUFACTURER_NAME = 0x20, BLOCK, Text, ReadOnly;
+    DEVICE_NAME = 0x21, BLOCK, Text, ReadOnly;
+    DEVICE_CHEMISTRY = 0x22, BLOCK, Text, ReadOnly;
+    MANUFACTURER_DATA = 0x23, BLOCK, Raw, ReadOnly;
+}
+
+pub fn info(code: u8) -> Option<&'static CommandInfo> {
+    lookup(COMMANDS, code)
+}
+
+/// Bits of BatteryMode.
+pub mod mode {
+    pub const INTERNAL_CHARGE_CONTROLLER: u16 = 1 << 0;
+    pub const PRIMARY_BATTERY_SUPPORT: u16 = 1 << 1;
+    pub const CONDITION_FLAG: u16 = 1 << 7;
+    pub const CHARGE_CONTROLLER_ENABLED: u16 = 1 << 8;
+    pub const PRIMARY_BATTERY: u16 = 1 << 9;
+    pub const ALARM_MODE: u16 = 1 << 13;
+    pub const CHARGER_MODE: u16 = 1 << 14;
+    /// Capacities are reported in 10 mWh and rates in 10 mW instead of mAh and mA.
+    pub const CAPACITY_MODE: u16 = 1 << 15;
+    /// Bits the host may change.
+    pub const WRITABLE: u16 =
+        CHARGE_CONTROLLER_ENABLED | PRIMARY_BATTERY | ALARM_MODE | CHARGER_MODE | CAPACITY_MODE;
+}
+
+/// Bits of BatteryStatus; the low nibble holds an [`ErrorCode`].
+pub mod status {
+    pub const OVER_CHARGED_ALARM: u16 = 1 << 15;
+    pub const TERMINATE_CHARGE_ALARM: u16 = 1 << 14;
+    pub const OVER_TEMP_ALARM: u16 = 1 << 12;
+    pub const TERMINATE_DISCHARGE_ALARM: u16 = 1 << 11;
+    pub const REMAINING_CAPACITY_ALARM: u16 = 1 << 9;
+    pub const REMAINING_TIME_ALARM: u16 = 1 << 8;
+    pub const INITIALIZED: u16 = 1 << 7;
+    pub const DISCHARGING: u16 = 1 << 6;
+    pub const FULLY_CHARGED: u16 = 1 << 5;
+    pub const FULLY_DISCHARGED: u16 = 1 << 4;
+}
+
+/// Result of the previous command, reported in BatteryStatus.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+#[repr(u16)]
+pub enum ErrorCode {
+    #[default]
+    Ok = 0,
+    Busy = 1,
+    ReservedCommand = 2,
+    UnsupportedCommand = 3,
+    AccessDenied = 4,
+    OverflowUnderflow = 5,
+    BadSize = 6,
+    UnknownError = 7,
+}
+
+/// Minutes reported when a time estimate does not apply.
+pub const NOT_APPLICABLE: u16 = 0xffff;
+
+/// Measurements and identification of a smart battery, in SBS units.
+/// Capacities and currents are always in mAh and mA; [`Battery`] converts
+/// them when the host selects power units.
+pub trait SmartBattery {
+    type Error: From<Unsupported>;
+
+    /// 0.1 K.
+    fn temperature(&mut self) -> u16;
+    /// mV.
+    fn voltage(&mut self) -> u16;
+    /// mA, positive while charging.
+    fn current(&mut self) -> i16;
+    fn relative_state_of_charge(&mut self) -> u8;
+    /// mAh.
+    fn remaining_capacity(&mut self) -> u16;
+    /// mAh.
+    fn full_charge_capacity(&mut self) -> u16;
+    /// mAh.
+    fn design_capacity(&self) -> u16;
+    /// mV.
+    fn design_voltage(&self) -> u16;
+    /// [`status`] bits; alarms set by [`Battery`] and the error code are added to them.
+    fn battery_status(&mut self) -> u16;
+    fn serial_number(&self) -> u16;
+    fn manufacturer_name(&self) -> &[u8];
+    fn device_name(&self) -> &[u8];
+    fn device_chemistry(&self) -> &[u8];
+
+    fn average_current(&mut self) -> i16 {
+        self.current()
+    }
+
+    fn max_error(&mut self) -> u8 {
+        0
+    }
+
+    fn absolute_state_of_charge(&mut self) -> u8 {
+        let design = self.design_capacity().max(1) as u32;
+        (self.remaining_capacity() as u32 * 100 / design).min(u8::MAX as u32) as u8
+    }
+
+    /// mA the battery wants to be charged with.
+    fn charging_current(&mut self) -> u16 {
+        0
+    }
+
+    /// mV the battery wants to be charged with.
+    fn charging_voltage(&mut self) -> u16 {
+        0
+    }
+
+    fn cycle_count(&mut self) -> u16 {
+        0
+    }
+
+    fn manufacture_date(&self) -> u16 {
+        0
+    }
+
+    /// SBS 1.1 without PEC and without scaling.
+    fn specification_info(&self) -> u16 {
+        0x0021
+    }
+
+    /// Read-only [`mode`] bits: capabilities and the condition flag.
+    fn mode_flags(&mut self) -> u16 {
+        0
+    }
+
+    fn manufacturer_data(&self) -> &[u8] {
+        &[]
+    }
+
+    fn manufacturer_access(&mut self) -> u16 {
+        0
+    }
+
+    fn write_manufacturer_access(&mut self, _data: u16) -> Result<(), Self::Error> {
+        Err(Unsupported.into())
+    }
+}
+
+/// [`CommandHandler`] serving the Smart Battery Data commands of a [`SmartBattery`].
+/// The settings written by the host (BatteryMode, alarms, AtRate) are kept here.
+#[derive(Debug, Clone)]
+pub struct Battery<B> {
+    battery: B,
+    mode: u16,
+    remaining_capacity_alarm: u16,
+    remaining_time_alarm: u16,
+    at_rate: i16,
+    error: ErrorCode,
+}
+
+impl<B: SmartBattery> Battery<B> {
+    /// Alarms start at the SBS defaults of 10% of the design capacity and 10 minutes.
+    pub fn new(battery: B) -> Self {
+        let remaining_capacity_alarm = battery.design_capacity() / 10;
+        Self {
+            battery,
+            mode: 0,
+            remaining_capacity_alarm,
+            remaining_time_alarm: 10,
+            at_rate: 0,
+            error: ErrorCode::Ok,
+        }
+    }
+
+    pub fn battery(&self) -> &B {
+        &self.battery
+    }
+
+    pub fn battery_mut(&mut self) -> &mut B {
+        &mut self.battery
+    }
+
+    pub fn into_inner(self) -> B {
+        self.battery
+    }
+
+    pub fn mode(&self) -> u16 {
+        self.mode
+    }
+
+    pub fn at_rate(&self) -> i16 {
+        self.at_rate
+    }
+
+    fn power_units(&self) -> bool {
+        self.mode & mode::CAPACITY_MODE != 0
+    }
+
+    /// mAh to the capacity unit selected by BatteryMode, at the design voltage.
+    fn capacity(&self, mah: u16) -> u16 {
+        if !self.power_units() {
+            return mah;
+        }
+        let mwh = mah as u32 * self.battery.design_voltage() as u32 / 1000;
+        (mwh / 10).min(u16::MAX as u32) as u16
+    }
+
+    /// Minutes until `capacity` is used up at `rate`, both in the same unit.
+    fn minutes(capacity: u16, rate: i32) -> u16 {
+        if rate <= 0 {
+            return NOT_APPLICABLE;
+        }
+        (capacity as i32 * 60 / rate).min(NOT_APPLICABLE as i32 - 1) as u16
+    }
+
+    fn time_to_empty(&mut self, current: i16) -> u16 {
+        Self::minutes(self.battery.remaining_capacity(), -(current as i32))
+    }
+
+    fn time_to_full(&mut self, current: i16) -> u16 {
+        let missing = self
+            .battery
+            .full_charge_capacity()
+            .saturating_sub(self.battery.remaining_capacity());
+        Self::minutes(missing, current as i32)
+    }
+
+    fn status(&mut self) -> u16 {
+        let mut status = self.battery.battery_status() & !0x000f;
+        let remaining = self.battery.remaining_capacity();
+        let remaining = self.capacity(remaining);
+        if remaining < self.remaining_capacity_alarm {
+            status |= status::REMAINING_CAPACITY_ALARM;
+        }
+        let average = self.battery.average_current();
+        if self.time_to_empty(average) < self.remaining_time_alarm {
+            status |= status::REMAINING_TIME_ALARM;
+        }
+        status | self.error as u16
+    }
+
+    fn word(&mut self, reg: u8) -> Option<u16> {
+        let remaining = self.battery.remaining_capacity();
+        let remaining = self.capacity(remaining);
+        let full = self.battery.full_charge_capacity();
+        let full = self.capacity(full);
+        let value = match reg {
+            MANUFACTURER_ACCESS => self.battery.manufacturer_access(),
+            REMAINING_CAPACITY_ALARM => self.remaining_capacity_alarm,
+            REMAINING_TIME_ALARM => self.remaining_time_alarm,
+            BATTERY_MODE => self.mode | (self.battery.mode_flags() & !mode::WRITABLE),
+            AT_RATE => self.at_rate as u16,
+            AT_RATE_TIME_TO_FULL => {
+                Self::minutes(full.saturating_sub(remaining), self.at_rate as i32)
+            }
+            AT_RATE_TIME_TO_EMPTY => Self::minutes(remaining, -(self.at_rate as i32)),
+            AT_RATE_OK => (self.at_rate >= 0 || remaining > 0) as u16,
+            TEMPERATURE => self.battery.temperature(),
+            VOLTAGE => self.battery.voltage(),
+            CURRENT => self.battery.current() as u16,
+            AVERAGE_CURRENT => self.battery.average_current() as u16,
+            MAX_ERROR => self.battery.max_error() as u16,
+            RELATIVE_STATE_OF_CHARGE => self.battery.relative_state_of_charge() as u16,
+            ABSOLUTE_STATE_OF_CHARGE => self.battery.absolute_state_of_charge() as u16,
+            REMAINING_CAPACITY => remaining,
+            FULL_CHARGE_CAPACITY => full,
+            RUN_TIME_TO_EMPTY => {
+                let current = self.battery.current();
+                self.time_to_empty(current)
+            }
+            AVERAGE_TIME_TO_EMPTY => {
+                let current = self.battery.average_current();
+                self.time_to_empty(current)
+            }
+            AVERAGE_TIME_TO_FULL => {
+                let current = self.battery.average_current();
+                self.time_to_full(current)
+            }
+            CHARGING_CURRENT => self.battery.charging_current(),
+            CHARGING_VOLTAGE => self.battery.charging_voltage(),
+            BATTERY_STATUS => self.status(),
+            CYCLE_COUNT => self.battery.cycle_count(),
+            DESIGN_CAPACITY => self.capacity(self.battery.design_capacity()),
+            DESIGN_VOLTAGE => self.battery.design_voltage(),
+            SPECIFICATION_INFO => self.battery.specification_info(),
+            MANUFACTURE_DATE => self.battery.manufacture_date(),
+            SERIAL_NUMBER => self.battery.serial_number(),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    /// Check `reg` is known, readable or writable as requested, and of `width`.
+    fn check(&mut self, reg: u8, width: AccessWidths, write: bool) -> bool {
+        self.error = match info(reg) {
+            None => ErrorCode::UnsupportedCommand,
+            Some(info) if info.width != width || (write && !info.access.is_writable()) => {
+                ErrorCode::AccessDenied
+            }
+            Some(_) => ErrorCode::Ok,
+        };
+        self.error == ErrorCode::Ok
+    }
+}
+
+impl<B: SmartBattery> CommandHandler for Battery<B> {
+    type Error = B::Error;
+
+    fn handle_read_word_data(&mut self, reg: u8) -> Option<u16> {
+        if reg == BATTERY_STATUS {
+            // Reports the result of the previous command.
+            let status = self.status();
+            self.error = ErrorCode::Ok;
+            return Some(status);
+        }
+        if !self.check(reg, AccessWidths::WORD, false) {
+            return None;
+        }
+        self.word(reg)
+    }
+
+    fn handle_read_block_data(&mut self, reg: u8, index: u8) -> Option<u8> {
+        if index == 0 && !self.check(reg, AccessWidths::BLOCK, false) {
+            return None;
+        }
+        match reg {
+            MANUFACTURER_NAME => block_byte(self.battery.manufacturer_name(), index),
+            DEVICE_NAME => block_byte(self.battery.device_name(), index),
+            DEVICE_CHEMISTRY => block_byte(self.battery.device_chemistry(), index),
+            MANUFACTURER_DATA => block_byte(self.battery.manufacturer_data(), index),
+            _ => None,
+        }
+    }
+
+    fn handle_write_word_data(&mut self, reg: u8, data: u16) -> Result<(), Self::Error> {
+        if !self.check(reg, AccessWidths::WORD, true) {
+            return Err(Unsupported.into());
+        }
+        match reg {
+            MANUFACTURER_ACCESS => {
+                let result = self.battery.write_manufacturer_access(data);
+                if result.is_err() {
+                    self.error = ErrorCode::UnknownError;
+                }
+                return result;
+            }
+            REMAINING_CAPACITY_ALARM => self.remaining_capacity_alarm = data,
+            REMAINING_TIME_ALARM => self.remaining_time_alarm = data,
+            BATTERY_MODE => self.mode = data & mode::WRITABLE,
+            AT_RATE => self.at_rate = data as i16,
+            _ => unreachable!("every writable command is handled"),
+        }
+        Ok(())
+    }
+
+    /// Widths only; access is checked by the hooks so that it is reported in BatteryStatus.
+    fn register_policy(&self, reg: u8) -> Option<RegisterPolicy> {
+        let info = info(reg)?;
+        Some(RegisterPolicy {
+            widths: info.width,
+            ..RegisterPolicy::new(Access::ReadWrite)
+        })
+    }
+
+    fn on_abort(&mut self) {
+        if self.error == ErrorCode::Ok {
+            self.error = ErrorCode::BadSize;
+        }
+    }
+}
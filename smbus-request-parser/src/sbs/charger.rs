@@ -0,0 +1,225 @@
+//! Smart Battery Charger commands.
+
+use super::{commands, lookup, CommandInfo, Unit};
+use crate::{
+    register_map::RegisterPolicy, sbs::battery::status as battery_status, CommandHandler,
+    Unsupported,
+};
+
+commands! {
+    CHARGER_SPEC_INFO = 0x11, WORD, Raw, ReadOnly;
+    CHARGER_MODE = 0x12, WORD, Flags, WriteOnly;
+    CHARGER_STATUS = 0x13, WORD, Flags, ReadOnly;
+    CHARGING_CURRENT = 0x14, WORD, MilliAmps, WriteOnly;
+    CHARGING_VOLTAGE = 0x15, WORD, MilliVolts, WriteOnly;
+    ALARM_WARNING = 0x16, WORD, Flags, WriteOnly;
+}
+
+pub fn info(code: u8) -> Option<&'static CommandInfo> {
+    lookup(COMMANDS, code)
+}
+
+/// Bits of ChargerMode.
+pub mod mode {
+    pub const INHIBIT_CHARGE: u16 = 1 << 0;
+    pub const ENABLE_POLLING: u16 = 1 << 1;
+    pub const POR_RESET: u16 = 1 << 2;
+    pub const RESET_TO_ZERO: u16 = 1 << 3;
+}
+
+/// Bits of ChargerStatus.
+pub mod status {
+    pub const CHARGE_INHIBITED: u16 = 1 << 0;
+    pub const POLLING_ENABLED: u16 = 1 << 1;
+    pub const VOLTAGE_NOTREG: u16 = 1 << 2;
+    pub const CURRENT_NOTREG: u16 = 1 << 3;
+    pub const LEVEL_2: u16 = 1 << 4;
+    pub const LEVEL_3: u16 = 1 << 5;
+    pub const CURRENT_OR: u16 = 1 << 6;
+    pub const VOLTAGE_OR: u16 = 1 << 7;
+    pub const RES_OR: u16 = 1 << 8;
+    pub const RES_COLD: u16 = 1 << 9;
+    pub const RES_HOT: u16 = 1 << 10;
+    pub const RES_UR: u16 = 1 << 11;
+    pub const ALARM_INHIBITED: u16 = 1 << 12;
+    pub const POWER_FAIL: u16 = 1 << 13;
+    pub const BATTERY_PRESENT: u16 = 1 << 14;
+    pub const AC_PRESENT: u16 = 1 << 15;
+    /// Bits maintained by [`super::Charger`].
+    pub const PROTOCOL: u16 =
+        CHARGE_INHIBITED | POLLING_ENABLED | CURRENT_OR | VOLTAGE_OR | ALARM_INHIBITED;
+}
+
+/// AlarmWarning bits that make the charger stop charging.
+pub const INHIBITING_ALARMS: u16 = battery_status::OVER_CHARGED_ALARM
+    | battery_status::TERMINATE_CHARGE_ALARM
+    | battery_status::OVER_TEMP_ALARM;
+
+/// Charging hardware driven by [`Charger`].
+pub trait SmartCharger {
+    type Error: From<Unsupported>;
+
+    /// mA.
+    fn max_charging_current(&self) -> u16;
+    /// mV.
+    fn max_charging_voltage(&self) -> u16;
+
+    /// Apply the charging current (mA) and voltage (mV); both are zero while charging is inhibited.
+    fn set_charging(&mut self, current: u16, voltage: u16) -> Result<(), Self::Error>;
+
+    /// Hardware [`status`] bits; the bits in [`status::PROTOCOL`] are added by [`Charger`].
+    fn status(&mut self) -> u16;
+
+    /// Charger specification 1.1.
+    fn spec_info(&self) -> u16 {
+        0x0002
+    }
+
+    /// An AlarmWarning was received from the battery.
+    fn on_alarm(&mut self, _alarms: u16) {}
+}
+
+/// Settings received over the bus, cleared by POR_RESET.
+#[derive(Debug, Clone, Default)]
+struct State {
+    current: u16,
+    voltage: u16,
+    inhibited: bool,
+    polling: bool,
+    alarm_inhibited: bool,
+    /// ChargingCurrent and ChargingVoltage rewritten since the alarm, as bit 0 and 1.
+    rewritten: u8,
+    alarms: u16,
+}
+
+/// [`CommandHandler`] implementing the Smart Battery Charger commands on a [`SmartCharger`].
+/// Charging stops on an inhibiting AlarmWarning until both ChargingCurrent and
+/// ChargingVoltage are written again or the charger is reset.
+#[derive(Debug, Clone)]
+pub struct Charger<C> {
+    charger: C,
+    state: State,
+}
+
+impl<C: SmartCharger> Charger<C> {
+    pub fn new(charger: C) -> Self {
+        Self {
+            charger,
+            state: State::default(),
+        }
+    }
+
+    pub fn charger(&self) -> &C {
+        &self.charger
+    }
+
+    pub fn charger_mut(&mut self) -> &mut C {
+        &mut self.charger
+    }
+
+    pub fn into_inner(self) -> C {
+        self.charger
+    }
+
+    /// Last AlarmWarning received.
+    pub fn alarms(&self) -> u16 {
+        self.state.alarms
+    }
+
+    /// Requested charging current and voltage, before limiting.
+    pub fn requested(&self) -> (u16, u16) {
+        (self.state.current, self.state.voltage)
+    }
+
+    fn status(&mut self) -> u16 {
+        let state = &self.state;
+        let mut status = self.charger.status() & !status::PROTOCOL;
+        if state.inhibited {
+            status |= status::CHARGE_INHIBITED;
+        }
+        if state.polling {
+            status |= status::POLLING_ENABLED;
+        }
+        if state.current > self.charger.max_charging_current() {
+            status |= status::CURRENT_OR;
+        }
+        if state.voltage > self.charger.max_charging_voltage() {
+            status |= status::VOLTAGE_OR;
+        }
+        if state.alarm_inhibited {
+            status |= status::ALARM_INHIBITED;
+        }
+        status
+    }
+
+    fn apply(&mut self) -> Result<(), C::Error> {
+        let state = &self.state;
+        if state.inhibited || state.alarm_inhibited {
+            return self.charger.set_charging(0, 0);
+        }
+        let current = state.current.min(self.charger.max_charging_current());
+        let voltage = state.voltage.min(self.charger.max_charging_voltage());
+        self.charger.set_charging(current, voltage)
+    }
+
+    fn rewrite(&mut self, bit: u8) {
+        self.state.rewritten |= bit;
+        if self.state.rewritten == 0b11 {
+            self.state.alarm_inhibited = false;
+        }
+    }
+}
+
+impl<C: SmartCharger> CommandHandler for Charger<C> {
+    type Error = C::Error;
+
+    fn handle_read_word_data(&mut self, reg: u8) -> Option<u16> {
+        match reg {
+            CHARGER_SPEC_INFO => Some(self.charger.spec_info()),
+            CHARGER_STATUS => Some(self.status()),
+            _ => None,
+        }
+    }
+
+    fn handle_write_word_data(&mut self, reg: u8, data: u16) -> Result<(), Self::Error> {
+        match reg {
+            CHARGER_MODE => {
+                if data & mode::POR_RESET != 0 {
+                    self.state = State::default();
+                }
+                if data & mode::RESET_TO_ZERO != 0 {
+                    self.state.current = 0;
+                    self.state.voltage = 0;
+                }
+                self.state.inhibited = data & mode::INHIBIT_CHARGE != 0;
+                self.state.polling = data & mode::ENABLE_POLLING != 0;
+            }
+            CHARGING_CURRENT => {
+                self.state.current = data;
+                self.rewrite(0b01);
+            }
+            CHARGING_VOLTAGE => {
+                self.state.voltage = data;
+                self.rewrite(0b10);
+            }
+            ALARM_WARNING => {
+                self.state.alarms = data;
+                if data & INHIBITING_ALARMS != 0 {
+                    self.state.alarm_inhibited = true;
+                    self.state.rewritten = 0;
+                }
+                self.charger.on_alarm(data);
+            }
+            _ => return Err(Unsupported.into()),
+        }
+        self.apply()
+    }
+
+    fn register_policy(&self, reg: u8) -> Option<RegisterPolicy> {
+        let info = info(reg)?;
+        Some(RegisterPolicy {
+            widths: info.width,
+            ..RegisterPolicy::new(info.access)
+        })
+    }
+}
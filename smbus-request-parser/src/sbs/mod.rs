@@ -0,0 +1,83 @@
+//! Smart Battery System (SBS 1.1) target profiles.
+
+use crate::register_map::{Access, AccessWidths};
+
+pub mod battery;
+pub mod charger;
+pub mod selector;
+
+#[cfg(test)]
+mod test;
+
+/// Unit of an SBS value.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Unit {
+    MilliVolts,
+    MilliAmps,
+    /// mAh, or 10 mWh when the battery reports in power units.
+    Capacity,
+    /// mA, or 10 mW when the battery reports in power units.
+    Rate,
+    Minutes,
+    Percent,
+    /// 0.1 K.
+    DeciKelvin,
+    Count,
+    /// `(year - 1980) * 512 + month * 32 + day`, see [`date`].
+    Date,
+    Flags,
+    Text,
+    Raw,
+}
+
+/// Protocol details of an SBS command.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct CommandInfo {
+    pub code: u8,
+    pub name: &'static str,
+    /// [`AccessWidths::WORD`] or [`AccessWidths::BLOCK`].
+    pub width: AccessWidths,
+    pub unit: Unit,
+    pub access: Access,
+}
+
+/// Look up `code` in a table ordered by code.
+fn lookup(table: &'static [CommandInfo], code: u8) -> Option<&'static CommandInfo> {
+    table
+        .binary_search_by_key(&code, |command| command.code)
+        .ok()
+        .map(|i| &table[i])
+}
+
+/// Pack a manufacture date.
+pub const fn date(year: u16, month: u8, day: u8) -> u16 {
+    (year - 1980) * 512 + month as u16 * 32 + day as u16
+}
+
+/// Byte `index` of an SBS block read of `data`, truncated to 32 bytes.
+fn block_byte(data: &[u8], index: u8) -> Option<u8> {
+    let data = &data[..data.len().min(32)];
+    match index {
+        0 => Some(data.len() as u8),
+        n => data.get(n as usize - 1).copied(),
+    }
+}
+
+macro_rules! commands {
+    ($($name:ident = $code:literal, $width:ident, $unit:ident, $access:ident;)*) => {
+        $(pub const $name: u8 = $code;)*
+
+        /// Every command of the profile, ordered by code.
+        pub static COMMANDS: &[CommandInfo] = &[
+            $(CommandInfo {
+                code: $code,
+                name: stringify!($name),
+                width: crate::register_map::AccessWidths::$width,
+                unit: Unit::$unit,
+                access: crate::register_map::Access::$access,
+            },)*
+        ];
+    };
+}
+
+pub(crate) use commands;
@@ -0,0 +1,198 @@
+//! Smart Battery Selector commands.
+
+use super::{commands, lookup, CommandInfo, Unit};
+use crate::{register_map::RegisterPolicy, CommandHandler, Unsupported};
+
+commands! {
+    SELECTOR_STATE = 0x01, WORD, Flags, ReadWrite;
+    SELECTOR_PRESETS = 0x02, WORD, Flags, ReadWrite;
+    SELECTOR_INFO = 0x04, WORD, Flags, ReadOnly;
+}
+
+pub fn info(code: u8) -> Option<&'static CommandInfo> {
+    lookup(COMMANDS, code)
+}
+
+/// Bits of SelectorInfo besides the supported batteries in bits 3:0.
+pub mod selector_info {
+    /// Selector specification 1.1, in bits 7:4.
+    pub const REVISION_1_1: u16 = 0b0010 << 4;
+    pub const CHARGING_INDICATOR: u16 = 1 << 8;
+}
+
+/// SelectorState; every field is a mask of batteries, bit 0 being battery A.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct SelectorState {
+    /// Battery connected to the SMBus.
+    pub smb: u8,
+    /// Batteries powering the system.
+    pub power_by: u8,
+    /// Batteries being charged.
+    pub charge: u8,
+    /// Batteries present; read-only.
+    pub present: u8,
+}
+
+impl SelectorState {
+    pub const fn from_word(word: u16) -> Self {
+        Self {
+            smb: word as u8 & 0xf,
+            power_by: (word >> 4) as u8 & 0xf,
+            charge: (word >> 8) as u8 & 0xf,
+            present: (word >> 12) as u8 & 0xf,
+        }
+    }
+
+    pub const fn word(self) -> u16 {
+        (self.smb & 0xf) as u16
+            | ((self.power_by & 0xf) as u16) << 4
+            | ((self.charge & 0xf) as u16) << 8
+            | ((self.present & 0xf) as u16) << 12
+    }
+}
+
+/// SelectorPresets; masks of batteries like [`SelectorState`].
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct SelectorPresets {
+    /// Batteries the selector may switch to on its own.
+    pub ok_to_use: u8,
+    /// Battery to power the system from next.
+    pub use_next: u8,
+}
+
+impl SelectorPresets {
+    pub const fn from_word(word: u16) -> Self {
+        Self {
+            ok_to_use: word as u8 & 0xf,
+            use_next: (word >> 4) as u8 & 0xf,
+        }
+    }
+
+    pub const fn word(self) -> u16 {
+        (self.ok_to_use & 0xf) as u16 | ((self.use_next & 0xf) as u16) << 4
+    }
+}
+
+/// Battery switching hardware driven by [`Selector`].
+pub trait SmartSelector {
+    type Error: From<Unsupported>;
+
+    /// Mask of the battery slots, at most four.
+    fn supported(&self) -> u8;
+    /// Mask of the batteries currently present.
+    fn present(&mut self) -> u8;
+
+    /// Connect the batteries of `state`, already checked against the present ones.
+    fn switch(&mut self, state: SelectorState) -> Result<(), Self::Error>;
+
+    /// Bits of SelectorInfo besides the supported batteries.
+    fn info(&self) -> u16 {
+        selector_info::REVISION_1_1
+    }
+
+    fn set_presets(&mut self, _presets: SelectorPresets) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// [`CommandHandler`] implementing the Smart Battery Selector commands on a [`SmartSelector`].
+/// Writes to SelectorState select batteries for the SMBus, power and charging; at most one
+/// battery can be on the SMBus and only present batteries can be selected.
+#[derive(Debug, Clone)]
+pub struct Selector<S> {
+    selector: S,
+    state: SelectorState,
+    presets: SelectorPresets,
+}
+
+impl<S: SmartSelector> Selector<S> {
+    pub fn new(selector: S) -> Self {
+        Self {
+            selector,
+            state: SelectorState::default(),
+            presets: SelectorPresets::default(),
+        }
+    }
+
+    pub fn selector(&self) -> &S {
+        &self.selector
+    }
+
+    pub fn selector_mut(&mut self) -> &mut S {
+        &mut self.selector
+    }
+
+    pub fn into_inner(self) -> S {
+        self.selector
+    }
+
+    /// Current selection, without the present batteries.
+    pub fn state(&self) -> SelectorState {
+        self.state
+    }
+
+    pub fn presets(&self) -> SelectorPresets {
+        self.presets
+    }
+
+    fn select(&mut self, mut state: SelectorState) -> Result<(), S::Error> {
+        state.present = self.selector.present() & self.selector.supported();
+        let selected = state.smb | state.power_by | state.charge;
+        if selected & !state.present != 0 || state.smb.count_ones() > 1 {
+            return Err(Unsupported.into());
+        }
+        state.present = 0;
+        self.selector.switch(state)?;
+        self.state = state;
+        Ok(())
+    }
+}
+
+impl<S: SmartSelector> CommandHandler for Selector<S> {
+    type Error = S::Error;
+
+    fn handle_read_word_data(&mut self, reg: u8) -> Option<u16> {
+        match reg {
+            SELECTOR_STATE => {
+                let present = self.selector.present() & self.selector.supported();
+                Some(
+                    SelectorState {
+                        present,
+                        ..self.state
+                    }
+                    .word(),
+                )
+            }
+            SELECTOR_PRESETS => Some(self.presets.word()),
+            SELECTOR_INFO => {
+                Some(self.selector.supported() as u16 & 0xf | self.selector.info() & !0xf)
+            }
+            _ => None,
+        }
+    }
+
+    fn handle_write_word_data(&mut self, reg: u8, data: u16) -> Result<(), Self::Error> {
+        match reg {
+            SELECTOR_STATE => self.select(SelectorState::from_word(data)),
+            SELECTOR_PRESETS => {
+                let presets = SelectorPresets::from_word(data);
+                let supported = self.selector.supported();
+                if (presets.ok_to_use | presets.use_next) & !supported != 0 {
+                    return Err(Unsupported.into());
+                }
+                self.selector.set_presets(presets)?;
+                self.presets = presets;
+                Ok(())
+            }
+            _ => Err(Unsupported.into()),
+        }
+    }
+
+    fn register_policy(&self, reg: u8) -> Option<RegisterPolicy> {
+        let info = info(reg)?;
+        Some(RegisterPolicy {
+            widths: info.width,
+            ..RegisterPolicy::new(info.access)
+        })
+    }
+}
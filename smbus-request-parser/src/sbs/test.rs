@@ -0,0 +1,471 @@
+use crate::sbs::battery::{self, *};
+use crate::sbs::charger::{self, Charger, SmartCharger};
+use crate::sbs::selector::*;
+use crate::sbs::*;
+use crate::test::{read, write};
+use crate::*;
+
+struct Pack {
+    current: i16,
+    remaining: u16,
+    manufacturer_access: u16,
+}
+
+impl SmartBattery for Pack {
+    type Error = ();
+
+    fn temperature(&mut self) -> u16 {
+        2982
+    }
+
+    fn voltage(&mut self) -> u16 {
+        11_800
+    }
+
+    fn current(&mut self) -> i16 {
+        self.current
+    }
+
+    fn relative_state_of_charge(&mut self) -> u8 {
+        (self.remaining as u32 * 100 / 4000) as u8
+    }
+
+    fn remaining_capacity(&mut self) -> u16 {
+        self.remaining
+    }
+
+    fn full_charge_capacity(&mut self) -> u16 {
+        4000
+    }
+
+    fn design_capacity(&self) -> u16 {
+        4400
+    }
+
+    fn design_voltage(&self) -> u16 {
+        10_800
+    }
+
+    fn battery_status(&mut self) -> u16 {
+        let mut status = status::INITIALIZED;
+        if self.current < 0 {
+            status |= status::DISCHARGING;
+        }
+        status
+    }
+
+    fn serial_number(&self) -> u16 {
+        0x1234
+    }
+
+    fn manufacturer_name(&self) -> &[u8] {
+        b"ACME"
+    }
+
+    fn device_name(&self) -> &[u8] {
+        b"PACK-3S"
+    }
+
+    fn device_chemistry(&self) -> &[u8] {
+        b"LION"
+    }
+
+    fn write_manufacturer_access(&mut self, data: u16) -> Result<(), ()> {
+        self.manufacturer_access = data;
+        Ok(())
+    }
+}
+
+fn pack() -> Battery<Pack> {
+    Battery::new(Pack {
+        current: -1000,
+        remaining: 2000,
+        manufacturer_access: 0,
+    })
+}
+
+fn read_word(handler: &mut Battery<Pack>, bus_state: &mut SMBusState, reg: u8) -> u16 {
+    let mut word = [0; 2];
+    read(handler, bus_state, &[reg], &mut word).unwrap();
+    u16::from_le_bytes(word)
+}
+
+#[test]
+fn test_command_table() {
+    assert!(battery::COMMANDS
+        .windows(2)
+        .all(|pair| pair[0].code < pair[1].code));
+    let command = battery::info(CURRENT).unwrap();
+    assert_eq!("CURRENT", command.name);
+    assert_eq!(Unit::MilliAmps, command.unit);
+    assert_eq!(register_map::Access::ReadOnly, command.access);
+    assert_eq!(
+        register_map::AccessWidths::BLOCK,
+        battery::info(DEVICE_NAME).unwrap().width
+    );
+    assert_eq!(None, battery::info(0x1d));
+    assert_eq!(0x5a21, date(2025, 1, 1));
+}
+
+#[test]
+fn test_measurements() {
+    let mut pack = pack();
+    let mut bus_state = SMBusState::default();
+
+    assert_eq!(11_800, read_word(&mut pack, &mut bus_state, VOLTAGE));
+    assert_eq!(-1000, read_word(&mut pack, &mut bus_state, CURRENT) as i16);
+    assert_eq!(
+        50,
+        read_word(&mut pack, &mut bus_state, RELATIVE_STATE_OF_CHARGE)
+    );
+    assert_eq!(
+        45,
+        read_word(&mut pack, &mut bus_state, ABSOLUTE_STATE_OF_CHARGE)
+    );
+    assert_eq!(120, read_word(&mut pack, &mut bus_state, RUN_TIME_TO_EMPTY));
+    assert_eq!(
+        NOT_APPLICABLE,
+        read_word(&mut pack, &mut bus_state, AVERAGE_TIME_TO_FULL)
+    );
+    assert_eq!(
+        0x0021,
+        read_word(&mut pack, &mut bus_state, SPECIFICATION_INFO)
+    );
+
+    let mut name = [0; 5];
+    read(&mut pack, &mut bus_state, &[MANUFACTURER_NAME], &mut name).unwrap();
+    assert_eq!(*b"\x04ACME", name);
+}
+
+#[test]
+fn test_capacity_mode_and_at_rate() {
+    let mut pack = pack();
+    let mut bus_state = SMBusState::default();
+
+    write(&mut pack, &mut bus_state, &[BATTERY_MODE, 0x00, 0x80]).unwrap();
+    assert_eq!(
+        mode::CAPACITY_MODE,
+        read_word(&mut pack, &mut bus_state, BATTERY_MODE)
+    );
+    assert_eq!(
+        2160,
+        read_word(&mut pack, &mut bus_state, REMAINING_CAPACITY)
+    );
+    assert_eq!(4752, read_word(&mut pack, &mut bus_state, DESIGN_CAPACITY));
+
+    // 10 W at 10 mW per unit.
+    let rate = (-1000i16).to_le_bytes();
+    write(&mut pack, &mut bus_state, &[AT_RATE, rate[0], rate[1]]).unwrap();
+    assert_eq!(
+        129,
+        read_word(&mut pack, &mut bus_state, AT_RATE_TIME_TO_EMPTY)
+    );
+    assert_eq!(
+        NOT_APPLICABLE,
+        read_word(&mut pack, &mut bus_state, AT_RATE_TIME_TO_FULL)
+    );
+    assert_eq!(1, read_word(&mut pack, &mut bus_state, AT_RATE_OK));
+
+    write(&mut pack, &mut bus_state, &[BATTERY_MODE, 0x81, 0x00]).unwrap();
+    assert_eq!(0, pack.mode());
+}
+
+#[test]
+fn test_battery_status() {
+    let mut pack = pack();
+    let mut bus_state = SMBusState::default();
+
+    assert_eq!(
+        status::INITIALIZED | status::DISCHARGING,
+        read_word(&mut pack, &mut bus_state, BATTERY_STATUS)
+    );
+
+    write(
+        &mut pack,
+        &mut bus_state,
+        &[REMAINING_CAPACITY_ALARM, 0xd1, 0x07],
+    )
+    .unwrap();
+    write(
+        &mut pack,
+        &mut bus_state,
+        &[REMAINING_TIME_ALARM, 0x80, 0x00],
+    )
+    .unwrap();
+    let alarms = status::REMAINING_CAPACITY_ALARM | status::REMAINING_TIME_ALARM;
+    assert_eq!(
+        alarms,
+        read_word(&mut pack, &mut bus_state, BATTERY_STATUS) & alarms
+    );
+
+    let result = write(&mut pack, &mut bus_state, &[VOLTAGE, 0x00, 0x10]);
+    assert_eq!(
+        Err(SMBusProtocolError::InvalidWriteRegister(VOLTAGE)),
+        result
+    );
+    let status = read_word(&mut pack, &mut bus_state, BATTERY_STATUS);
+    assert_eq!(ErrorCode::AccessDenied as u16, status & 0x000f);
+
+    let mut word = [0; 2];
+    let result = read(&mut pack, &mut bus_state, &[0x1d], &mut word);
+    assert_eq!(Err(SMBusProtocolError::InvalidReadRegister(0x1d)), result);
+    let status = read_word(&mut pack, &mut bus_state, BATTERY_STATUS);
+    assert_eq!(ErrorCode::UnsupportedCommand as u16, status & 0x000f);
+    let status = read_word(&mut pack, &mut bus_state, BATTERY_STATUS);
+    assert_eq!(ErrorCode::Ok as u16, status & 0x000f);
+
+    write(
+        &mut pack,
+        &mut bus_state,
+        &[MANUFACTURER_ACCESS, 0x34, 0x12],
+    )
+    .unwrap();
+    assert_eq!(0x1234, pack.battery().manufacturer_access);
+}
+
+#[derive(Default)]
+struct Supply {
+    current: u16,
+    voltage: u16,
+    alarms: u8,
+}
+
+impl SmartCharger for Supply {
+    type Error = ();
+
+    fn max_charging_current(&self) -> u16 {
+        3000
+    }
+
+    fn max_charging_voltage(&self) -> u16 {
+        12_600
+    }
+
+    fn set_charging(&mut self, current: u16, voltage: u16) -> Result<(), ()> {
+        self.current = current;
+        self.voltage = voltage;
+        Ok(())
+    }
+
+    fn status(&mut self) -> u16 {
+        charger::status::AC_PRESENT | charger::status::BATTERY_PRESENT | charger::status::LEVEL_2
+    }
+
+    fn on_alarm(&mut self, _alarms: u16) {
+        self.alarms += 1;
+    }
+}
+
+#[test]
+fn test_charger() {
+    let mut supply = Charger::new(Supply::default());
+    let mut bus_state = SMBusState::default();
+    let mut word = [0; 2];
+
+    write(
+        &mut supply,
+        &mut bus_state,
+        &[charger::CHARGING_CURRENT, 0xb8, 0x0b],
+    )
+    .unwrap();
+    write(
+        &mut supply,
+        &mut bus_state,
+        &[charger::CHARGING_VOLTAGE, 0x38, 0x31],
+    )
+    .unwrap();
+    assert_eq!(3000, supply.charger().current);
+    assert_eq!(12_600, supply.charger().voltage);
+
+    write(
+        &mut supply,
+        &mut bus_state,
+        &[charger::CHARGING_CURRENT, 0xa0, 0x0f],
+    )
+    .unwrap();
+    assert_eq!(3000, supply.charger().current);
+    assert_eq!((4000, 12_600), supply.requested());
+    read(
+        &mut supply,
+        &mut bus_state,
+        &[charger::CHARGER_STATUS],
+        &mut word,
+    )
+    .unwrap();
+    let status = u16::from_le_bytes(word);
+    assert_eq!(
+        charger::status::CURRENT_OR,
+        status & charger::status::PROTOCOL
+    );
+    assert_ne!(0, status & charger::status::AC_PRESENT);
+
+    let result = read(
+        &mut supply,
+        &mut bus_state,
+        &[charger::CHARGING_CURRENT],
+        &mut word,
+    );
+    assert_eq!(
+        Err(SMBusProtocolError::WriteOnlyRegister(
+            charger::CHARGING_CURRENT
+        )),
+        result
+    );
+
+    write(
+        &mut supply,
+        &mut bus_state,
+        &[charger::CHARGER_MODE, 0x01, 0x00],
+    )
+    .unwrap();
+    assert_eq!(0, supply.charger().current);
+    write(
+        &mut supply,
+        &mut bus_state,
+        &[charger::CHARGER_MODE, 0x00, 0x00],
+    )
+    .unwrap();
+    assert_eq!(3000, supply.charger().current);
+}
+
+#[test]
+fn test_charger_alarm_warning() {
+    let mut supply = Charger::new(Supply::default());
+    let mut bus_state = SMBusState::default();
+    let mut word = [0; 2];
+
+    write(
+        &mut supply,
+        &mut bus_state,
+        &[charger::CHARGING_CURRENT, 0xe8, 0x03],
+    )
+    .unwrap();
+    write(
+        &mut supply,
+        &mut bus_state,
+        &[charger::CHARGING_VOLTAGE, 0x38, 0x31],
+    )
+    .unwrap();
+
+    let alarm = (status::OVER_TEMP_ALARM | status::INITIALIZED).to_le_bytes();
+    write(
+        &mut supply,
+        &mut bus_state,
+        &[charger::ALARM_WARNING, alarm[0], alarm[1]],
+    )
+    .unwrap();
+    assert_eq!(1, supply.charger().alarms);
+    assert_eq!((0, 0), (supply.charger().current, supply.charger().voltage));
+    read(
+        &mut supply,
+        &mut bus_state,
+        &[charger::CHARGER_STATUS],
+        &mut word,
+    )
+    .unwrap();
+    assert_ne!(
+        0,
+        u16::from_le_bytes(word) & charger::status::ALARM_INHIBITED
+    );
+
+    write(
+        &mut supply,
+        &mut bus_state,
+        &[charger::CHARGING_CURRENT, 0xe8, 0x03],
+    )
+    .unwrap();
+    assert_eq!(0, supply.charger().current);
+    write(
+        &mut supply,
+        &mut bus_state,
+        &[charger::CHARGING_VOLTAGE, 0x38, 0x31],
+    )
+    .unwrap();
+    assert_eq!(1000, supply.charger().current);
+
+    write(
+        &mut supply,
+        &mut bus_state,
+        &[charger::ALARM_WARNING, alarm[0], alarm[1]],
+    )
+    .unwrap();
+    write(
+        &mut supply,
+        &mut bus_state,
+        &[charger::CHARGER_MODE, 0x04, 0x00],
+    )
+    .unwrap();
+    assert_eq!((0, 0), supply.requested());
+    read(
+        &mut supply,
+        &mut bus_state,
+        &[charger::CHARGER_STATUS],
+        &mut word,
+    )
+    .unwrap();
+    assert_eq!(0, u16::from_le_bytes(word) & charger::status::PROTOCOL);
+}
+
+#[derive(Default)]
+struct Bay {
+    connected: SelectorState,
+}
+
+impl SmartSelector for Bay {
+    type Error = ();
+
+    fn supported(&self) -> u8 {
+        0b0011
+    }
+
+    fn present(&mut self) -> u8 {
+        0b0001
+    }
+
+    fn switch(&mut self, state: SelectorState) -> Result<(), ()> {
+        self.connected = state;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_selector() {
+    let mut bay = Selector::new(Bay::default());
+    let mut bus_state = SMBusState::default();
+    let mut word = [0; 2];
+
+    read(&mut bay, &mut bus_state, &[SELECTOR_INFO], &mut word).unwrap();
+    assert_eq!(0x0023, u16::from_le_bytes(word));
+
+    write(&mut bay, &mut bus_state, &[SELECTOR_STATE, 0x11, 0x01]).unwrap();
+    let selected = SelectorState {
+        smb: 1,
+        power_by: 1,
+        charge: 1,
+        present: 0,
+    };
+    assert_eq!(selected, bay.selector().connected);
+    read(&mut bay, &mut bus_state, &[SELECTOR_STATE], &mut word).unwrap();
+    assert_eq!(0x1111, u16::from_le_bytes(word));
+
+    let result = write(&mut bay, &mut bus_state, &[SELECTOR_STATE, 0x12, 0x00]);
+    assert_eq!(
+        Err(SMBusProtocolError::InvalidWriteRegister(SELECTOR_STATE)),
+        result
+    );
+    assert_eq!(selected, bay.state());
+
+    write(&mut bay, &mut bus_state, &[SELECTOR_PRESETS, 0x13, 0x00]).unwrap();
+    assert_eq!(
+        SelectorPresets {
+            ok_to_use: 0b0011,
+            use_next: 1
+        },
+        bay.presets()
+    );
+    let result = write(&mut bay, &mut bus_state, &[SELECTOR_INFO, 0x00, 0x00]);
+    assert_eq!(
+        Err(SMBusProtocolError::ReadOnlyRegister(SELECTOR_INFO)),
+        result
+    );
+}
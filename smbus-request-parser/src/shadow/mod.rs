@@ -0,0 +1,238 @@
+use crate::{register_map::RegisterPolicy, AddressingMode, ByteOrder, CommandHandler};
+
+#[cfg(test)]
+mod test;
+
+/// When the writes collected by [`Shadowed`] take effect.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Commit {
+    /// At the STOP of every transaction which completed without an error.
+    OnStop,
+    /// When the command code is sent, as send byte or with a byte of data.
+    /// Writes of earlier transactions stay pending until then, also when a
+    /// later transaction fails.
+    OnRegister(u8),
+}
+
+/// Adapter applying writes to a shadow copy of `H` first, so that changes
+/// spanning several registers take effect atomically. A failed transaction
+/// discards its own writes, those of earlier transactions stay pending. Reads
+/// return the data of the active copy; their side effects and every other
+/// hook reach both copies, so that a commit does not undo them.
+#[derive(Debug, Clone)]
+pub struct Shadowed<H> {
+    active: H,
+    shadow: Option<H>,
+    /// Shadow as it was at the start of the current transaction.
+    checkpoint: Option<H>,
+    commit: Commit,
+}
+
+impl<H: CommandHandler + Clone> Shadowed<H> {
+    pub fn new(handler: H, commit: Commit) -> Self {
+        Self {
+            active: handler,
+            shadow: None,
+            checkpoint: None,
+            commit,
+        }
+    }
+
+    pub fn active(&self) -> &H {
+        &self.active
+    }
+
+    /// Changes made here while writes are pending are lost when they are
+    /// committed, use [`Shadowed::update`] for state the firmware maintains.
+    pub fn active_mut(&mut self) -> &mut H {
+        &mut self.active
+    }
+
+    /// Apply `f` to the active copy and to the pending writes, e.g. to update
+    /// telemetry or status registers.
+    pub fn update(&mut self, mut f: impl FnMut(&mut H)) {
+        f(&mut self.active);
+        if let Some(shadow) = self.shadow.as_mut() {
+            f(shadow);
+        }
+        if let Some(checkpoint) = self.checkpoint.as_mut() {
+            f(checkpoint);
+        }
+    }
+
+    /// Shadow copy holding the writes not committed yet.
+    pub fn pending(&self) -> Option<&H> {
+        self.shadow.as_ref()
+    }
+
+    pub fn into_inner(self) -> H {
+        self.active
+    }
+
+    fn shadow(&mut self) -> &mut H {
+        self.shadow.get_or_insert_with(|| self.active.clone())
+    }
+
+    fn commit(&mut self) {
+        self.checkpoint = None;
+        if let Some(shadow) = self.shadow.take() {
+            self.active = shadow;
+        }
+    }
+
+    fn is_commit(&self, reg: u8) -> bool {
+        self.commit == Commit::OnRegister(reg)
+    }
+}
+
+impl<H: CommandHandler + Clone> CommandHandler for Shadowed<H> {
+    type Error = H::Error;
+
+    fn handle_read_byte(&mut self) -> Option<u8> {
+        self.active.handle_read_byte()
+    }
+
+    fn handle_read_byte_data(&mut self, reg: u8) -> Option<u8> {
+        self.active.handle_read_byte_data(reg)
+    }
+
+    fn handle_read_word_data(&mut self, reg: u8) -> Option<u16> {
+        self.active.handle_read_word_data(reg)
+    }
+
+    fn handle_read_block_data(&mut self, reg: u8, index: u8) -> Option<u8> {
+        self.active.handle_read_block_data(reg, index)
+    }
+
+    fn handle_read_complete(&mut self, reg: Option<u8>, count: u8) {
+        self.update(|handler| handler.handle_read_complete(reg, count));
+    }
+
+    fn handle_block_process_call(&mut self, reg: u8, request: &[u8], index: u8) -> Option<u8> {
+        self.active.handle_block_process_call(reg, request, index)
+    }
+
+    fn handle_write_byte(&mut self, data: u8) -> Result<(), Self::Error> {
+        if self.is_commit(data) {
+            self.commit();
+            return Ok(());
+        }
+        self.shadow().handle_write_byte(data)
+    }
+
+    fn handle_write_byte_data(&mut self, reg: u8, data: u8) -> Result<(), Self::Error> {
+        if self.is_commit(reg) {
+            self.commit();
+            return Ok(());
+        }
+        self.shadow().handle_write_byte_data(reg, data)
+    }
+
+    fn handle_write_word_data(&mut self, reg: u8, data: u16) -> Result<(), Self::Error> {
+        self.shadow().handle_write_word_data(reg, data)
+    }
+
+    fn handle_write_block_data(
+        &mut self,
+        reg: u8,
+        count: u8,
+        block: &[u8],
+    ) -> Result<(), Self::Error> {
+        self.shadow().handle_write_block_data(reg, count, block)
+    }
+
+    fn is_streamed_block_write(&self, reg: u8) -> bool {
+        self.shadow
+            .as_ref()
+            .unwrap_or(&self.active)
+            .is_streamed_block_write(reg)
+    }
+
+    fn handle_write_block_start(&mut self, reg: u8, count: u8) -> Result<(), Self::Error> {
+        self.shadow().handle_write_block_start(reg, count)
+    }
+
+    fn handle_write_block_chunk(
+        &mut self,
+        reg: u8,
+        offset: u8,
+        chunk: &[u8],
+    ) -> Result<(), Self::Error> {
+        self.shadow().handle_write_block_chunk(reg, offset, chunk)
+    }
+
+    fn handle_write_block_commit(&mut self, reg: u8) -> Result<(), Self::Error> {
+        self.shadow().handle_write_block_commit(reg)
+    }
+
+    fn handle_write_block_abort(&mut self, reg: u8) {
+        if let Some(shadow) = self.shadow.as_mut() {
+            shadow.handle_write_block_abort(reg);
+        }
+    }
+
+    fn on_start(&mut self) {
+        self.checkpoint = self.shadow.clone();
+        self.update(H::on_start);
+    }
+
+    fn register_policy(&self, reg: u8) -> Option<RegisterPolicy> {
+        self.shadow
+            .as_ref()
+            .unwrap_or(&self.active)
+            .register_policy(reg)
+    }
+
+    fn addressing_mode(&self) -> AddressingMode {
+        self.active.addressing_mode()
+    }
+
+    fn handle_read_memory(&mut self, address: u16) -> Option<u8> {
+        self.active.handle_read_memory(address)
+    }
+
+    fn handle_write_memory(&mut self, address: u16, data: u8) -> Result<(), Self::Error> {
+        self.shadow().handle_write_memory(address, data)
+    }
+
+    fn handle_raw_write(&mut self, index: u8, data: u8) -> Result<(), Self::Error> {
+        self.shadow().handle_raw_write(index, data)
+    }
+
+    fn handle_raw_read(&mut self, index: u8) -> Option<u8> {
+        self.active.handle_raw_read(index)
+    }
+
+    fn byte_order(&self, reg: u8) -> ByteOrder {
+        self.active.byte_order(reg)
+    }
+
+    fn pec_address(&self) -> Option<u8> {
+        self.active.pec_address()
+    }
+
+    fn pec_required(&self) -> bool {
+        self.active.pec_required()
+    }
+
+    fn on_read_begin(&mut self, reg: u8) {
+        self.update(|handler| handler.on_read_begin(reg));
+    }
+
+    fn on_stop(&mut self) {
+        self.checkpoint = None;
+        if self.commit == Commit::OnStop {
+            self.commit();
+        }
+        self.update(H::on_stop);
+    }
+
+    fn on_abort(&mut self) {
+        self.shadow = self.checkpoint.take();
+        self.update(H::on_abort);
+    }
+
+    fn on_pec_error(&mut self) {
+        self.update(H::on_pec_error);
+    }
+}
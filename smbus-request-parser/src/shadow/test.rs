@@ -0,0 +1,142 @@
+use crate::register_map::*;
+use crate::shadow::*;
+use crate::test::{read, write};
+use crate::*;
+
+static REGISTERS: [RegisterDescriptor; 3] = [
+    RegisterDescriptor {
+        code: 0x21,
+        width: Width::Word,
+        access: Access::ReadWrite,
+        reset: &[0x00, 0x10],
+        reserved: 0,
+        offset: 0,
+    },
+    RegisterDescriptor {
+        code: 0x40,
+        width: Width::Word,
+        access: Access::ReadWrite,
+        reset: &[0x00, 0x11],
+        reserved: 0xf000,
+        offset: 2,
+    },
+    RegisterDescriptor {
+        code: 0x7a,
+        width: Width::Byte,
+        access: Access::ClearOnRead,
+        reset: &[0x81],
+        reserved: 0,
+        offset: 4,
+    },
+];
+
+const APPLY: u8 = 0xd0;
+
+#[test]
+fn test_commit_register() {
+    let map = RegisterMap::<5>::new(&REGISTERS).unwrap();
+    let mut device = Shadowed::new(map, Commit::OnRegister(APPLY));
+    let mut bus_state = SMBusState::default();
+
+    write(&mut device, &mut bus_state, &[0x21, 0x00, 0x0c]).unwrap();
+    write(&mut device, &mut bus_state, &[0x40, 0x00, 0x0d]).unwrap();
+    assert_eq!(Some(0x1000), device.active().word(0x21));
+    assert_eq!(Some(0x0d00), device.pending().unwrap().word(0x40));
+
+    let mut word = [0; 2];
+    read(&mut device, &mut bus_state, &[0x21], &mut word).unwrap();
+    assert_eq!([0x00, 0x10], word);
+
+    write(&mut device, &mut bus_state, &[APPLY]).unwrap();
+    assert!(device.pending().is_none());
+    assert_eq!(Some(0x0c00), device.active().word(0x21));
+    assert_eq!(Some(0x0d00), device.active().word(0x40));
+}
+
+#[test]
+fn test_failed_transaction_rolls_back() {
+    let map = RegisterMap::<5>::new(&REGISTERS).unwrap();
+    let mut device = Shadowed::new(map, Commit::OnRegister(APPLY));
+    let mut bus_state = SMBusState::default();
+
+    write(&mut device, &mut bus_state, &[0x21, 0x00, 0x0c]).unwrap();
+    write(&mut device, &mut bus_state, &[0x40, 0x00, 0x0d]).unwrap();
+    let result = write(&mut device, &mut bus_state, &[0x40, 0x00, 0xf0]);
+    assert_eq!(Err(SMBusProtocolError::ReservedBitsSet(0x40)), result);
+    assert_eq!(Some(0x0d00), device.pending().unwrap().word(0x40));
+
+    write(&mut device, &mut bus_state, &[APPLY, 0x01]).unwrap();
+    assert_eq!(Some(0x0c00), device.active().word(0x21));
+    assert_eq!(Some(0x0d00), device.active().word(0x40));
+}
+
+#[test]
+fn test_commit_keeps_read_side_effects_and_updates() {
+    let map = RegisterMap::<5>::new(&REGISTERS).unwrap();
+    let mut device = Shadowed::new(map, Commit::OnRegister(APPLY));
+    let mut bus_state = SMBusState::default();
+
+    write(&mut device, &mut bus_state, &[0x21, 0x00, 0x0c]).unwrap();
+    let mut byte = [0];
+    read(&mut device, &mut bus_state, &[0x7a], &mut byte).unwrap();
+    assert_eq!([0x81], byte);
+    write(&mut device, &mut bus_state, &[APPLY]).unwrap();
+    assert_eq!(Some(0x0c00), device.active().word(0x21));
+    assert_eq!(Some(0), device.active().byte(0x7a));
+
+    write(&mut device, &mut bus_state, &[0x21, 0x00, 0x0b]).unwrap();
+    device.update(|map| map.set_byte(0x7a, 0x42).unwrap());
+    write(&mut device, &mut bus_state, &[APPLY]).unwrap();
+    assert_eq!(Some(0x0b00), device.active().word(0x21));
+    assert_eq!(Some(0x42), device.active().byte(0x7a));
+
+    device.active_mut().set_byte(0x7a, 0x24).unwrap();
+    read(&mut device, &mut bus_state, &[0x7a], &mut byte).unwrap();
+    assert_eq!([0x24], byte);
+}
+
+/// Sequentially addressed bank whose last register rejects every write.
+#[derive(Debug, Clone, Default)]
+struct Bank {
+    values: [u8; 4],
+}
+
+impl CommandHandler for Bank {
+    type Error = ();
+
+    fn addressing_mode(&self) -> AddressingMode {
+        AddressingMode::Sequential {
+            last: 3,
+            wrap_to: None,
+        }
+    }
+
+    fn handle_read_byte_data(&mut self, reg: u8) -> Option<u8> {
+        self.values.get(reg as usize).copied()
+    }
+
+    fn handle_write_byte_data(&mut self, reg: u8, data: u8) -> Result<(), ()> {
+        match reg {
+            0..=2 => {
+                self.values[reg as usize] = data;
+                Ok(())
+            }
+            _ => Err(()),
+        }
+    }
+}
+
+#[test]
+fn test_commit_on_stop() {
+    let mut device = Shadowed::new(Bank::default(), Commit::OnStop);
+    let mut bus_state = SMBusState::default();
+
+    write(&mut device, &mut bus_state, &[0x00, 0x11, 0x22]).unwrap();
+    assert!(device.pending().is_none());
+    assert_eq!([0x11, 0x22, 0, 0], device.active().values);
+
+    let result = write(&mut device, &mut bus_state, &[0x02, 0x33, 0x44]);
+    assert_eq!(Err(SMBusProtocolError::InvalidWriteRegister(0x03)), result);
+    assert!(device.pending().is_none());
+    assert_eq!([0x11, 0x22, 0, 0], device.active().values);
+}
@@ -1,5 +1,53 @@
 use crate::*;
 
+/// Run a complete master write of `bytes`, returning the first protocol error.
+pub(crate) fn write<H: CommandHandler>(
+    handler: &mut H,
+    bus_state: &mut SMBusState,
+    bytes: &[u8],
+) -> Result<(), SMBusProtocolError> {
+    let mut event = I2cEvent::Initiated {
+        direction: Direction::MasterToSlave,
+    };
+    let mut result = handler.handle_i2c_event(&mut event, bus_state);
+    for byte in bytes {
+        event = I2cEvent::ReceivedByte { byte: *byte };
+        result = result.and(handler.handle_i2c_event(&mut event, bus_state));
+    }
+    event = I2cEvent::Stopped;
+    result.and(handler.handle_i2c_event(&mut event, bus_state))
+}
+
+/// Write `command`, then read `buffer.len()` bytes after a repeated start.
+pub(crate) fn read<H: CommandHandler>(
+    handler: &mut H,
+    bus_state: &mut SMBusState,
+    command: &[u8],
+    buffer: &mut [u8],
+) -> Result<(), SMBusProtocolError> {
+    let mut result = Ok(());
+    if !command.is_empty() {
+        let mut event = I2cEvent::Initiated {
+            direction: Direction::MasterToSlave,
+        };
+        result = handler.handle_i2c_event(&mut event, bus_state);
+        for byte in command {
+            event = I2cEvent::ReceivedByte { byte: *byte };
+            result = result.and(handler.handle_i2c_event(&mut event, bus_state));
+        }
+    }
+    let mut event = I2cEvent::Initiated {
+        direction: Direction::SlaveToMaster,
+    };
+    result = result.and(handler.handle_i2c_event(&mut event, bus_state));
+    for byte in buffer.iter_mut() {
+        event = I2cEvent::RequestedByte { byte };
+        result = result.and(handler.handle_i2c_event(&mut event, bus_state));
+    }
+    event = I2cEvent::Stopped;
+    result.and(handler.handle_i2c_event(&mut event, bus_state))
+}
+
 struct Thing {
     byte_a: u8,
     byte_b: u8,
@@ -9,11 +57,11 @@ struct Thing {
 impl CommandHandler for Thing {
     type Error = ();
 
-    fn handle_read_byte(&self) -> Option<u8> {
+    fn handle_read_byte(&mut self) -> Option<u8> {
         Some(self.byte_a)
     }
 
-    fn handle_read_byte_data(&self, reg: u8) -> Option<u8> {
+    fn handle_read_byte_data(&mut self, reg: u8) -> Option<u8> {
         match reg {
             1 => Some(self.byte_a),
             2 => Some(self.byte_b),
@@ -22,7 +70,7 @@ impl CommandHandler for Thing {
         }
     }
 
-    fn handle_read_word_data(&self, reg: u8) -> Option<u16> {
+    fn handle_read_word_data(&mut self, reg: u8) -> Option<u16> {
         match reg {
             7 => {
                 let data = self.byte_a as u16 | (self.byte_b as u16) << 8;
@@ -36,7 +84,7 @@ impl CommandHandler for Thing {
         }
     }
 
-    fn handle_read_block_data(&self, reg: u8, index: u8) -> Option<u8> {
+    fn handle_read_block_data(&mut self, reg: u8, index: u8) -> Option<u8> {
         match reg {
             11 => match index {
                 0 => Some(6),
@@ -116,7 +164,7 @@ impl CommandHandler for Thing {
                 }
                 let sum = block.iter().take(10).sum();
                 self.byte_a = sum;
-                return Ok(());
+                Ok(())
             }
             15 => {
                 if count != 32 {
@@ -127,8 +175,339 @@ impl CommandHandler for Thing {
                 }
                 Ok(())
             }
-            _ => return Err(()),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Uploader {
+    started: Option<(u8, u8)>,
+    sum: u32,
+    received: usize,
+    committed: Option<u32>,
+    aborted: bool,
+    reject_commit: bool,
+}
+
+impl CommandHandler for Uploader {
+    type Error = ();
+
+    fn is_streamed_block_write(&self, reg: u8) -> bool {
+        reg == 0x20
+    }
+
+    fn handle_write_block_start(&mut self, reg: u8, count: u8) -> Result<(), ()> {
+        self.started = Some((reg, count));
+        self.sum = 0;
+        self.received = 0;
+        Ok(())
+    }
+
+    fn handle_write_block_chunk(&mut self, _reg: u8, offset: u8, chunk: &[u8]) -> Result<(), ()> {
+        assert_eq!(self.received, offset as usize);
+        self.sum += chunk.iter().map(|b| *b as u32).sum::<u32>();
+        self.received += chunk.len();
+        Ok(())
+    }
+
+    fn handle_write_block_commit(&mut self, _reg: u8) -> Result<(), ()> {
+        if self.reject_commit {
+            return Err(());
+        }
+        self.committed = Some(self.sum);
+        Ok(())
+    }
+
+    fn handle_write_block_abort(&mut self, _reg: u8) {
+        self.aborted = true;
+    }
+}
+
+#[derive(Default)]
+struct Checksummed {
+    block: Option<([u8; 32], u8)>,
+    command: Option<u8>,
+    byte: Option<u8>,
+    word: Option<u16>,
+}
+
+impl CommandHandler for Checksummed {
+    type Error = ();
+
+    fn handle_write_byte(&mut self, data: u8) -> Result<(), ()> {
+        self.command = Some(data);
+        Ok(())
+    }
+
+    fn handle_write_byte_data(&mut self, _reg: u8, data: u8) -> Result<(), ()> {
+        self.byte = Some(data);
+        Ok(())
+    }
+
+    fn handle_write_word_data(&mut self, _reg: u8, data: u16) -> Result<(), ()> {
+        self.word = Some(data);
+        Ok(())
+    }
+
+    fn handle_write_block_data(&mut self, _reg: u8, count: u8, block: &[u8]) -> Result<(), ()> {
+        let mut data = [0; 32];
+        data[..block.len()].copy_from_slice(block);
+        self.block = Some((data, count));
+        Ok(())
+    }
+
+    fn pec_address(&self) -> Option<u8> {
+        Some(0x2a)
+    }
+
+    fn register_policy(&self, reg: u8) -> Option<RegisterPolicy> {
+        let widths = match reg {
+            0x01 => AccessWidths::NONE,
+            0x02 => AccessWidths::BYTE,
+            0x03 => AccessWidths::WORD,
+            _ => return None,
+        };
+        Some(RegisterPolicy {
+            widths,
+            ..RegisterPolicy::new(Access::ReadWrite)
+        })
+    }
+}
+
+#[derive(Default)]
+struct Fifo {
+    status: u8,
+    entries: [u8; 4],
+    head: usize,
+    reads: usize,
+}
+
+impl CommandHandler for Fifo {
+    type Error = ();
+
+    fn handle_read_byte(&mut self) -> Option<u8> {
+        Some(self.entries[self.head])
+    }
+
+    fn handle_read_byte_data(&mut self, reg: u8) -> Option<u8> {
+        match reg {
+            0x01 => Some(self.status),
+            _ => None,
+        }
+    }
+
+    fn handle_read_complete(&mut self, reg: Option<u8>, count: u8) {
+        self.reads += 1;
+        match (reg, count) {
+            (None, 1) => self.head = (self.head + 1) % self.entries.len(),
+            (Some(0x01), 1) => self.status = 0,
+            _ => {}
+        }
+    }
+}
+
+#[derive(Default)]
+struct Sensor {
+    measurement: u32,
+    snapshot: Option<u32>,
+    consumed: Option<u8>,
+    starts: usize,
+    stops: usize,
+    aborts: usize,
+}
+
+impl CommandHandler for Sensor {
+    type Error = ();
+
+    fn handle_read_block_data(&mut self, reg: u8, index: u8) -> Option<u8> {
+        let snapshot = self.snapshot?;
+        match (reg, index) {
+            (0x10, 0) => Some(4),
+            (0x10, 1..=4) => Some(snapshot.to_le_bytes()[index as usize - 1]),
+            _ => None,
+        }
+    }
+
+    /// Scales the snapshot by the requested factor.
+    fn handle_block_process_call(&mut self, reg: u8, request: &[u8], index: u8) -> Option<u8> {
+        let scaled = self.snapshot? * *request.first()? as u32;
+        match (reg, index) {
+            (0x11, 0) => Some(4),
+            (0x11, 1..=4) => Some(scaled.to_le_bytes()[index as usize - 1]),
+            _ => None,
+        }
+    }
+
+    fn on_start(&mut self) {
+        self.starts += 1;
+    }
+
+    fn on_read_begin(&mut self, _reg: u8) {
+        self.snapshot = Some(self.measurement);
+    }
+
+    fn handle_read_complete(&mut self, _reg: Option<u8>, count: u8) {
+        self.consumed = Some(count);
+    }
+
+    fn on_stop(&mut self) {
+        self.snapshot = None;
+        self.stops += 1;
+    }
+
+    fn on_abort(&mut self) {
+        self.snapshot = None;
+        self.aborts += 1;
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum LedError {
+    Unsupported,
+    InvalidRegister,
+}
+
+impl From<Unsupported> for LedError {
+    fn from(_: Unsupported) -> Self {
+        LedError::Unsupported
+    }
+}
+
+#[derive(Default)]
+struct Led {
+    brightness: u8,
+}
+
+impl CommandHandler for Led {
+    type Error = LedError;
+
+    fn handle_read_byte_data(&mut self, reg: u8) -> Option<u8> {
+        (reg == 0x01).then_some(self.brightness)
+    }
+
+    fn handle_write_byte_data(&mut self, reg: u8, data: u8) -> Result<(), LedError> {
+        if reg != 0x01 {
+            return Err(LedError::InvalidRegister);
+        }
+        self.brightness = data;
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct BigEndianCounter {
+    value: u16,
+}
+
+impl CommandHandler for BigEndianCounter {
+    type Error = ();
+
+    fn handle_read_word_data(&mut self, reg: u8) -> Option<u16> {
+        (reg == 0x02).then_some(self.value)
+    }
+
+    fn handle_write_word_data(&mut self, reg: u8, data: u16) -> Result<(), ()> {
+        if reg != 0x02 {
+            return Err(());
         }
+        self.value = data;
+        Ok(())
+    }
+
+    fn byte_order(&self, _reg: u8) -> ByteOrder {
+        ByteOrder::BigEndian
+    }
+}
+
+struct Eeprom {
+    memory: [u8; 0x200],
+    latched: Option<u8>,
+    consumed: Option<u8>,
+}
+
+impl Eeprom {
+    fn new() -> Self {
+        Eeprom {
+            memory: [0; 0x200],
+            latched: None,
+            consumed: None,
+        }
+    }
+}
+
+impl CommandHandler for Eeprom {
+    type Error = ();
+
+    fn addressing_mode(&self) -> AddressingMode {
+        AddressingMode::Wide(ByteOrder::BigEndian)
+    }
+
+    fn handle_read_memory(&mut self, address: u16) -> Option<u8> {
+        self.memory.get(address as usize).copied()
+    }
+
+    fn handle_write_memory(&mut self, address: u16, data: u8) -> Result<(), ()> {
+        *self.memory.get_mut(address as usize).ok_or(())? = data;
+        Ok(())
+    }
+
+    fn on_read_begin(&mut self, reg: u8) {
+        self.latched = Some(reg);
+    }
+
+    fn handle_read_complete(&mut self, reg: Option<u8>, count: u8) {
+        assert_eq!(None, reg);
+        self.consumed = Some(count);
+    }
+}
+
+/// Word register whose policy allows every transfer width.
+struct Permissive {
+    value: u16,
+}
+
+impl CommandHandler for Permissive {
+    type Error = ();
+
+    fn handle_read_word_data(&mut self, reg: u8) -> Option<u16> {
+        (reg == 0x05).then_some(self.value)
+    }
+
+    fn register_policy(&self, reg: u8) -> Option<RegisterPolicy> {
+        (reg == 0x05).then_some(RegisterPolicy::new(Access::ReadOnly))
+    }
+}
+
+struct RegisterFile {
+    values: [u8; 8],
+    wrap_to: Option<u8>,
+}
+
+impl CommandHandler for RegisterFile {
+    type Error = ();
+
+    fn addressing_mode(&self) -> AddressingMode {
+        AddressingMode::Sequential {
+            last: 0x07,
+            wrap_to: self.wrap_to,
+        }
+    }
+
+    fn handle_read_byte_data(&mut self, reg: u8) -> Option<u8> {
+        self.values.get(reg as usize).copied()
+    }
+
+    /// Register 0x07 is a status register cleared by reading it.
+    fn handle_read_complete(&mut self, reg: Option<u8>, _count: u8) {
+        if reg == Some(0x07) {
+            self.values[7] = 0;
+        }
+    }
+
+    fn handle_write_byte_data(&mut self, reg: u8, data: u8) -> Result<(), ()> {
+        *self.values.get_mut(reg as usize).ok_or(())? = data;
+        Ok(())
     }
 }
 
@@ -515,3 +894,628 @@ fn test_write_block_data_35() {
     let error = thing.handle_i2c_event(&mut event, &mut bus_state);
     assert_eq!(Err(SMBusProtocolError::InvalidWriteBound(32)), error);
 }
+
+#[test]
+fn test_streamed_write_block_200() {
+    let mut loader = Uploader::default();
+    let mut bus_state = SMBusState::default();
+
+    let mut event = I2cEvent::Initiated {
+        direction: Direction::MasterToSlave,
+    };
+    loader.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    event = I2cEvent::ReceivedByte { byte: 0x20 };
+    loader.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    event = I2cEvent::ReceivedByte { byte: 200 };
+    loader.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    for v in 0..200 {
+        event = I2cEvent::ReceivedByte { byte: v };
+        loader.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+    }
+    assert_eq!(None, loader.committed);
+
+    event = I2cEvent::Stopped;
+    loader.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    assert_eq!(Some((0x20, 200)), loader.started);
+    assert_eq!(200, loader.received);
+    assert_eq!(Some((0..200).sum()), loader.committed);
+    assert!(!loader.aborted);
+}
+
+#[test]
+fn test_streamed_write_block_aborted_on_short_transfer() {
+    let mut loader = Uploader::default();
+    let mut bus_state = SMBusState::default();
+
+    let mut event = I2cEvent::Initiated {
+        direction: Direction::MasterToSlave,
+    };
+    loader.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    event = I2cEvent::ReceivedByte { byte: 0x20 };
+    loader.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    event = I2cEvent::ReceivedByte { byte: 64 };
+    loader.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    for v in 0..10 {
+        event = I2cEvent::ReceivedByte { byte: v };
+        loader.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+    }
+
+    event = I2cEvent::Stopped;
+    let error = loader.handle_i2c_event(&mut event, &mut bus_state);
+    assert_eq!(Err(SMBusProtocolError::IncompleteWriteBlock(10)), error);
+    assert!(loader.aborted);
+    assert_eq!(None, loader.committed);
+}
+
+#[test]
+fn test_streamed_write_block_aborted_on_overrun() {
+    let mut loader = Uploader::default();
+    let mut bus_state = SMBusState::default();
+
+    let mut event = I2cEvent::Initiated {
+        direction: Direction::MasterToSlave,
+    };
+    loader.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    event = I2cEvent::ReceivedByte { byte: 0x20 };
+    loader.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    event = I2cEvent::ReceivedByte { byte: 2 };
+    loader.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    for v in 0..2 {
+        event = I2cEvent::ReceivedByte { byte: v };
+        loader.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+    }
+    event = I2cEvent::ReceivedByte { byte: 0xde };
+    let error = loader.handle_i2c_event(&mut event, &mut bus_state);
+    assert_eq!(Err(SMBusProtocolError::InvalidWriteBound(2)), error);
+    assert!(loader.aborted);
+    assert_eq!(None, loader.committed);
+}
+
+#[test]
+fn test_streamed_write_block_empty() {
+    let mut loader = Uploader::default();
+    let mut bus_state = SMBusState::default();
+
+    write(&mut loader, &mut bus_state, &[0x20, 0]).unwrap();
+    assert_eq!(Some((0x20, 0)), loader.started);
+    assert_eq!(Some(0), loader.committed);
+}
+
+#[test]
+fn test_streamed_write_block_commit_rejected() {
+    let mut loader = Uploader {
+        reject_commit: true,
+        ..Default::default()
+    };
+    let mut bus_state = SMBusState::default();
+
+    let result = write(&mut loader, &mut bus_state, &[0x20, 2, 1, 2]);
+    assert_eq!(Err(SMBusProtocolError::InvalidWriteRegister(0x20)), result);
+    assert_eq!(None, loader.committed);
+}
+
+#[test]
+fn test_read_byte_pops_fifo_on_completion() {
+    let mut fifo = Fifo {
+        entries: [0xa, 0xb, 0xc, 0xd],
+        ..Default::default()
+    };
+    let mut bus_state = SMBusState::default();
+
+    for expected in [0xa, 0xb, 0xc] {
+        let mut event = I2cEvent::Initiated {
+            direction: Direction::SlaveToMaster,
+        };
+        fifo.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+        let mut data = 0;
+        event = I2cEvent::RequestedByte { byte: &mut data };
+        fifo.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+        event = I2cEvent::Stopped;
+        fifo.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+        assert_eq!(expected, data);
+    }
+    assert_eq!(3, fifo.head);
+}
+
+#[test]
+fn test_read_byte_data_clears_status_on_completion() {
+    let mut fifo = Fifo {
+        status: 0x81,
+        ..Default::default()
+    };
+    let mut bus_state = SMBusState::default();
+
+    let mut event = I2cEvent::Initiated {
+        direction: Direction::MasterToSlave,
+    };
+    fifo.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    event = I2cEvent::ReceivedByte { byte: 0x01 };
+    fifo.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    event = I2cEvent::Initiated {
+        direction: Direction::SlaveToMaster,
+    };
+    fifo.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    let mut data = 0;
+    event = I2cEvent::RequestedByte { byte: &mut data };
+    fifo.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+    assert_eq!(0x81, fifo.status);
+
+    event = I2cEvent::Stopped;
+    fifo.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    assert_eq!(0x81, data);
+    assert_eq!(0, fifo.status);
+    assert_eq!(1, fifo.reads);
+}
+
+#[test]
+fn test_read_aborted_before_data_is_not_consumed() {
+    let mut fifo = Fifo {
+        status: 0x81,
+        ..Default::default()
+    };
+    let mut bus_state = SMBusState::default();
+
+    let mut event = I2cEvent::Initiated {
+        direction: Direction::MasterToSlave,
+    };
+    fifo.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    event = I2cEvent::ReceivedByte { byte: 0x01 };
+    fifo.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    event = I2cEvent::Initiated {
+        direction: Direction::SlaveToMaster,
+    };
+    fifo.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    event = I2cEvent::Stopped;
+    fifo.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    assert_eq!(0x81, fifo.status);
+    assert_eq!(1, fifo.reads);
+}
+
+#[test]
+fn test_snapshot_is_latched_for_whole_transfer() {
+    let mut sensor = Sensor {
+        measurement: 0x1234_5678,
+        ..Default::default()
+    };
+    let mut bus_state = SMBusState::default();
+
+    let mut event = I2cEvent::Initiated {
+        direction: Direction::MasterToSlave,
+    };
+    sensor.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    event = I2cEvent::ReceivedByte { byte: 0x10 };
+    sensor.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    event = I2cEvent::Initiated {
+        direction: Direction::SlaveToMaster,
+    };
+    sensor.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    let mut size = 0;
+    event = I2cEvent::RequestedByte { byte: &mut size };
+    sensor.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    let mut block = [0; 4];
+    for v in block.iter_mut() {
+        sensor.measurement += 0x0101_0101;
+        let mut data = 0;
+        event = I2cEvent::RequestedByte { byte: &mut data };
+        sensor.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+        *v = data;
+    }
+
+    event = I2cEvent::Stopped;
+    sensor.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    assert_eq!(4, size);
+    assert_eq!(0x1234_5678, u32::from_le_bytes(block));
+    assert_eq!(None, sensor.snapshot);
+    assert_eq!(1, sensor.starts);
+    assert_eq!(1, sensor.stops);
+    assert_eq!(0, sensor.aborts);
+}
+
+#[test]
+fn test_snapshot_is_latched_for_process_call() {
+    let mut sensor = Sensor {
+        measurement: 0x1234,
+        ..Default::default()
+    };
+    let mut bus_state = SMBusState::default();
+
+    let mut response = [0; 5];
+    read(&mut sensor, &mut bus_state, &[0x11, 1, 2], &mut response).unwrap();
+    assert_eq!([4, 0x68, 0x24, 0, 0], response);
+    assert_eq!(None, sensor.snapshot);
+}
+
+#[test]
+fn test_read_past_end_of_block_is_not_consumed() {
+    let mut sensor = Sensor::default();
+    let mut bus_state = SMBusState::default();
+
+    let mut data = [0xee; 7];
+    read(&mut sensor, &mut bus_state, &[0x10], &mut data).unwrap();
+    assert_eq!([0xee; 2], data[5..]);
+    assert_eq!(Some(5), sensor.consumed);
+}
+
+#[test]
+fn test_failed_transfer_is_aborted_without_stop() {
+    let mut sensor = Sensor::default();
+    let mut bus_state = SMBusState::default();
+
+    let mut event = I2cEvent::Initiated {
+        direction: Direction::MasterToSlave,
+    };
+    sensor.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    event = I2cEvent::ReceivedByte { byte: 0x11 };
+    sensor.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    event = I2cEvent::Initiated {
+        direction: Direction::SlaveToMaster,
+    };
+    sensor.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    let mut data = 0;
+    event = I2cEvent::RequestedByte { byte: &mut data };
+    let error = sensor.handle_i2c_event(&mut event, &mut bus_state);
+    assert_eq!(Err(SMBusProtocolError::InvalidReadRegister(0x11)), error);
+
+    let mut data = 0;
+    event = I2cEvent::RequestedByte { byte: &mut data };
+    let _ = sensor.handle_i2c_event(&mut event, &mut bus_state);
+
+    event = I2cEvent::Stopped;
+    sensor.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    assert_eq!(1, sensor.starts);
+    assert_eq!(0, sensor.stops);
+    assert_eq!(1, sensor.aborts);
+}
+
+#[test]
+fn test_repeated_start_after_failed_write_does_not_restart() {
+    let mut sensor = Sensor::default();
+    let mut bus_state = SMBusState::default();
+
+    let mut event = I2cEvent::Initiated {
+        direction: Direction::MasterToSlave,
+    };
+    sensor.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    let mut result = Ok(());
+    for byte in [0x10; 36] {
+        event = I2cEvent::ReceivedByte { byte };
+        result = result.and(sensor.handle_i2c_event(&mut event, &mut bus_state));
+    }
+    assert_eq!(Err(SMBusProtocolError::InvalidWriteBound(32)), result);
+
+    event = I2cEvent::Initiated {
+        direction: Direction::SlaveToMaster,
+    };
+    sensor.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    event = I2cEvent::Stopped;
+    sensor.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    assert_eq!(1, sensor.starts);
+    assert_eq!(0, sensor.stops);
+    assert_eq!(1, sensor.aborts);
+}
+
+#[test]
+fn test_unimplemented_protocols_are_rejected() {
+    let mut led = Led::default();
+    let mut bus_state = SMBusState::default();
+
+    let mut event = I2cEvent::Initiated {
+        direction: Direction::MasterToSlave,
+    };
+    led.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    event = I2cEvent::ReceivedByte { byte: 0x01 };
+    led.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    event = I2cEvent::ReceivedByte { byte: 0x80 };
+    led.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    event = I2cEvent::Stopped;
+    led.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+    assert_eq!(0x80, led.brightness);
+
+    event = I2cEvent::Initiated {
+        direction: Direction::MasterToSlave,
+    };
+    led.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    event = I2cEvent::ReceivedByte { byte: 0x01 };
+    led.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    event = I2cEvent::Stopped;
+    let error = led.handle_i2c_event(&mut event, &mut bus_state);
+    assert_eq!(Err(SMBusProtocolError::WriteByteUnsupported), error);
+
+    event = I2cEvent::Initiated {
+        direction: Direction::SlaveToMaster,
+    };
+    led.handle_i2c_event(&mut event, &mut bus_state).unwrap();
+
+    let mut data = 0;
+    event = I2cEvent::RequestedByte { byte: &mut data };
+    let error = led.handle_i2c_event(&mut event, &mut bus_state);
+    assert_eq!(Err(SMBusProtocolError::ReadByteUnsupported), error);
+    assert_eq!(
+        Err(LedError::Unsupported),
+        led.handle_write_word_data(0x01, 0)
+    );
+}
+
+#[test]
+fn test_any_width_policy_allows_word_and_partial_reads() {
+    let mut permissive = Permissive { value: 0xbeef };
+    let mut bus_state = SMBusState::default();
+
+    let mut word = [0; 2];
+    read(&mut permissive, &mut bus_state, &[0x05], &mut word).unwrap();
+    assert_eq!([0xef, 0xbe], word);
+
+    let mut byte = [0; 1];
+    read(&mut permissive, &mut bus_state, &[0x05], &mut byte).unwrap();
+    assert_eq!([0xef], byte);
+
+    let mut long = [0; 3];
+    read(&mut permissive, &mut bus_state, &[0x05], &mut long).unwrap();
+    assert_eq!([0xef, 0xbe, 0x00], long);
+}
+
+#[test]
+fn test_big_endian_word_data() {
+    let mut counter = BigEndianCounter::default();
+    let mut bus_state = SMBusState::default();
+
+    let mut event = I2cEvent::Initiated {
+        direction: Direction::MasterToSlave,
+    };
+    counter
+        .handle_i2c_event(&mut event, &mut bus_state)
+        .unwrap();
+
+    for byte in [0x02, 0x12, 0x34] {
+        event = I2cEvent::ReceivedByte { byte };
+        counter
+            .handle_i2c_event(&mut event, &mut bus_state)
+            .unwrap();
+    }
+
+    event = I2cEvent::Stopped;
+    counter
+        .handle_i2c_event(&mut event, &mut bus_state)
+        .unwrap();
+    assert_eq!(0x1234, counter.value);
+
+    event = I2cEvent::Initiated {
+        direction: Direction::MasterToSlave,
+    };
+    counter
+        .handle_i2c_event(&mut event, &mut bus_state)
+        .unwrap();
+
+    event = I2cEvent::ReceivedByte { byte: 0x02 };
+    counter
+        .handle_i2c_event(&mut event, &mut bus_state)
+        .unwrap();
+
+    event = I2cEvent::Initiated {
+        direction: Direction::SlaveToMaster,
+    };
+    counter
+        .handle_i2c_event(&mut event, &mut bus_state)
+        .unwrap();
+
+    let mut data1 = 0;
+    event = I2cEvent::RequestedByte { byte: &mut data1 };
+    counter
+        .handle_i2c_event(&mut event, &mut bus_state)
+        .unwrap();
+
+    let mut data2 = 0;
+    event = I2cEvent::RequestedByte { byte: &mut data2 };
+    counter
+        .handle_i2c_event(&mut event, &mut bus_state)
+        .unwrap();
+
+    event = I2cEvent::Stopped;
+    counter
+        .handle_i2c_event(&mut event, &mut bus_state)
+        .unwrap();
+
+    assert_eq!(0x12, data1);
+    assert_eq!(0x34, data2);
+}
+
+#[test]
+fn test_wide_address_write_and_read() {
+    let mut eeprom = Eeprom::new();
+    let mut bus_state = SMBusState::default();
+
+    write(&mut eeprom, &mut bus_state, &[0x01, 0x02, 0xaa, 0xbb, 0xcc]).unwrap();
+    assert_eq!([0xaa, 0xbb, 0xcc], eeprom.memory[0x102..0x105]);
+
+    let mut data = [0; 2];
+    read(&mut eeprom, &mut bus_state, &[0x01, 0x03], &mut data).unwrap();
+    assert_eq!([0xbb, 0xcc], data);
+    assert_eq!(Some(0x03), eeprom.latched);
+    assert_eq!(Some(2), eeprom.consumed);
+
+    // Current address read continues where the previous transfer stopped.
+    let mut data = [0; 1];
+    read(&mut eeprom, &mut bus_state, &[], &mut data).unwrap();
+    assert_eq!([0x00], data);
+    assert_eq!(0x106, bus_state.pointer);
+}
+
+#[test]
+fn test_wide_address_errors() {
+    let mut eeprom = Eeprom::new();
+    let mut bus_state = SMBusState::default();
+
+    let result = write(&mut eeprom, &mut bus_state, &[0x01]);
+    assert_eq!(Err(SMBusProtocolError::IncompleteAddress), result);
+
+    let result = write(&mut eeprom, &mut bus_state, &[0x01, 0xff, 0x11, 0x22]);
+    assert_eq!(Err(SMBusProtocolError::InvalidMemoryAddress(0x200)), result);
+    assert_eq!(0x11, eeprom.memory[0x1ff]);
+
+    let mut data = [0; 1];
+    let result = read(&mut eeprom, &mut bus_state, &[0x02, 0x00], &mut data);
+    assert_eq!(Err(SMBusProtocolError::InvalidMemoryAddress(0x200)), result);
+}
+
+#[test]
+fn test_sequential_register_access() {
+    let mut registers = RegisterFile {
+        values: [0; 8],
+        wrap_to: Some(0x00),
+    };
+    let mut bus_state = SMBusState::default();
+
+    write(&mut registers, &mut bus_state, &[0x02, 0x12, 0x34, 0x56]).unwrap();
+    assert_eq!([0, 0, 0x12, 0x34, 0x56, 0, 0, 0], registers.values);
+
+    let mut data = [0; 3];
+    read(&mut registers, &mut bus_state, &[0x03], &mut data).unwrap();
+    assert_eq!([0x34, 0x56, 0x00], data);
+
+    write(&mut registers, &mut bus_state, &[0x06, 0xaa, 0xbb, 0xcc]).unwrap();
+    assert_eq!([0xcc, 0, 0x12, 0x34, 0x56, 0, 0xaa, 0xbb], registers.values);
+
+    let mut data = [0; 3];
+    read(&mut registers, &mut bus_state, &[0x06], &mut data).unwrap();
+    assert_eq!([0xaa, 0xbb, 0xcc], data);
+    assert_eq!(0, registers.values[7]);
+
+    // Reads without a register pointer continue at the current pointer.
+    let mut data = [0; 2];
+    read(&mut registers, &mut bus_state, &[], &mut data).unwrap();
+    assert_eq!([0x00, 0x12], data);
+}
+
+#[test]
+fn test_sequential_access_without_wrap() {
+    let mut registers = RegisterFile {
+        values: [0; 8],
+        wrap_to: None,
+    };
+    let mut bus_state = SMBusState::default();
+
+    let result = write(&mut registers, &mut bus_state, &[0x07, 0x01, 0x02]);
+    assert_eq!(Err(SMBusProtocolError::InvalidWriteBound(1)), result);
+    assert_eq!([0, 0, 0, 0, 0, 0, 0, 0x01], registers.values);
+
+    let mut data = [0; 2];
+    let result = read(&mut registers, &mut bus_state, &[0x07], &mut data);
+    assert_eq!(Err(SMBusProtocolError::InvalidReadBound(1)), result);
+    assert_eq!(0x01, registers.values[7]);
+}
+
+#[test]
+fn test_block_write_pec() {
+    let mut device = Checksummed::default();
+    let mut bus_state = SMBusState::default();
+
+    let mut bytes = [0u8; 35];
+    bytes[0] = 0x30;
+    bytes[1] = 32;
+    for (i, byte) in bytes[2..34].iter_mut().enumerate() {
+        *byte = i as u8;
+    }
+    bytes[34] = pec::update(pec::compute(&[0x54]), &bytes[..34]);
+    write(&mut device, &mut bus_state, &bytes).unwrap();
+    assert_eq!(Some(32), device.block.map(|(_, count)| count));
+
+    device.block = None;
+    bytes[34] ^= 1;
+    let result = write(&mut device, &mut bus_state, &bytes);
+    assert_eq!(Err(SMBusProtocolError::InvalidPec), result);
+    assert_eq!(None, device.block);
+
+    write(&mut device, &mut bus_state, &[0x30, 2, 0xaa, 0x55]).unwrap();
+    assert_eq!(
+        Some(([0xaa, 0x55], 2)),
+        device
+            .block
+            .map(|(data, count)| ([data[0], data[1]], count))
+    );
+
+    device.block = None;
+    let pec = pec::update(pec::compute(&[0x54]), &[0x30, 1, 0xaa]);
+    let result = write(&mut device, &mut bus_state, &[0x30, 1, 0xaa, pec, 0]);
+    assert_eq!(Err(SMBusProtocolError::InvalidWriteBound(1)), result);
+    assert_eq!(None, device.block);
+}
+
+#[test]
+fn test_write_pec() {
+    let mut device = Checksummed::default();
+    let mut bus_state = SMBusState::default();
+    let crc = |bytes: &[u8]| pec::update(pec::compute(&[0x54]), bytes);
+
+    write(&mut device, &mut bus_state, &[0x01, crc(&[0x01])]).unwrap();
+    assert_eq!(Some(0x01), device.command);
+    write(
+        &mut device,
+        &mut bus_state,
+        &[0x02, 0x5a, crc(&[0x02, 0x5a])],
+    )
+    .unwrap();
+    assert_eq!(Some(0x5a), device.byte);
+    let pec = crc(&[0x03, 0x34, 0x12]);
+    write(&mut device, &mut bus_state, &[0x03, 0x34, 0x12, pec]).unwrap();
+    assert_eq!(Some(0x1234), device.word);
+    // 0x04 has no policy.
+    let pec = crc(&[0x04, 0xcd, 0xab]);
+    write(&mut device, &mut bus_state, &[0x04, 0xcd, 0xab, pec]).unwrap();
+    assert_eq!(Some(0xabcd), device.word);
+
+    // Without PEC the writes are accepted unchecked.
+    write(&mut device, &mut bus_state, &[0x02, 0xa5]).unwrap();
+    assert_eq!(Some(0xa5), device.byte);
+    write(&mut device, &mut bus_state, &[0x03, 0x78, 0x56]).unwrap();
+    assert_eq!(Some(0x5678), device.word);
+
+    let result = write(
+        &mut device,
+        &mut bus_state,
+        &[0x02, 0x00, !crc(&[0x02, 0x00])],
+    );
+    assert_eq!(Err(SMBusProtocolError::InvalidPec), result);
+    assert_eq!(Some(0xa5), device.byte);
+    let pec = crc(&[0x03, 0x00, 0x00]);
+    let result = write(&mut device, &mut bus_state, &[0x03, 0x00, 0x00, pec, 0]);
+    assert_eq!(Err(SMBusProtocolError::InvalidAccessWidth(0x03)), result);
+    assert_eq!(Some(0x5678), device.word);
+}
@@ -15,11 +15,11 @@ impl CommandHandler for Data {
     // TODO better errors (snafu or manual).
     type Error = ();
 
-    fn handle_read_byte(&self) -> Option<u8> {
+    fn handle_read_byte(&mut self) -> Option<u8> {
         Some(self.byte_a)
     }
 
-    fn handle_read_byte_data(&self, reg: u8) -> Option<u8> {
+    fn handle_read_byte_data(&mut self, reg: u8) -> Option<u8> {
         match reg {
             1 => Some(self.byte_a),
             2 => Some(self.byte_b),
@@ -28,7 +28,7 @@ impl CommandHandler for Data {
         }
     }
 
-    fn handle_read_word_data(&self, reg: u8) -> Option<u16> {
+    fn handle_read_word_data(&mut self, reg: u8) -> Option<u16> {
         match reg {
             7 => {
                 let data = self.byte_a as u16 | (self.byte_b as u16) << 8;
@@ -42,7 +42,7 @@ impl CommandHandler for Data {
         }
     }
 
-    fn handle_read_block_data(&self, reg: u8, index: u8) -> Option<u8> {
+    fn handle_read_block_data(&mut self, reg: u8, index: u8) -> Option<u8> {
         rprintln!("block read {}", reg);
         match reg {
             11 => match index {